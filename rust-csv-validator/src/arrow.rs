@@ -0,0 +1,511 @@
+use crate::engine::parse_number;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::rules::{day_number, parse_date};
+use crate::Dataset;
+
+// --- Arrow IPC Output ---
+//
+// The valid rows as an Arrow IPC stream (schema, record batches, end marker),
+// which DuckDB-WASM's `insertArrowFromIPCStream` and `apache-arrow`'s
+// `tableFromIPC` read without parsing text again. A column whose rules
+// include `number` becomes Float64 and one with `date` becomes Date32 (days);
+// every other column is Utf8. Blank cells and `null_values` tokens are nulls.
+
+// Rows per record batch (and Parquet row group), keeping each batch's
+// offsets well inside `i32`
+const ARROW_BATCH_ROWS: usize = 64 * 1024;
+
+// `MetadataVersion::V5`
+const METADATA_VERSION: i16 = 4;
+
+// `MessageHeader` union tags
+const HEADER_SCHEMA: u8 = 1;
+const HEADER_RECORD_BATCH: u8 = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum ArrowType {
+    Utf8,
+    Float64,
+    Date32,
+}
+
+impl ArrowType {
+    /// The `Field.type` union tag and table.
+    fn flatbuffer(self) -> (u8, Table) {
+        match self {
+            ArrowType::Utf8 => (5, Table::default()),
+            // `FloatingPoint { precision: DOUBLE }`
+            ArrowType::Float64 => (3, Table::default().field(0, Value::Short(2))),
+            // `Date { unit: DAY }`, written out since the default is MILLISECOND
+            ArrowType::Date32 => (8, Table::default().field(0, Value::Short(0))),
+        }
+    }
+}
+
+/// One column of a record batch as its validity bitmap and value buffers.
+pub(crate) struct ColumnBuffers {
+    pub(crate) kind: ArrowType,
+    pub(crate) validity: Vec<u8>,
+    pub(crate) null_count: usize,
+    // Utf8: `i32` end offsets after a leading 0; otherwise unused
+    pub(crate) offsets: Vec<u8>,
+    // Utf8: the text; Float64: `f64`s; Date32: `i32` days
+    pub(crate) values: Vec<u8>,
+}
+
+impl ColumnBuffers {
+    fn new(kind: ArrowType, rows: usize) -> ColumnBuffers {
+        let offsets = if kind == ArrowType::Utf8 { 0i32.to_le_bytes().to_vec() } else { Vec::new() };
+        ColumnBuffers { kind, validity: vec![0; rows.div_ceil(8)], null_count: 0, offsets, values: Vec::new() }
+    }
+
+    fn push(&mut self, idx: usize, value: Option<&str>) -> Result<(), ValidatorError> {
+        let valid = match self.kind {
+            ArrowType::Utf8 => {
+                self.values.extend_from_slice(value.unwrap_or("").as_bytes());
+                let end = i32::try_from(self.values.len())
+                    .map_err(|_| ValidatorError::new(ErrorKind::Export, "Arrow Export: a batch's text exceeds 2 GiB"))?;
+                self.offsets.extend_from_slice(&end.to_le_bytes());
+                value.is_some()
+            }
+            ArrowType::Float64 => {
+                let num = value.and_then(|v| parse_number(v).ok());
+                self.values.extend_from_slice(&num.unwrap_or(0.0).to_le_bytes());
+                num.is_some()
+            }
+            ArrowType::Date32 => {
+                let days = value.and_then(parse_date).and_then(|date| i32::try_from(day_number(date)).ok());
+                self.values.extend_from_slice(&days.unwrap_or(0).to_le_bytes());
+                days.is_some()
+            }
+        };
+        if valid {
+            self.validity[idx / 8] |= 1 << (idx % 8);
+        } else {
+            self.null_count += 1;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_valid(&self, idx: usize) -> bool {
+        self.validity[idx / 8] & (1 << (idx % 8)) != 0
+    }
+
+    /// The buffers in the order the Arrow columnar format lists them.
+    fn buffers(&self) -> Vec<&[u8]> {
+        match self.kind {
+            ArrowType::Utf8 => vec![&self.validity, &self.offsets, &self.values],
+            ArrowType::Float64 | ArrowType::Date32 => vec![&self.validity, &self.values],
+        }
+    }
+}
+
+impl Dataset {
+    /// The valid rows as an Arrow IPC stream.
+    pub(crate) fn write_arrow(&self, progress: &Progress) -> Result<Vec<u8>, ValidatorError> {
+        let types: Vec<ArrowType> = (0..self.headers.len()).map(|col| self.arrow_type(col)).collect();
+        let mut out = Vec::new();
+        write_message(&mut out, HEADER_SCHEMA, self.arrow_schema(&types), &[]);
+
+        self.valid_batches(progress, |rows| self.write_record_batch(&mut out, &types, rows))?;
+
+        // End-of-stream marker: a continuation with an empty message
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        Ok(out)
+    }
+
+    /// Calls `write` with the indices of each `ARROW_BATCH_ROWS` valid rows
+    /// in turn, the last batch holding the rest.
+    pub(crate) fn valid_batches(&self, progress: &Progress, mut write: impl FnMut(&[usize]) -> Result<(), ValidatorError>) -> Result<(), ValidatorError> {
+        let total = self.records.len();
+        let mut rows = Vec::with_capacity(ARROW_BATCH_ROWS);
+        for idx in 0..total {
+            progress.row(idx, total)?;
+            if self.violations(idx).is_empty() {
+                rows.push(idx);
+            }
+            if rows.len() == ARROW_BATCH_ROWS || (idx + 1 == total && !rows.is_empty()) {
+                write(&rows)?;
+                rows.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// The type of `col`, from its first enabled `number` or `date` rule.
+    pub(crate) fn arrow_type(&self, col: usize) -> ArrowType {
+        self.rule_set.rules_for(col).iter().enumerate()
+            .filter(|&(idx, _)| self.rule_set.is_enabled(col, idx))
+            .find_map(|(_, rule)| match rule.name() {
+                "number" => Some(ArrowType::Float64),
+                "date" => Some(ArrowType::Date32),
+                _ => None,
+            })
+            .unwrap_or(ArrowType::Utf8)
+    }
+
+    fn arrow_schema(&self, types: &[ArrowType]) -> Table {
+        let fields = self.headers.iter().zip(types)
+            .map(|(header, kind)| {
+                let (type_tag, type_table) = kind.flatbuffer();
+                Table::default()
+                    .field(0, Value::String(header.clone()))
+                    .field(1, Value::Bool(true))
+                    .field(2, Value::Byte(type_tag))
+                    .field(3, Value::Table(type_table))
+                    // Arrow C++ (and so DuckDB) rejects a field without a children vector
+                    .field(5, Value::Tables(Vec::new()))
+            })
+            .collect();
+        Table::default().field(1, Value::Tables(fields))
+    }
+
+    /// Column `col` of `rows` converted to `kind`, blank cells and
+    /// `null_values` tokens (and values that don't convert) as nulls.
+    pub(crate) fn column_buffers(&self, col: usize, kind: ArrowType, rows: &[usize]) -> Result<ColumnBuffers, ValidatorError> {
+        let mut column = ColumnBuffers::new(kind, rows.len());
+        for (idx, &row) in rows.iter().enumerate() {
+            let value = self.records.get(row, col);
+            let missing = value.trim().is_empty() || self.rule_set.is_null(value);
+            column.push(idx, (!missing).then_some(value))?;
+        }
+        Ok(column)
+    }
+
+    fn write_record_batch(&self, out: &mut Vec<u8>, types: &[ArrowType], rows: &[usize]) -> Result<(), ValidatorError> {
+        let mut nodes = Vec::with_capacity(types.len());
+        let mut buffers = Vec::new();
+        let mut body = Vec::new();
+        for (col, &kind) in types.iter().enumerate() {
+            let column = self.column_buffers(col, kind, rows)?;
+            nodes.push([rows.len() as i64, column.null_count as i64]);
+            for buffer in column.buffers() {
+                buffers.push([body.len() as i64, buffer.len() as i64]);
+                body.extend_from_slice(buffer);
+                pad_to_8(&mut body);
+            }
+        }
+        let batch = Table::default()
+            .field(0, Value::Long(rows.len() as i64))
+            .field(1, Value::Structs(nodes))
+            .field(2, Value::Structs(buffers));
+        write_message(out, HEADER_RECORD_BATCH, batch, &body);
+        Ok(())
+    }
+}
+
+/// Frames one IPC message: continuation marker, metadata length, the
+/// `Message` flatbuffer and the body, each padded to 8 bytes.
+fn write_message(out: &mut Vec<u8>, header_type: u8, header: Table, body: &[u8]) {
+    let message = Table::default()
+        .field(0, Value::Short(METADATA_VERSION))
+        .field(1, Value::Byte(header_type))
+        .field(2, Value::Table(header))
+        .field(3, Value::Long(body.len() as i64));
+    let mut metadata = FlatBuffer::finish(message);
+    pad_to_8(&mut metadata);
+    out.extend_from_slice(&u32::MAX.to_le_bytes());
+    out.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    out.extend_from_slice(&metadata);
+    out.extend_from_slice(body);
+}
+
+fn pad_to_8(bytes: &mut Vec<u8>) {
+    bytes.resize(bytes.len().next_multiple_of(8), 0);
+}
+
+// --- Flatbuffers ---
+//
+// Just enough of the flatbuffers wire format for Arrow's `Message`: tables of
+// scalars, strings, tables and vectors of 16-byte structs. The buffer is laid
+// out front to back, each table's vtable just before it and its children
+// after it, so every offset points forward as the format requires.
+
+enum Value {
+    Bool(bool),
+    Byte(u8),
+    Short(i16),
+    Long(i64),
+    String(String),
+    Table(Table),
+    Tables(Vec<Table>),
+    // `FieldNode`s and `Buffer`s: two `i64`s each
+    Structs(Vec<[i64; 2]>),
+}
+
+impl Value {
+    /// Size (and alignment) of the value inline in its table.
+    fn inline_size(&self) -> usize {
+        match self {
+            Value::Bool(_) | Value::Byte(_) => 1,
+            Value::Short(_) => 2,
+            Value::Long(_) => 8,
+            Value::String(_) | Value::Table(_) | Value::Tables(_) | Value::Structs(_) => 4,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Table {
+    // (field slot, value)
+    fields: Vec<(usize, Value)>,
+}
+
+impl Table {
+    fn field(mut self, slot: usize, value: Value) -> Table {
+        self.fields.push((slot, value));
+        self
+    }
+}
+
+struct FlatBuffer {
+    buf: Vec<u8>,
+}
+
+impl FlatBuffer {
+    /// `root` as a finished buffer.
+    fn finish(root: Table) -> Vec<u8> {
+        let mut fb = FlatBuffer { buf: vec![0; 4] };
+        let pos = fb.table(&root);
+        fb.patch(0, pos);
+        fb.buf
+    }
+
+    fn align(&mut self, align: usize) {
+        self.buf.resize(self.buf.len().next_multiple_of(align), 0);
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Points the `uoffset` at `at` to `target`.
+    fn patch(&mut self, at: usize, target: usize) {
+        self.buf[at..at + 4].copy_from_slice(&((target - at) as u32).to_le_bytes());
+    }
+
+    fn table(&mut self, table: &Table) -> usize {
+        // Inline layout after the leading vtable offset, largest fields first
+        let mut order: Vec<&(usize, Value)> = table.fields.iter().collect();
+        order.sort_by_key(|(_, value)| std::cmp::Reverse(value.inline_size()));
+        let mut size = 4usize;
+        let mut layout = Vec::with_capacity(order.len());
+        for (slot, value) in order {
+            size = size.next_multiple_of(value.inline_size());
+            layout.push((*slot, value, size));
+            size += value.inline_size();
+        }
+
+        let slots = table.fields.iter().map(|(slot, _)| slot + 1).max().unwrap_or(0);
+        let mut vtable = vec![0u16; 2 + slots];
+        vtable[0] = (4 + 2 * slots) as u16;
+        vtable[1] = size as u16;
+        for &(slot, _, at) in &layout {
+            vtable[2 + slot] = at as u16;
+        }
+        self.align(2);
+        let vtable_pos = self.buf.len();
+        for entry in vtable {
+            self.buf.extend_from_slice(&entry.to_le_bytes());
+        }
+
+        self.align(8);
+        let table_pos = self.buf.len();
+        self.buf.extend_from_slice(&((table_pos - vtable_pos) as i32).to_le_bytes());
+        self.buf.resize(table_pos + size, 0);
+        let mut children = Vec::new();
+        for (_, value, at) in layout {
+            let at = table_pos + at;
+            match value {
+                Value::Bool(v) => self.buf[at] = u8::from(*v),
+                Value::Byte(v) => self.buf[at] = *v,
+                Value::Short(v) => self.buf[at..at + 2].copy_from_slice(&v.to_le_bytes()),
+                Value::Long(v) => self.buf[at..at + 8].copy_from_slice(&v.to_le_bytes()),
+                _ => children.push((at, value)),
+            }
+        }
+        for (at, value) in children {
+            let pos = match value {
+                Value::String(text) => self.string(text),
+                Value::Table(child) => self.table(child),
+                Value::Tables(tables) => self.tables(tables),
+                Value::Structs(structs) => self.structs(structs),
+                _ => unreachable!("scalars are written inline"),
+            };
+            self.patch(at, pos);
+        }
+        table_pos
+    }
+
+    fn string(&mut self, text: &str) -> usize {
+        self.align(4);
+        let pos = self.buf.len();
+        self.put_u32(text.len() as u32);
+        self.buf.extend_from_slice(text.as_bytes());
+        self.buf.push(0);
+        pos
+    }
+
+    fn tables(&mut self, tables: &[Table]) -> usize {
+        self.align(4);
+        let pos = self.buf.len();
+        self.put_u32(tables.len() as u32);
+        let slots = self.buf.len();
+        self.buf.resize(slots + 4 * tables.len(), 0);
+        for (idx, table) in tables.iter().enumerate() {
+            let table_pos = self.table(table);
+            self.patch(slots + 4 * idx, table_pos);
+        }
+        pos
+    }
+
+    fn structs(&mut self, structs: &[[i64; 2]]) -> usize {
+        // The length sits just before the first 8-aligned element
+        self.align(4);
+        if self.buf.len().is_multiple_of(8) {
+            self.put_u32(0);
+        }
+        let pos = self.buf.len();
+        self.put_u32(structs.len() as u32);
+        for pair in structs {
+            for v in pair {
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Validator;
+
+    fn u16_at(b: &[u8], at: usize) -> usize {
+        u16::from_le_bytes([b[at], b[at + 1]]) as usize
+    }
+
+    fn u32_at(b: &[u8], at: usize) -> usize {
+        u32::from_le_bytes(b[at..at + 4].try_into().unwrap()) as usize
+    }
+
+    fn i64_at(b: &[u8], at: usize) -> i64 {
+        i64::from_le_bytes(b[at..at + 8].try_into().unwrap())
+    }
+
+    /// A flatbuffer table, read back field by field.
+    struct Reader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn root(buf: &'a [u8]) -> Reader<'a> {
+            Reader { buf, pos: u32_at(buf, 0) }
+        }
+
+        /// Absolute position of `slot`'s inline value, if set.
+        fn slot(&self, slot: usize) -> Option<usize> {
+            let vtable = self.pos - i32::from_le_bytes(self.buf[self.pos..self.pos + 4].try_into().unwrap()) as usize;
+            let entry = 4 + 2 * slot;
+            let at = if entry < u16_at(self.buf, vtable) { u16_at(self.buf, vtable + entry) } else { 0 };
+            (at != 0).then_some(self.pos + at)
+        }
+
+        /// A non-negative scalar `size` bytes wide; 0 when not set.
+        fn scalar(&self, slot: usize, size: usize) -> i64 {
+            let mut bytes = [0; 8];
+            if let Some(at) = self.slot(slot) {
+                bytes[..size].copy_from_slice(&self.buf[at..at + size]);
+            }
+            i64::from_le_bytes(bytes)
+        }
+
+        fn target(&self, slot: usize) -> usize {
+            let at = self.slot(slot).unwrap();
+            at + u32_at(self.buf, at)
+        }
+
+        fn table(&self, slot: usize) -> Reader<'a> {
+            Reader { buf: self.buf, pos: self.target(slot) }
+        }
+
+        fn string(&self, slot: usize) -> String {
+            let at = self.target(slot);
+            String::from_utf8(self.buf[at + 4..at + 4 + u32_at(self.buf, at)].to_vec()).unwrap()
+        }
+
+        fn tables(&self, slot: usize) -> Vec<Reader<'a>> {
+            let at = self.target(slot);
+            (0..u32_at(self.buf, at))
+                .map(|idx| at + 4 + 4 * idx)
+                .map(|at| Reader { buf: self.buf, pos: at + u32_at(self.buf, at) })
+                .collect()
+        }
+
+        fn structs(&self, slot: usize) -> Vec<(i64, i64)> {
+            let at = self.target(slot);
+            assert_eq!((at + 4) % 8, 0);
+            (0..u32_at(self.buf, at)).map(|idx| (i64_at(self.buf, at + 4 + 16 * idx), i64_at(self.buf, at + 12 + 16 * idx))).collect()
+        }
+    }
+
+    #[test]
+    fn stream_decodes_to_the_schema_and_valid_rows() {
+        let csv = "amount,when,note\n1.5,2024-02-03,kept\nx,2024-02-04,dropped\nNA,2024-02-05,\n-2,1969-12-31,ünï\n";
+        let rules = r#"[{"column": "amount", "rules": [{"type": "number", "allow_empty": true}]}, {"column": "when", "rules": [{"type": "date"}]}]"#;
+        let options = r#"{"validation": {"null_values": ["NA"]}}"#;
+        let bytes = Validator::from_json(csv, rules, Some(options)).unwrap().export_arrow().unwrap();
+
+        let mut messages = Vec::new();
+        let mut pos = 0;
+        loop {
+            assert_eq!(u32_at(&bytes, pos), u32::MAX as usize);
+            let len = u32_at(&bytes, pos + 4);
+            pos += 8;
+            if len == 0 {
+                break;
+            }
+            let message = &bytes[pos..pos + len];
+            let body_len = Reader::root(message).scalar(3, 8) as usize;
+            messages.push((message, &bytes[pos + len..pos + len + body_len]));
+            pos += len + body_len;
+        }
+        assert_eq!(pos, bytes.len());
+        assert_eq!(messages.len(), 2);
+
+        let (schema, body) = messages[0];
+        let schema = Reader::root(schema);
+        assert_eq!((schema.scalar(0, 2), schema.scalar(1, 1)), (4, 1));
+        assert!(body.is_empty());
+        let fields: Vec<(String, i64, i64)> = schema.table(2).tables(1).iter()
+            .map(|field| (field.string(0), field.scalar(2, 1), field.table(3).scalar(0, 2)))
+            .collect();
+        // Float64 (precision DOUBLE), Date32 (unit DAY), Utf8
+        assert_eq!(fields, [("amount".to_string(), 3, 2), ("when".to_string(), 8, 0), ("note".to_string(), 5, 0)]);
+
+        let (batch, body) = messages[1];
+        let batch = Reader::root(batch).table(2);
+        assert_eq!(batch.scalar(0, 8), 3);
+        assert_eq!(batch.structs(1), [(3, 1), (3, 0), (3, 1)]);
+        let buffers: Vec<&[u8]> = batch.structs(2).iter()
+            .map(|&(offset, len)| {
+                assert_eq!(offset % 8, 0);
+                &body[offset as usize..(offset + len) as usize]
+            })
+            .collect();
+        assert_eq!(buffers.len(), 7);
+
+        assert_eq!(buffers[0], [0b101]);
+        let amounts: Vec<f64> = buffers[1].chunks(8).map(|v| f64::from_le_bytes(v.try_into().unwrap())).collect();
+        assert_eq!(amounts, [1.5, 0.0, -2.0]);
+        assert_eq!(buffers[2], [0b111]);
+        let days: Vec<i32> = buffers[3].chunks(4).map(|v| i32::from_le_bytes(v.try_into().unwrap())).collect();
+        assert_eq!(days, [19756, 19758, -1]);
+        assert_eq!(buffers[4], [0b101]);
+        let offsets: Vec<usize> = buffers[5].chunks(4).map(|v| u32::from_le_bytes(v.try_into().unwrap()) as usize).collect();
+        assert_eq!(offsets, [0, 4, 4, 4 + "ünï".len()]);
+        assert_eq!(buffers[6], "keptünï".as_bytes());
+    }
+}
@@ -0,0 +1,75 @@
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::generate::generate;
+use crate::progress::now_ms;
+use crate::{parse_rules, ProcessorOptions, Validator};
+
+// --- Benchmark ---
+//
+// A quick self-test of how fast this device parses and validates, so an app
+// can warn about (or hand off) files that would take too long here.
+
+// Used when the caller has no rules of their own: one of each built-in type
+const BENCHMARK_RULES: &str = r#"[
+    {"column": "id", "rules": [{"type": "notempty"}]},
+    {"column": "full_name", "rules": [{"type": "notempty"}]},
+    {"column": "email", "rules": [{"type": "email"}]},
+    {"column": "age", "rules": [{"type": "number", "min": 0, "max": 120}]},
+    {"column": "code", "rules": [{"type": "regex", "pattern": "^[A-Z]{3}-\\d{4}$"}]},
+    {"column": "status", "rules": [{"type": "oneof", "options": ["active", "inactive", "pending"]}]}
+]"#;
+
+// Share of generated rows that break a rule, so error paths are timed too
+const BENCHMARK_INVALID_FRACTION: f64 = 0.05;
+
+#[derive(Serialize)]
+pub struct BenchmarkResult {
+    pub rows: usize,
+    pub bytes: usize,
+    pub parse_ms: f64,
+    pub validate_ms: f64,
+    /// Rows parsed and validated per second.
+    pub rows_per_second: f64,
+    /// Megabytes (10^6 bytes) parsed and validated per second.
+    pub mb_per_second: f64,
+}
+
+/// Generates `rows` records (see `generate_sample_data`) for `rules_json`,
+/// or a mix of the built-in rule types when it is omitted, then times
+/// loading and validating them with default options. Generation isn't
+/// timed. Use a few hundred thousand rows for a stable figure; the first
+/// call also pays for warming up the engine.
+pub fn benchmark(rows: usize, rules_json: Option<&str>) -> Result<BenchmarkResult, ValidatorError> {
+    let rules = parse_rules(rules_json.unwrap_or(BENCHMARK_RULES))?;
+    let csv_data = String::from_utf8(generate(&rules, rows, 0, BENCHMARK_INVALID_FRACTION)?)
+        .map_err(|e| ValidatorError::new(ErrorKind::Data, e.to_string()))?;
+
+    let started = now_ms();
+    let validator = Validator::new(&csv_data, &rules, ProcessorOptions::default())?;
+    let parsed = now_ms();
+    validator.summary()?;
+    let finished = now_ms();
+
+    // Guards against a zero reading from a coarse clock
+    let seconds = ((finished - started) / 1000.0).max(1e-6);
+    Ok(BenchmarkResult {
+        rows,
+        bytes: csv_data.len(),
+        parse_ms: parsed - started,
+        validate_ms: finished - parsed,
+        rows_per_second: rows as f64 / seconds,
+        mb_per_second: csv_data.len() as f64 / 1e6 / seconds,
+    })
+}
+
+/// `benchmark` for JS.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = "benchmark", unchecked_return_type = "BenchmarkResult")]
+pub fn js_benchmark(rows: usize, rules_json: Option<String>) -> Result<JsValue, JsValue> {
+    let result = benchmark(rows, rules_json.as_deref())?;
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    result.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+}
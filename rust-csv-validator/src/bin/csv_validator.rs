@@ -0,0 +1,166 @@
+//! `csv-validator`: the validation engine as a command-line tool, for CI
+//! pipelines. Prints a summary, optionally writes the valid and invalid rows,
+//! and exits non-zero when the file fails its `Thresholds`, judged as
+//! `CsvProcessor.evaluate` judges them. Without `--thresholds` any error
+//! fails it.
+//!
+//! Exit codes: 0 accepted, 1 rejected, 2 bad arguments or a file that
+//! can't be loaded.
+
+use clap::Parser;
+use rust_csv_validator::{ErrorSummary, ExportOptions, ProcessorOptions, Thresholds, Validator, ValidatorError, Verdict};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "csv-validator", version, about = "Validate a CSV file against a set of column rules")]
+struct Args {
+    /// The CSV file to validate.
+    csv: PathBuf,
+    /// JSON rules file, as passed to `CsvProcessor`.
+    #[arg(short, long)]
+    rules: PathBuf,
+    /// JSON `ProcessorOptions` file.
+    #[arg(long)]
+    options: Option<PathBuf>,
+    /// JSON `ExportOptions` file for the written outputs.
+    #[arg(long)]
+    export_options: Option<PathBuf>,
+    /// Write the valid rows here.
+    #[arg(long)]
+    valid_out: Option<PathBuf>,
+    /// Write the invalid rows, with their errors, here.
+    #[arg(long)]
+    invalid_out: Option<PathBuf>,
+    /// Write one line per error here.
+    #[arg(long)]
+    errors_out: Option<PathBuf>,
+    /// JSON `Thresholds` file, as passed to `evaluate`.
+    #[arg(long)]
+    thresholds: Option<PathBuf>,
+    /// Errors allowed before failing; sets the thresholds' `max_errors`.
+    #[arg(long)]
+    allowed_errors: Option<usize>,
+    /// Share of invalid rows allowed before failing, in percent; sets the
+    /// thresholds' `max_invalid_fraction`.
+    #[arg(long)]
+    allowed_invalid_percent: Option<f64>,
+    /// Print the summary as JSON instead of text.
+    #[arg(long)]
+    json: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(message) => {
+            eprintln!("csv-validator: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Validates and writes the outputs; `Ok(false)` when the file is rejected.
+fn run(args: &Args) -> Result<bool, String> {
+    let thresholds = thresholds(args)?;
+    let rules = read(&args.rules)?;
+    let options: ProcessorOptions = match &args.options {
+        Some(path) => serde_json::from_str(&read(path)?).map_err(|e| format!("Invalid Options JSON: {}", e))?,
+        None => ProcessorOptions::default(),
+    };
+    let export_options = match &args.export_options {
+        Some(path) => ExportOptions::from_json(Some(read(path)?)).map_err(|e| e.to_string())?,
+        None => ExportOptions::default(),
+    };
+    let bytes = std::fs::read(&args.csv).map_err(|e| format!("{}: {}", args.csv.display(), e))?;
+    let rules = serde_json::from_str::<Vec<_>>(&rules).map_err(|e| format!("Invalid Rules JSON: {}", e))?;
+    let validator = Validator::from_bytes(&bytes, &rules, options).map_err(|e| describe(&args.csv, &e))?;
+
+    let summary = validator.summary().map_err(|e| e.to_string())?;
+    let verdict = thresholds.verdict(&summary, validator.headers()).map_err(|e| e.to_string())?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?);
+    } else {
+        print_summary(&summary, &verdict);
+    }
+
+    if args.valid_out.is_some() || args.invalid_out.is_some() || args.errors_out.is_some() {
+        let output = validator.export_split(&export_options).map_err(|e| e.to_string())?;
+        write(args.valid_out.as_deref(), &output.valid)?;
+        write(args.invalid_out.as_deref(), &output.invalid)?;
+        if let Some(path) = &args.errors_out {
+            let errors = match output.errors {
+                Some(errors) => errors,
+                None => validator.export_errors(&export_options).map_err(|e| e.to_string())?,
+            };
+            write(Some(path), &errors)?;
+        }
+    }
+
+    Ok(verdict.accepted)
+}
+
+/// The `--thresholds` file with the `--allowed-*` flags applied over it.
+fn thresholds(args: &Args) -> Result<Thresholds, String> {
+    let mut thresholds = match &args.thresholds {
+        Some(path) => Thresholds::from_json(&read(path)?).map_err(|e| e.to_string())?,
+        None => Thresholds { max_errors: Some(0), ..Thresholds::default() },
+    };
+    if let Some(max) = args.allowed_errors {
+        thresholds.max_errors = Some(max);
+    }
+    if let Some(percent) = args.allowed_invalid_percent {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err("--allowed-invalid-percent must be between 0 and 100".to_string());
+        }
+        thresholds.max_invalid_fraction = Some(percent / 100.0);
+    }
+    Ok(thresholds)
+}
+
+fn print_summary(summary: &ErrorSummary, verdict: &Verdict) {
+    println!("{} rows, {} invalid, {} errors", summary.row_count, summary.invalid_rows, summary.total_errors);
+    let mut columns: Vec<_> = summary.stats.iter().collect();
+    columns.sort_by(|a, b| a.0.cmp(b.0));
+    for (column, counts) in columns {
+        let mut counts: Vec<_> = counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (error, count) in counts {
+            println!("  {}: {} x{}", column, error, count);
+        }
+    }
+    if summary.truncated {
+        println!("  (stopped early at max_errors)");
+    }
+    for warning in &summary.warnings {
+        println!("warning: {}", warning);
+    }
+    for check in verdict.checks.iter().filter(|check| !check.passed) {
+        let check_name = match &check.column {
+            Some(column) => format!("{} ({})", check.check, column),
+            None => check.check.to_string(),
+        };
+        println!("failed: {}: {} (limit {})", check_name, check.actual, check.limit);
+    }
+}
+
+fn describe(path: &Path, error: &ValidatorError) -> String {
+    match (error.line, &error.snippet) {
+        (Some(line), Some(snippet)) => format!("{}:{}: {}\n  {}", path.display(), line, error, snippet),
+        (Some(line), None) => format!("{}:{}: {}", path.display(), line, error),
+        _ => format!("{}: {}", path.display(), error),
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn write(path: Option<&Path>, bytes: &[u8]) -> Result<(), String> {
+    match path {
+        Some(path) => std::fs::write(path, bytes).map_err(|e| format!("{}: {}", path.display(), e)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,110 @@
+// --- BLAKE3 ---
+//
+// One-shot BLAKE3 with the default 32-byte output, for `hash_column`. Follows
+// the reference implementation: 1 KiB chunks hashed block by block, then
+// merged pairwise into a left-balanced tree. No keyed or derive-key modes.
+
+const IV: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+
+const CHUNK_START: u32 = 1;
+const CHUNK_END: u32 = 2;
+const PARENT: u32 = 4;
+const ROOT: u32 = 8;
+
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, mx: u32, my: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(mx);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(my);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn compress(cv: &[u32; 8], block: &[u32; 16], counter: u64, block_len: u32, flags: u32) -> [u32; 8] {
+    let mut state = [
+        cv[0], cv[1], cv[2], cv[3], cv[4], cv[5], cv[6], cv[7],
+        IV[0], IV[1], IV[2], IV[3], counter as u32, (counter >> 32) as u32, block_len, flags,
+    ];
+    let mut m = *block;
+    for round in 0..7 {
+        // Columns, then diagonals
+        g(&mut state, 0, 4, 8, 12, m[0], m[1]);
+        g(&mut state, 1, 5, 9, 13, m[2], m[3]);
+        g(&mut state, 2, 6, 10, 14, m[4], m[5]);
+        g(&mut state, 3, 7, 11, 15, m[6], m[7]);
+        g(&mut state, 0, 5, 10, 15, m[8], m[9]);
+        g(&mut state, 1, 6, 11, 12, m[10], m[11]);
+        g(&mut state, 2, 7, 8, 13, m[12], m[13]);
+        g(&mut state, 3, 4, 9, 14, m[14], m[15]);
+        if round < 6 {
+            m = MSG_PERMUTATION.map(|i| m[i]);
+        }
+    }
+    // Only the first half of the output is needed for a 32-byte hash
+    let mut out = [0; 8];
+    for (i, word) in out.iter_mut().enumerate() {
+        *word = state[i] ^ state[i + 8];
+    }
+    out
+}
+
+fn block_words(bytes: &[u8]) -> [u32; 16] {
+    let mut padded = [0u8; BLOCK_LEN];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    let mut words = [0; 16];
+    for (word, chunk) in words.iter_mut().zip(padded.chunks_exact(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    words
+}
+
+/// Chaining value of one chunk (at most `CHUNK_LEN` bytes, possibly empty).
+fn chunk_cv(chunk: &[u8], counter: u64, root: u32) -> [u32; 8] {
+    let blocks: Vec<&[u8]> = if chunk.is_empty() { vec![chunk] } else { chunk.chunks(BLOCK_LEN).collect() };
+    let last = blocks.len() - 1;
+    let mut cv = IV;
+    for (i, block) in blocks.into_iter().enumerate() {
+        let mut flags = 0;
+        if i == 0 {
+            flags |= CHUNK_START;
+        }
+        if i == last {
+            flags |= CHUNK_END | root;
+        }
+        cv = compress(&cv, &block_words(block), counter, block.len() as u32, flags);
+    }
+    cv
+}
+
+/// Chaining value of the subtree over `data`, whose first chunk is number `counter`.
+fn subtree_cv(data: &[u8], counter: u64, root: u32) -> [u32; 8] {
+    if data.len() <= CHUNK_LEN {
+        return chunk_cv(data, counter, root);
+    }
+    // The left subtree takes the largest power-of-two number of whole chunks
+    // that leaves at least one byte on the right
+    let chunks = data.len().div_ceil(CHUNK_LEN);
+    let left_chunks = 1 << (usize::BITS - 1 - (chunks - 1).leading_zeros());
+    let (left, right) = data.split_at(left_chunks * CHUNK_LEN);
+    let left_cv = subtree_cv(left, counter, 0);
+    let right_cv = subtree_cv(right, counter + left_chunks as u64, 0);
+
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&left_cv);
+    block[8..].copy_from_slice(&right_cv);
+    compress(&IV, &block, 0, BLOCK_LEN as u32, PARENT | root)
+}
+
+/// BLAKE3 of `data` as lowercase hex.
+pub fn blake3_hex(data: &[u8]) -> String {
+    subtree_cv(data, 0, ROOT).iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
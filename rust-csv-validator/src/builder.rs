@@ -0,0 +1,123 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::{ErrorKind, ValidatorError};
+#[cfg(feature = "wasm")]
+use crate::CsvProcessor;
+use crate::{parse_options, parse_rules, ColumnRule, Limits, ProcessorOptions, Validator};
+
+// --- Builder ---
+//
+// Named steps instead of positional JSON strings, so adding an option doesn't
+// mean another constructor argument:
+//
+//     new CsvProcessorBuilder().rules(rulesJson).encoding("utf-8-lossy").build(bytes)
+//
+// Each step consumes the builder and returns it, as wasm-bindgen passes
+// `self` by value. From Rust, `build_validator` loads a `Validator` instead.
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Default)]
+pub struct CsvProcessorBuilder {
+    rules: Option<Vec<ColumnRule>>,
+    options: ProcessorOptions,
+    // Set by `encoding` and `limits`, and kept whatever `parser_options` says
+    lossy_utf8: Option<bool>,
+    limits: Option<Limits>,
+    #[cfg(feature = "wasm")]
+    on_progress: Option<js_sys::Function>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CsvProcessorBuilder {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> CsvProcessorBuilder {
+        CsvProcessorBuilder::default()
+    }
+
+    /// The rules JSON, as passed to the constructor. Required.
+    pub fn rules(mut self, rules_json: &str) -> Result<CsvProcessorBuilder, ValidatorError> {
+        self.rules = Some(parse_rules(rules_json)?);
+        Ok(self)
+    }
+
+    /// A `ProcessorOptions` JSON object, as passed to `with_options`.
+    pub fn parser_options(mut self, options_json: &str) -> Result<CsvProcessorBuilder, ValidatorError> {
+        self.options = parse_options(options_json)?;
+        Ok(self)
+    }
+
+    /// How `build` decodes bytes: `utf-8` (default; invalid input is
+    /// refused) or `utf-8-lossy` (see `ProcessorOptions::lossy_utf8`).
+    pub fn encoding(mut self, encoding: &str) -> Result<CsvProcessorBuilder, ValidatorError> {
+        self.lossy_utf8 = Some(match encoding.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => false,
+            "utf-8-lossy" | "utf8-lossy" => true,
+            _ => {
+                let message = format!("Invalid Encoding: '{}' (expected utf-8 or utf-8-lossy)", encoding);
+                return Err(ValidatorError::new(ErrorKind::Options, message));
+            }
+        });
+        Ok(self)
+    }
+
+    /// A `Limits` JSON object, e.g. `{"max_bytes": 104857600, "max_rows": 1000000}`.
+    pub fn limits(mut self, limits_json: &str) -> Result<CsvProcessorBuilder, ValidatorError> {
+        let limits = serde_json::from_str(limits_json)
+            .map_err(|e| ValidatorError::new(ErrorKind::Options, format!("Invalid Limits JSON: {}", e)))?;
+        self.limits = Some(limits);
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl CsvProcessorBuilder {
+    /// The `on_progress(percent, phase)` callback, kept for later calls.
+    pub fn on_progress(mut self, on_progress: js_sys::Function) -> CsvProcessorBuilder {
+        self.on_progress = Some(on_progress);
+        self
+    }
+
+    /// Loads `data`, a string or the raw file bytes (a `Uint8Array`).
+    pub fn build(mut self, data: JsValue) -> Result<CsvProcessor, JsValue> {
+        let on_progress = self.on_progress.take();
+        let (rules, options) = self.into_parts()?;
+        match data.as_string() {
+            Some(csv_data) => CsvProcessor::load(&csv_data, rules, options, on_progress),
+            None if data.is_instance_of::<js_sys::Uint8Array>() => {
+                let bytes = js_sys::Uint8Array::from(data).to_vec();
+                CsvProcessor::load_bytes(&bytes, rules, options, on_progress)
+            }
+            None => Err(ValidatorError::new(ErrorKind::Options, "Invalid Data: expected a string or a Uint8Array").into()),
+        }
+    }
+}
+
+impl CsvProcessorBuilder {
+    /// Loads `csv_data` into a `Validator`.
+    pub fn build_validator(self, csv_data: &str) -> Result<Validator, ValidatorError> {
+        let (rules, options) = self.into_parts()?;
+        Validator::new(csv_data, &rules, options)
+    }
+
+    /// Like `build_validator`, for the raw file bytes.
+    pub fn build_validator_from_bytes(self, bytes: &[u8]) -> Result<Validator, ValidatorError> {
+        let (rules, options) = self.into_parts()?;
+        Validator::from_bytes(bytes, &rules, options)
+    }
+
+    /// The rules and the options with every step applied.
+    fn into_parts(self) -> Result<(Vec<ColumnRule>, ProcessorOptions), ValidatorError> {
+        let rules = self.rules
+            .ok_or_else(|| ValidatorError::new(ErrorKind::Rules, "Invalid Rules: no rules given to the builder"))?;
+        let mut options = self.options;
+        if let Some(lossy_utf8) = self.lossy_utf8 {
+            options.lossy_utf8 = lossy_utf8;
+        }
+        if let Some(limits) = self.limits {
+            options.limits = limits;
+        }
+        Ok((rules, options))
+    }
+}
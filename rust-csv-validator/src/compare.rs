@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::store::RecordStore;
+use crate::{csv_reader, read_body, read_headers, Dataset, ProcessorOptions};
+
+// --- Comparing Files ---
+//
+// Rows are matched by a key column. Only columns present in both files are
+// compared; columns on one side only are listed instead.
+
+/// What changed between an earlier file and the loaded data.
+#[derive(Serialize)]
+pub struct CompareReport<'a> {
+    pub key_column: &'a str,
+    /// Rows whose key only the loaded data has.
+    pub added: Vec<RowRef<'a>>,
+    /// Rows whose key only the earlier file has, with their values.
+    pub removed: Vec<RemovedRow>,
+    pub changed: Vec<ChangedRow<'a>>,
+    pub unchanged: usize,
+    /// Headers only the loaded data has.
+    pub columns_added: Vec<&'a str>,
+    /// Headers only the earlier file has.
+    pub columns_removed: Vec<String>,
+    /// Keys found more than once in either file; only the first row with
+    /// each key is compared.
+    pub duplicate_keys: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RowRef<'a> {
+    pub key: &'a str,
+    /// Current index in the loaded data.
+    pub row: usize,
+    /// 1-based row number as loaded.
+    pub row_number: usize,
+}
+
+#[derive(Serialize)]
+pub struct RemovedRow {
+    pub key: String,
+    /// 1-based row number in the earlier file.
+    pub row_number: usize,
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct ChangedRow<'a> {
+    #[serde(flatten)]
+    pub row: RowRef<'a>,
+    pub cells: Vec<CellChange<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct CellChange<'a> {
+    pub column: &'a str,
+    pub previous: String,
+    pub current: &'a str,
+}
+
+impl Dataset {
+    /// Compares `previous_csv` (e.g. last month's file) with the loaded rows,
+    /// matching rows on `key_column`. Its header is read with the same
+    /// options as the loaded one.
+    pub(crate) fn compare<'a>(&'a self, previous_csv: &str, key_column: &'a str, options: &ProcessorOptions, progress: &Progress) -> Result<CompareReport<'a>, ValidatorError> {
+        let key = self.column_index(key_column)?;
+
+        let mut reader = csv_reader(previous_csv, options);
+        let previous_headers = read_headers(&mut reader, options).map_err(|e| e.with_snippet(previous_csv))?;
+        let previous_key = previous_headers.iter().position(|h| h == key_column)
+            .ok_or_else(|| ValidatorError::new(ErrorKind::Header, format!("Unknown Compare Key: '{}' is not a column of the other file", key_column)))?;
+        let mut previous = RecordStore::new(previous_headers.len());
+        read_body(&mut reader, previous_csv.len(), &mut previous, None, options, progress)
+            .map_err(|e| e.with_snippet(previous_csv))?;
+
+        let mut duplicate_keys = Vec::new();
+        let mut previous_rows: HashMap<&str, usize> = HashMap::with_capacity(previous.len());
+        for (row, value) in previous.column(previous_key).enumerate() {
+            match previous_rows.entry(value) {
+                Entry::Occupied(_) => duplicate_keys.push(value.to_string()),
+                Entry::Vacant(entry) => {
+                    entry.insert(row);
+                }
+            }
+        }
+
+        // (column here, column in the earlier file) for every shared header
+        let shared: Vec<(usize, usize)> = self.headers.iter().enumerate()
+            .filter_map(|(col, h)| previous_headers.iter().position(|p| p == h).map(|prev| (col, prev)))
+            .collect();
+
+        let mut report = CompareReport {
+            key_column,
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            unchanged: 0,
+            columns_added: self.headers.iter().filter(|h| !previous_headers.contains(h)).map(|h| h.as_str()).collect(),
+            columns_removed: previous_headers.iter().filter(|h| !self.headers.contains(h)).cloned().collect(),
+            duplicate_keys: Vec::new(),
+        };
+
+        let total = self.records.len();
+        let mut seen: HashSet<&str> = HashSet::with_capacity(total);
+        for (idx, record) in self.records.rows().enumerate() {
+            progress.row(idx, total)?;
+            let value = record.get(key);
+            if !seen.insert(value) {
+                duplicate_keys.push(value.to_string());
+                continue;
+            }
+            let row = RowRef { key: value, row: idx, row_number: self.row_number(idx) };
+            let Some(&prev) = previous_rows.get(value) else {
+                report.added.push(row);
+                continue;
+            };
+            let cells: Vec<CellChange> = shared.iter()
+                .filter(|&&(col, prev_col)| record.get(col) != previous.get(prev, prev_col))
+                .map(|&(col, prev_col)| CellChange {
+                    column: &self.headers[col],
+                    previous: previous.get(prev, prev_col).to_string(),
+                    current: record.get(col),
+                })
+                .collect();
+            if cells.is_empty() {
+                report.unchanged += 1;
+            } else {
+                report.changed.push(ChangedRow { row, cells });
+            }
+        }
+
+        let mut removed: Vec<usize> = previous_rows.iter().filter(|(k, _)| !seen.contains(*k)).map(|(_, &row)| row).collect();
+        removed.sort_unstable();
+        report.removed = removed.into_iter()
+            .map(|row| RemovedRow {
+                key: previous.get(row, previous_key).to_string(),
+                row_number: row + 1,
+                values: previous_headers.iter().cloned().zip(previous.row(row).iter().map(|v| v.to_string())).collect(),
+            })
+            .collect();
+
+        duplicate_keys.sort_unstable();
+        duplicate_keys.dedup();
+        report.duplicate_keys = duplicate_keys;
+        Ok(report)
+    }
+}
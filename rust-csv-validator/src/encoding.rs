@@ -0,0 +1,69 @@
+use serde::Serialize;
+use std::borrow::Cow;
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::{Dataset, ErrorSummary};
+
+// --- Input Encoding ---
+//
+// `from_bytes` input must be UTF-8. With `lossy_utf8` set, invalid bytes are
+// replaced with U+FFFD instead of refusing the file, and every cell still
+// holding one is reported as an "Encoding" issue until it is fixed.
+
+const REPLACEMENT: char = char::REPLACEMENT_CHARACTER;
+
+/// A cell that held invalid UTF-8 at load.
+#[derive(Serialize)]
+pub struct EncodingIssue {
+    /// Current index of the row.
+    pub row: usize,
+    /// 1-based row number as loaded.
+    pub row_number: usize,
+    pub column: String,
+}
+
+/// Decodes `bytes`, returning the text and whether anything was replaced.
+pub fn decode(bytes: &[u8], lossy: bool) -> Result<(Cow<'_, str>, bool), ValidatorError> {
+    if lossy {
+        let text = String::from_utf8_lossy(bytes);
+        let replaced = matches!(text, Cow::Owned(_));
+        return Ok((text, replaced));
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok((Cow::Borrowed(text), false)),
+        Err(e) => {
+            let offset = e.valid_up_to();
+            let before = &bytes[..offset];
+            let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+            let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |at| at + 1);
+            let message = format!("Encoding Error: invalid UTF-8 at byte {} (line {})", offset, line);
+            Err(ValidatorError::new(ErrorKind::Parse, message).at_line(line_start, line))
+        }
+    }
+}
+
+impl Dataset {
+    /// Adds every cell still holding a replacement character to the summary,
+    /// if the file was decoded lossily.
+    pub(crate) fn report_encoding(&self, summary: &mut ErrorSummary) {
+        if !self.lossy_decoded {
+            return;
+        }
+        let mut rows = Vec::new();
+        for (col, header) in self.headers.iter().enumerate() {
+            for (idx, value) in self.records.column(col).enumerate() {
+                if value.contains(REPLACEMENT) {
+                    summary.record(header, "Encoding", value);
+                    summary.encoding_issues.push(EncodingIssue { row: idx, row_number: self.row_number(idx), column: header.clone() });
+                    // Cells and rows failing a rule were counted already
+                    summary.invalid_cells += usize::from(self.rule_set.validate_cell(col, value, &self.records.row(idx)).next().is_none());
+                    rows.push(idx);
+                }
+            }
+        }
+        rows.sort_unstable();
+        rows.dedup();
+        summary.invalid_rows += rows.into_iter().filter(|&idx| self.violations(idx).is_empty()).count();
+        summary.encoding_issues.sort_by_key(|issue| issue.row);
+    }
+}
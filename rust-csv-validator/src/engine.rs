@@ -0,0 +1,433 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::mapping::HeaderMatching;
+use crate::store::RecordStore;
+use crate::{ColumnRule, RuleType, ValidationOptions};
+
+// --- Validation Engine ---
+//
+// Every public method that needs to know whether data is valid goes through
+// `RuleSet::validate_cell` / `RuleSet::validate_record`, with the record the
+// cell is in. Rules are compiled through the `RuleRegistry`, keyed by their
+// JSON `type` tag, so a new rule type only needs a `Rule` impl and a
+// `register` call (see `rules.rs` for the built-in ones).
+
+/// A compiled rule, checking one cell at a time. Implement it (and register
+/// a factory with `register_rule`) to add a rule type.
+pub trait Rule: Send + Sync {
+    /// The rule's `type` tag in the rules JSON.
+    fn name(&self) -> &'static str;
+
+    /// Returns the error type if `value` fails this rule, e.g.
+    /// "Invalid Email". `error_code` maps unknown error types to "INVALID".
+    fn check(&self, value: &str) -> Option<&'static str>;
+
+    /// Whether the rule checks that a cell is filled in, like `notempty`.
+    /// Only such rules see cells holding one of the `null_values` (as an
+    /// empty cell); the others skip them.
+    fn checks_presence(&self) -> bool {
+        false
+    }
+
+    /// For rules that read other cells of the record, like `dependson`: a
+    /// copy that knows where they are, given the rule's own `column` and
+    /// `find`, which gives the position of a column name as
+    /// `header_matching` matches it. `RuleSet` binds every rule once the
+    /// headers are known.
+    fn bind(&self, _column: usize, _find: &dyn Fn(&str) -> Option<usize>) -> Result<Option<CompiledRule>, String> {
+        Ok(None)
+    }
+
+    /// Like `check`, with the `record` the cell belongs to. Rules that read
+    /// other cells (see `bind`) implement this instead of `check`.
+    fn check_record(&self, value: &str, _record: &dyn Record) -> Option<&'static str> {
+        self.check(value)
+    }
+
+    /// For rules that judge a cell against the rest of the data, like
+    /// `anomaly`: a copy fitted to the `rows` records loaded, whose cells
+    /// `cell(row, column)` reads. `column` is the rule's own. `RuleSet::fit`
+    /// swaps it in once the data is there.
+    fn fit<'a>(&self, _column: usize, _rows: usize, _cell: &dyn Fn(usize, usize) -> &'a str) -> Option<CompiledRule> {
+        None
+    }
+
+    /// Whether a fitted rule is fitted again whenever the cells it read
+    /// change, like `dependson`, so fixing one cell can clear the others.
+    /// The rest keep what they learnt until the rules change.
+    fn follows_edits(&self) -> bool {
+        false
+    }
+}
+
+/// The cells of the record being validated, by header position. Rules see
+/// null tokens as "".
+pub trait Record {
+    /// The cell of `column`, or "" when there is none.
+    fn cell(&self, column: usize) -> &str;
+}
+
+impl Record for Vec<&str> {
+    fn cell(&self, column: usize) -> &str {
+        self.get(column).copied().unwrap_or("")
+    }
+}
+
+impl Record for Vec<String> {
+    fn cell(&self, column: usize) -> &str {
+        self.get(column).map_or("", String::as_str)
+    }
+}
+
+// What rules see of a record: its cells with null tokens blanked
+struct Blanked<'a> {
+    record: &'a dyn Record,
+    rule_set: &'a RuleSet,
+}
+
+impl Record for Blanked<'_> {
+    fn cell(&self, column: usize) -> &str {
+        let value = self.record.cell(column);
+        if self.rule_set.is_null(value) { "" } else { value }
+    }
+}
+
+/// A rule ready for evaluation, with any pattern it needs compiled once up front.
+pub type CompiledRule = Arc<dyn Rule>;
+
+/// Builds a rule from its JSON object (including the `type` key), or
+/// explains what is wrong with it.
+type RuleFactory = Arc<dyn Fn(&Value) -> Result<CompiledRule, String> + Send + Sync>;
+
+/// Rule factories by `type` tag.
+pub(crate) struct RuleRegistry {
+    factories: HashMap<String, RuleFactory>,
+}
+
+impl RuleRegistry {
+    /// A registry holding the built-in rule types.
+    pub fn new() -> RuleRegistry {
+        let mut registry = RuleRegistry { factories: HashMap::new() };
+        crate::rules::register_builtins(&mut registry);
+        registry
+    }
+
+    /// Adds the rule type `tag`, replacing any earlier one (built-ins
+    /// included).
+    pub fn register<F>(&mut self, tag: &str, factory: F)
+    where
+        F: Fn(&Value) -> Result<CompiledRule, String> + Send + Sync + 'static,
+    {
+        self.factories.insert(tag.to_string(), Arc::new(factory));
+    }
+
+    pub fn compile(&self, rule: &RuleType) -> Result<CompiledRule, String> {
+        let params = serde_json::to_value(rule).map_err(|e| e.to_string())?;
+        let tag = params.get("type").and_then(Value::as_str).unwrap_or_default();
+        let factory = self.factories.get(tag).ok_or_else(|| format!("unknown rule type '{}'", tag))?;
+        factory(&params)
+    }
+}
+
+fn registry() -> &'static RwLock<RuleRegistry> {
+    static REGISTRY: OnceLock<RwLock<RuleRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(RuleRegistry::new()))
+}
+
+/// Makes the rule type `tag` available to every rule set compiled from now
+/// on, e.g. `{"type": "iban"}` in the rules JSON. Rules that name a type
+/// nobody registered fail to compile.
+pub fn register_rule<F>(tag: &str, factory: F)
+where
+    F: Fn(&Value) -> Result<CompiledRule, String> + Send + Sync + 'static,
+{
+    registry().write().unwrap_or_else(|e| e.into_inner()).register(tag, factory);
+}
+
+/// Hot-path float parser; accepts the same syntax as `str::parse::<f64>`.
+pub fn parse_number(value: &str) -> Result<f64, fast_float2::Error> {
+    fast_float2::parse(value)
+}
+
+/// Stable machine-readable code for an error type returned by
+/// `Rule::check`, e.g. "Not a Number" -> "NOT_A_NUMBER".
+pub fn error_code(error_type: &str) -> &'static str {
+    match error_type {
+        "Required" => "REQUIRED",
+        "Min Value" => "MIN_VALUE",
+        "Max Value" => "MAX_VALUE",
+        "Not a Number" => "NOT_A_NUMBER",
+        "Not a Percentage" => "NOT_A_PERCENTAGE",
+        "Invalid Email" => "INVALID_EMAIL",
+        "Pattern Mismatch" => "PATTERN_MISMATCH",
+        "Invalid Option" => "INVALID_OPTION",
+        "Invalid Coordinate" => "INVALID_COORDINATE",
+        "Coordinate Out of Range" => "COORDINATE_OUT_OF_RANGE",
+        "Invalid Country Code" => "INVALID_COUNTRY_CODE",
+        "Invalid Language Tag" => "INVALID_LANGUAGE_TAG",
+        "Unknown Time Zone" => "UNKNOWN_TIME_ZONE",
+        "Invalid Date" => "INVALID_DATE",
+        "Not in the Past" => "DATE_NOT_PAST",
+        "Not in the Future" => "DATE_NOT_FUTURE",
+        "Date Too Early" => "DATE_TOO_EARLY",
+        "Date Too Late" => "DATE_TOO_LATE",
+        "Invalid Duration" => "INVALID_DURATION",
+        "Invalid Version" => "INVALID_VERSION",
+        "Invalid Hex Color" => "INVALID_HEX_COLOR",
+        "Invalid MAC Address" => "INVALID_MAC_ADDRESS",
+        "Invalid Base64" => "INVALID_BASE64",
+        "Invalid Hostname" => "INVALID_HOSTNAME",
+        "Invalid Path" => "INVALID_PATH",
+        "Path Not Absolute" => "PATH_NOT_ABSOLUTE",
+        "Extension Not Allowed" => "EXTENSION_NOT_ALLOWED",
+        "Invalid Postal Code" => "INVALID_POSTAL_CODE",
+        "Invalid National ID" => "INVALID_NATIONAL_ID",
+        "Statistical Outlier" => "STATISTICAL_OUTLIER",
+        "Dependency Conflict" => "DEPENDENCY_CONFLICT",
+        "Unvalidated Column" => "UNVALIDATED_COLUMN",
+        "Encoding" => "ENCODING",
+        "Type Mismatch" => "TYPE_MISMATCH",
+        _ => "INVALID",
+    }
+}
+
+fn compile_rules(registry: &RuleRegistry, rule: &ColumnRule) -> Result<Vec<CompiledRule>, String> {
+    rule.rules.iter()
+        .map(|r| registry.compile(r))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid Rule for column '{}': {}", rule.column, e))
+}
+
+/// A single rule failure on a single cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// Index of the column in the headers.
+    pub column: usize,
+    /// Index of the failed rule within that column's rule list.
+    pub rule: usize,
+    pub error_type: &'static str,
+}
+
+/// Compiled rules resolved against the header positions of a dataset.
+#[derive(Clone)]
+pub struct RuleSet {
+    // column index -> rules (empty when the column has none)
+    columns: Vec<Vec<CompiledRule>>,
+    // column index -> indices of its rules that are switched off
+    disabled: Vec<Vec<usize>>,
+    // column index -> the other columns its rules read (see `Rule::bind`)
+    reads: Vec<Vec<usize>>,
+    // for binding rules set later
+    headers: Vec<String>,
+    matching: HeaderMatching,
+    // stop at a cell's first failing rule
+    short_circuit: bool,
+    // tokens standing for a missing value
+    null_values: Vec<String>,
+}
+
+impl RuleSet {
+    pub fn new(headers: &[String], rules: &[ColumnRule], matching: &HeaderMatching) -> Result<RuleSet, String> {
+        let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+        let mut rule_set = RuleSet {
+            columns: vec![Vec::new(); headers.len()],
+            disabled: vec![Vec::new(); headers.len()],
+            reads: vec![Vec::new(); headers.len()],
+            short_circuit: false,
+            null_values: Vec::new(),
+            headers: headers.to_vec(),
+            matching: matching.clone(),
+        };
+        for r in rules {
+            let compiled = compile_rules(&registry, r)?;
+            for (idx, header) in headers.iter().enumerate() {
+                if matching.matches(header, &r.column) {
+                    rule_set.set_rules(idx, compiled.clone(), r.disabled.clone())
+                        .map_err(|e| format!("Invalid Rule for column '{}': {}", r.column, e))?;
+                }
+            }
+        }
+        Ok(rule_set)
+    }
+
+    pub fn rules_for(&self, column: usize) -> &[CompiledRule] {
+        self.columns.get(column).map_or(&[], |r| r.as_slice())
+    }
+
+    pub fn has_rules(&self, column: usize) -> bool {
+        !self.rules_for(column).is_empty()
+    }
+
+    /// Whether rule `rule` of `column` runs; `rules_for` lists disabled ones too.
+    pub fn is_enabled(&self, column: usize, rule: usize) -> bool {
+        self.disabled.get(column).is_none_or(|off| !off.contains(&rule))
+    }
+
+    /// Takes up the `short_circuit` and `null_values` settings.
+    pub fn set_options(&mut self, options: &ValidationOptions) {
+        self.short_circuit = options.short_circuit;
+        self.null_values = options.null_values.clone();
+    }
+
+    pub fn short_circuit(&self) -> bool {
+        self.short_circuit
+    }
+
+    pub fn null_values(&self) -> &[String] {
+        &self.null_values
+    }
+
+    pub fn is_null(&self, value: &str) -> bool {
+        self.null_values.iter().any(|token| token == value)
+    }
+
+    /// Runs one rule on `value`, which may be a null token, of `record`;
+    /// `validate_cell` is this for every enabled rule of a column.
+    pub fn check(&self, rule: &CompiledRule, value: &str, record: &dyn Record) -> Option<&'static str> {
+        let record = Blanked { record, rule_set: self };
+        if !self.is_null(value) {
+            rule.check_record(value, &record)
+        } else if rule.checks_presence() {
+            rule.check_record("", &record)
+        } else {
+            None
+        }
+    }
+
+    /// Compiles `rule`'s rules for the columns it applies to.
+    #[cfg(feature = "wasm")]
+    pub fn compile(rule: &ColumnRule) -> Result<Vec<CompiledRule>, String> {
+        compile_rules(&registry().read().unwrap_or_else(|e| e.into_inner()), rule)
+    }
+
+    /// Replaces the rules of `column`, e.g. with those from `compile`, and
+    /// binds them to the headers (see `Rule::bind`). Nothing changes if one
+    /// can't be bound.
+    pub fn set_rules(&mut self, column: usize, rules: Vec<CompiledRule>, disabled: Vec<usize>) -> Result<(), String> {
+        let reads = std::cell::RefCell::new(Vec::new());
+        let find = |name: &str| {
+            let found = self.headers.iter().position(|header| self.matching.matches(header, name));
+            reads.borrow_mut().extend(found.filter(|&col| col != column));
+            found
+        };
+        let rules = rules.iter()
+            .map(|rule| Ok(rule.bind(column, &find)?.unwrap_or_else(|| rule.clone())))
+            .collect::<Result<Vec<_>, String>>()?;
+        let mut reads = reads.into_inner();
+        reads.sort_unstable();
+        reads.dedup();
+        self.columns[column] = rules;
+        self.disabled[column] = disabled;
+        self.reads[column] = reads;
+        Ok(())
+    }
+
+    /// The columns besides its own that the rules of `column` read.
+    pub fn reads(&self, column: usize) -> &[usize] {
+        &self.reads[column]
+    }
+
+    /// The columns whose rules read `column` besides their own, so that
+    /// editing it can change their results.
+    pub fn readers(&self, column: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.reads.len()).filter(move |&col| self.reads[col].contains(&column))
+    }
+
+    /// Fits every column's rules to `records`; see `fit_column`.
+    pub fn fit(&mut self, records: &RecordStore) {
+        for column in 0..self.columns.len() {
+            self.fit_column(column, records);
+        }
+    }
+
+    /// Fits the rules of `column` that depend on the data (see `Rule::fit`)
+    /// to `records`. Unfitted, such rules pass every value; fitted, they
+    /// keep what they learnt until fitted again, so later edits are judged
+    /// against the data as it was (unless they follow edits).
+    pub fn fit_column(&mut self, column: usize, records: &RecordStore) {
+        for rule in 0..self.columns[column].len() {
+            let fitted = self.fit_rule(&self.columns[column][rule], column, records);
+            self.columns[column][rule] = fitted;
+        }
+    }
+
+    /// Fits the rules of `column` that follow edits (see
+    /// `Rule::follows_edits`) to `records` again. Returns whether it had
+    /// any, in which case any of its cells may have changed result.
+    pub fn refit_edited(&mut self, column: usize, records: &RecordStore) -> bool {
+        let mut refit = false;
+        for rule in 0..self.columns[column].len() {
+            if self.columns[column][rule].follows_edits() {
+                self.columns[column][rule] = self.fit_rule(&self.columns[column][rule], column, records);
+                refit = true;
+            }
+        }
+        refit
+    }
+
+    /// `rule`, for `column`, fitted to `records` if it needs to be.
+    pub fn fit_rule(&self, rule: &CompiledRule, column: usize, records: &RecordStore) -> CompiledRule {
+        let cell = |row: usize, col: usize| {
+            let value = records.get(row, col);
+            if self.is_null(value) { "" } else { value }
+        };
+        rule.fit(column, records.len(), &cell).unwrap_or_else(|| rule.clone())
+    }
+
+    /// The violations of `value` in `column` of `record`. `value` is
+    /// usually `record.cell(column)`, but needn't be, e.g. to try a new value.
+    pub fn validate_cell<'a>(&'a self, column: usize, value: &'a str, record: &'a dyn Record) -> impl Iterator<Item = Violation> + 'a {
+        self.rules_for(column).iter().enumerate()
+            .filter(move |&(rule, _)| self.is_enabled(column, rule))
+            .filter_map(move |(rule, r)| self.check(r, value, record).map(|error_type| Violation { column, rule, error_type }))
+            .take(if self.short_circuit { 1 } else { usize::MAX })
+    }
+
+    pub fn validate_record<'a>(&'a self, record: &'a dyn Record) -> impl Iterator<Item = Violation> + 'a {
+        (0..self.columns.len()).flat_map(move |column| self.validate_cell(column, record.cell(column), record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValidationOptions;
+
+    fn rule_set(options: &str) -> RuleSet {
+        let headers: Vec<String> = ["code", "qty", "note"].iter().map(|h| h.to_string()).collect();
+        let rules: Vec<ColumnRule> = serde_json::from_str(r#"[
+            {"column": "code", "rules": [{"type": "notempty"}, {"type": "regex", "pattern": "^[A-Z]+$"}, {"type": "oneof", "options": ["ABC", "XYZ"]}]},
+            {"column": "qty", "rules": [{"type": "number", "min": 1}, {"type": "notempty"}], "disabled": [1]}
+        ]"#).unwrap();
+        let mut rule_set = RuleSet::new(&headers, &rules, &HeaderMatching::default()).unwrap();
+        rule_set.set_options(&serde_json::from_str::<ValidationOptions>(options).unwrap());
+        rule_set
+    }
+
+    fn violations(rule_set: &RuleSet, record: [&str; 3]) -> Vec<(usize, usize, &'static str)> {
+        rule_set.validate_record(&record.to_vec()).map(|v| (v.column, v.rule, v.error_type)).collect()
+    }
+
+    #[test]
+    fn records_yield_every_failed_rule_in_order() {
+        let rule_set = rule_set("{}");
+        assert_eq!(violations(&rule_set, ["ABC", "2", "x"]), []);
+        assert_eq!(violations(&rule_set, ["abcd", "0", ""]), [(0, 1, "Pattern Mismatch"), (0, 2, "Invalid Option"), (1, 0, "Min Value")]);
+        // The disabled `notempty` on qty stays quiet
+        assert_eq!(violations(&rule_set, ["ABC", "", ""]), [(1, 0, "Not a Number")]);
+
+        let record = vec!["abcd", "2", ""];
+        let cell: Vec<usize> = rule_set.validate_cell(0, "ABCD", &record).map(|v| v.rule).collect();
+        assert_eq!(cell, [2]);
+    }
+
+    #[test]
+    fn options_stop_at_the_first_failure_and_skip_nulls() {
+        let rule_set = rule_set(r#"{"short_circuit": true, "null_values": ["N/A"]}"#);
+        assert_eq!(violations(&rule_set, ["abcd", "0", ""]), [(0, 1, "Pattern Mismatch"), (1, 0, "Min Value")]);
+        // A null token only fails the rules that check presence
+        assert_eq!(violations(&rule_set, ["N/A", "N/A", ""]), [(0, 0, "Required")]);
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(feature = "wasm")]
+use js_sys::Reflect;
+use serde::Serialize;
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::progress::Cancelled;
+
+// --- Errors ---
+//
+// Failures of the core (loading, validating, exporting) are `ValidatorError`s.
+// `message` keeps the usual "Prefix: detail" text; `kind` says what was at
+// fault and, when it was the file itself, `byte_offset`, `line` (1-based) and
+// `record` (0-based, header included) locate the bad record and `snippet`
+// holds that line of input. JS gets them as `Error` objects with the same
+// fields.
+
+// Longest snippet attached to an error, in bytes
+const SNIPPET_MAX_LEN: usize = 200;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    /// The rules JSON, or a rule that doesn't compile.
+    Rules,
+    /// The options JSON or one of its values.
+    Options,
+    /// The header line(s): unreadable, colliding after mapping, or rejected.
+    Header,
+    /// A malformed record: bad quoting, invalid UTF-8 or a wrong field count.
+    Parse,
+    /// No header, or no records (unless `allow_empty` is set).
+    Empty,
+    /// Anything else about the data, e.g. the storage limit.
+    Data,
+    /// Writing an export, e.g. an unknown export column.
+    Export,
+    /// Stopped by a `CancellationToken`.
+    Cancelled,
+}
+
+#[cfg(feature = "wasm")]
+impl ErrorKind {
+    fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Rules => "rules",
+            ErrorKind::Options => "options",
+            ErrorKind::Header => "header",
+            ErrorKind::Parse => "parse",
+            ErrorKind::Empty => "empty",
+            ErrorKind::Data => "data",
+            ErrorKind::Export => "export",
+            ErrorKind::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ValidatorError {
+    pub kind: ErrorKind,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub byte_offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl ValidatorError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> ValidatorError {
+        ValidatorError { kind, message: message.into(), byte_offset: None, line: None, record: None, snippet: None }
+    }
+
+    /// From a csv error, keeping its position in the input if it has one.
+    pub(crate) fn csv(kind: ErrorKind, prefix: &str, error: &csv::Error) -> ValidatorError {
+        ValidatorError::new(kind, format!("{}: {}", prefix, error)).at(error.position())
+    }
+
+    pub(crate) fn at(mut self, position: Option<&csv::Position>) -> ValidatorError {
+        if let Some(position) = position {
+            self.byte_offset = Some(position.byte());
+            self.line = Some(position.line());
+            self.record = Some(position.record());
+        }
+        self
+    }
+
+    /// At the line starting at `byte_offset`, for errors found before the
+    /// input is split into records.
+    pub(crate) fn at_line(mut self, byte_offset: usize, line: usize) -> ValidatorError {
+        self.byte_offset = Some(byte_offset as u64);
+        self.line = Some(line as u64);
+        self
+    }
+
+    /// Adds the line of `input` at `byte_offset` as the snippet. Errors
+    /// without a position are returned as they are.
+    pub(crate) fn with_snippet(mut self, input: &str) -> ValidatorError {
+        let Some(start) = self.byte_offset.map(|offset| offset as usize) else { return self };
+        if !input.is_char_boundary(start) {
+            return self;
+        }
+        let line = input[start..].lines().next().unwrap_or("");
+        let mut end = line.len().min(SNIPPET_MAX_LEN);
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.snippet = Some(line[..end].to_string());
+        self
+    }
+}
+
+impl fmt::Display for ValidatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ValidatorError {}
+
+impl From<Cancelled> for ValidatorError {
+    fn from(_: Cancelled) -> ValidatorError {
+        ValidatorError::new(ErrorKind::Cancelled, "Operation Cancelled")
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<ValidatorError> for JsValue {
+    fn from(error: ValidatorError) -> JsValue {
+        let object: JsValue = js_sys::Error::new(&error.message).into();
+        set(&object, "kind", error.kind.name().into());
+        for (key, value) in [("byte_offset", error.byte_offset), ("line", error.line), ("record", error.record)] {
+            if let Some(value) = value {
+                set(&object, key, (value as f64).into());
+            }
+        }
+        if let Some(snippet) = &error.snippet {
+            set(&object, "snippet", snippet.into());
+        }
+        object
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn set(object: &JsValue, key: &str, value: JsValue) {
+    // Only fails on frozen objects, which a fresh Error never is
+    let _ = Reflect::set(object, &JsValue::from_str(key), &value);
+}
@@ -0,0 +1,818 @@
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::engine::{error_code, Violation};
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::review::{Annotation, REVIEW_HEADERS};
+use crate::store::Row;
+use crate::xlsx::{CellStyle, SheetWriter};
+use crate::Dataset;
+// Chunked exports are only reachable from JS
+#[cfg(feature = "wasm")]
+use {std::sync::Arc, wasm_bindgen::prelude::*};
+
+// --- Exports ---
+
+#[cfg(feature = "wasm")]
+// Rows written per `next_export_chunk` call
+const EXPORT_CHUNK_ROWS: usize = 10_000;
+
+/// Output format and dialect for the export methods, passed as an optional JSON
+/// argument, e.g. `{"delimiter": ";", "quoting": "always", "line_ending": "crlf"}`.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Single ASCII character separating fields.
+    pub delimiter: char,
+    pub quoting: Quoting,
+    pub line_ending: LineEnding,
+    /// Prefix cells starting with `=`, `+`, `-`, `@`, tab or carriage return
+    /// with a `'` so spreadsheets show them as text instead of evaluating a
+    /// formula. Note this also applies to negative numbers. CSV only.
+    pub escape_formulas: bool,
+    /// Headers to write, in output order. Applies to the valid rows, the
+    /// `"all"` stream and `get_content_as_csv`; `None` keeps every column.
+    pub columns: Option<Vec<String>>,
+    /// Like `columns`, for invalid rows. Defaults to every column so the
+    /// review export keeps full context even when `columns` drops some.
+    pub invalid_columns: Option<Vec<String>>,
+    /// How invalid rows describe their errors.
+    pub error_format: ErrorFormat,
+    /// Append `Review_Status` and `Review_Note` columns holding the row and
+    /// cell annotations (after any error columns).
+    pub include_review: bool,
+    /// The one way missing values are written: empty cells and cells
+    /// holding one of the validation `null_values` all become this, e.g.
+    /// `""`, `"NULL"` or `"\\N"` for a strict database loader. `None` keeps
+    /// them as they are.
+    pub null_value: Option<String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> ExportOptions {
+        ExportOptions {
+            format: ExportFormat::Csv,
+            delimiter: ',',
+            quoting: Quoting::Minimal,
+            line_ending: LineEnding::Lf,
+            escape_formulas: false,
+            columns: None,
+            invalid_columns: None,
+            error_format: ErrorFormat::Reason,
+            include_review: false,
+            null_value: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line, keyed by the names a CSV header would have
+    /// (including any error columns). All values are strings.
+    Jsonl,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Quoting {
+    /// Quote only fields that contain the delimiter, quotes or newlines.
+    Minimal,
+    /// Quote every field.
+    Always,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorFormat {
+    /// One `Error_Reason` column, e.g. "email: Invalid; age: Invalid".
+    Reason,
+    /// One `<column>_Error` column per validated column, holding the error
+    /// codes of that cell separated by `|` (empty when the cell is valid).
+    Columns,
+    /// A `row_number` column (1-based record number) instead of the reason,
+    /// plus a separate `errors` output in the `generate_error_detail` layout.
+    Detail,
+}
+
+impl ExportOptions {
+    /// Parses the optional options argument; missing means defaults.
+    pub fn from_json(options_json: Option<String>) -> Result<ExportOptions, ValidatorError> {
+        let options: ExportOptions = match options_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| ValidatorError::new(ErrorKind::Options, format!("Invalid Export Options JSON: {}", e)))?,
+            None => ExportOptions::default(),
+        };
+        options.check()?;
+        Ok(options)
+    }
+
+    /// Rejects a delimiter the CSV couldn't be read back with: non-ASCII, a
+    /// quote or a line break.
+    fn check(&self) -> Result<(), ValidatorError> {
+        if !self.delimiter.is_ascii() || matches!(self.delimiter, '"' | '\r' | '\n') {
+            return Err(ValidatorError::new(
+                ErrorKind::Options,
+                "Invalid Export Options: delimiter must be a single ASCII character other than a quote or line break",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One export output written with a given `ExportOptions` format and
+/// dialect, keeping only the selected columns.
+pub struct Output {
+    sink: Sink,
+    escape_formulas: bool,
+    terminator: &'static [u8],
+    // Indices of the columns to write, in output order
+    columns: Vec<usize>,
+    // Selected headers followed by the extra ones; for JSONL, made distinct
+    // as object keys
+    names: Vec<String>,
+    // Null tokens and what to write for them (`ExportOptions::null_value`)
+    nulls: Option<(Vec<String>, String)>,
+}
+
+enum Sink {
+    Csv(Box<csv::Writer<Vec<u8>>>),
+    Jsonl(Vec<u8>),
+}
+
+impl Output {
+    /// Resolves `columns` (every column when `None`) against `headers`;
+    /// `extra` names the cells passed after the record in `write_row`.
+    pub fn new<'a>(
+        options: &ExportOptions,
+        headers: &[String],
+        columns: Option<&[String]>,
+        extra: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Output, ValidatorError> {
+        options.check()?;
+        let columns = resolve_columns(headers, columns)?;
+        let names: Vec<String> = columns.iter().map(|&col| headers[col].clone())
+            .chain(extra.into_iter().map(|name| name.to_string()))
+            .collect();
+        let names = match options.format {
+            ExportFormat::Csv => names,
+            ExportFormat::Jsonl => json_keys(&names),
+        };
+        let sink = match options.format {
+            ExportFormat::Csv => Sink::Csv(Box::new(csv::WriterBuilder::new()
+                .delimiter(options.delimiter as u8)
+                .quote_style(match options.quoting {
+                    Quoting::Minimal => csv::QuoteStyle::Necessary,
+                    Quoting::Always => csv::QuoteStyle::Always,
+                })
+                .terminator(match options.line_ending {
+                    LineEnding::Lf => csv::Terminator::Any(b'\n'),
+                    LineEnding::Crlf => csv::Terminator::CRLF,
+                })
+                .from_writer(vec![]))),
+            ExportFormat::Jsonl => Sink::Jsonl(vec![]),
+        };
+        let terminator: &[u8] = match options.line_ending {
+            LineEnding::Lf => b"\n",
+            LineEnding::Crlf => b"\r\n",
+        };
+
+        Ok(Output { sink, escape_formulas: options.escape_formulas, terminator, columns, names, nulls: None })
+    }
+
+    /// Writes the header line (CSV only; JSONL lines carry their own keys).
+    pub fn write_header(&mut self) -> Result<(), ValidatorError> {
+        match &mut self.sink {
+            Sink::Csv(wtr) => write_record(wtr, self.escape_formulas, self.names.iter().map(|n| n.as_str())),
+            Sink::Jsonl(_) => Ok(()),
+        }
+    }
+
+    /// Writes the selected cells of `row`, followed by `extra` cells.
+    pub fn write_row<'a>(&mut self, row: Row<'a>, extra: impl IntoIterator<Item = &'a str>) -> Result<(), ValidatorError> {
+        let nulls = &self.nulls;
+        // Replacements borrow from `self`, so the cells can't keep lifetime 'a
+        let cells = self.columns.iter().map(|&col| replace_null(nulls, row.get(col))).chain(extra.into_iter().map(|cell| cell as &str));
+        write_line(&mut self.sink, self.escape_formulas, self.terminator, &self.names, cells)
+    }
+
+    /// Writes one line of cells matching the names given to `new`.
+    pub fn write_cells<'a>(&mut self, cells: impl IntoIterator<Item = &'a str>) -> Result<(), ValidatorError> {
+        write_line(&mut self.sink, self.escape_formulas, self.terminator, &self.names, cells)
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>, ValidatorError> {
+        match self.sink {
+            Sink::Csv(wtr) => wtr.into_inner().map_err(|e| export_error(e.to_string())),
+            Sink::Jsonl(buf) => Ok(buf),
+        }
+    }
+}
+
+/// `value`, or the `null_value` replacement if it is empty or a null token.
+fn replace_null<'a>(nulls: &'a Option<(Vec<String>, String)>, value: &'a str) -> &'a str {
+    match nulls {
+        Some((tokens, replacement)) if value.is_empty() || tokens.iter().any(|token| token == value) => replacement,
+        _ => value,
+    }
+}
+
+/// Indices of the named columns, in the given order (every column when `None`).
+fn resolve_columns(headers: &[String], columns: Option<&[String]>) -> Result<Vec<usize>, ValidatorError> {
+    match columns {
+        Some(names) => names.iter()
+            .map(|name| headers.iter().position(|h| h == name)
+                .ok_or_else(|| export_error(format!("Unknown Export Column: {}", name))))
+            .collect(),
+        None => Ok((0..headers.len()).collect()),
+    }
+}
+
+/// `names` as object keys: a repeated name gets `_2`, `_3` and so on
+/// (skipping any that is itself one of the names), so no column is lost.
+fn json_keys(names: &[String]) -> Vec<String> {
+    let mut taken: HashSet<String> = names.iter().cloned().collect();
+    let mut seen = HashSet::new();
+    names.iter()
+        .map(|name| {
+            if seen.insert(name.as_str()) {
+                return name.clone();
+            }
+            let key = (2..).map(|n| format!("{}_{}", name, n)).find(|key| !taken.contains(key)).unwrap_or_default();
+            taken.insert(key.clone());
+            key
+        })
+        .collect()
+}
+
+/// One JSONL line: an object pairing output names with cells.
+struct JsonLine<'a> {
+    names: &'a [String],
+    cells: Vec<&'a str>,
+}
+
+impl Serialize for JsonLine<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.cells.len()))?;
+        for (name, cell) in self.names.iter().zip(&self.cells) {
+            map.serialize_entry(name, cell)?;
+        }
+        map.end()
+    }
+}
+
+fn write_line<'a>(
+    sink: &mut Sink,
+    escape_formulas: bool,
+    terminator: &[u8],
+    names: &[String],
+    cells: impl IntoIterator<Item = &'a str>,
+) -> Result<(), ValidatorError> {
+    match sink {
+        Sink::Csv(wtr) => write_record(wtr, escape_formulas, cells),
+        Sink::Jsonl(buf) => {
+            let line = JsonLine { names, cells: cells.into_iter().collect() };
+            serde_json::to_writer(&mut *buf, &line).map_err(|e| export_error(e.to_string()))?;
+            buf.extend_from_slice(terminator);
+            Ok(())
+        }
+    }
+}
+
+fn write_record<'a>(wtr: &mut csv::Writer<Vec<u8>>, escape_formulas: bool, cells: impl IntoIterator<Item = &'a str>) -> Result<(), ValidatorError> {
+    let result = if escape_formulas {
+        wtr.write_record(cells.into_iter().map(escape_formula))
+    } else {
+        wtr.write_record(cells)
+    };
+    result.map_err(|e| export_error(e.to_string()))
+}
+
+fn export_error(message: impl Into<String>) -> ValidatorError {
+    ValidatorError::new(ErrorKind::Export, message)
+}
+
+/// Neutralizes a cell that a spreadsheet would otherwise run as a formula.
+fn escape_formula(cell: &str) -> Cow<'_, [u8]> {
+    match cell.as_bytes().first() {
+        Some(b'=' | b'+' | b'-' | b'@' | b'\t' | b'\r') => Cow::Owned(format!("'{}", cell).into_bytes()),
+        _ => Cow::Borrowed(cell.as_bytes()),
+    }
+}
+
+/// Headers appended to every written row when `include_review` is set.
+fn review_headers(include_review: bool) -> &'static [&'static str] {
+    if include_review { &REVIEW_HEADERS } else { &[] }
+}
+
+// Header of the `ErrorFormat::Detail` errors output
+const ERROR_DETAIL_HEADERS: [&str; 5] = ["row_number", "column", "error_code", "message", "value"];
+
+/// Which rows an export covers.
+#[cfg(feature = "wasm")]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportKind {
+    Valid,
+    Invalid,
+    All,
+    /// The `ErrorFormat::Detail` errors file.
+    Errors,
+}
+
+#[cfg(feature = "wasm")]
+impl ExportKind {
+    pub fn parse(kind: &str) -> Result<ExportKind, ValidatorError> {
+        match kind {
+            "valid" => Ok(ExportKind::Valid),
+            "invalid" => Ok(ExportKind::Invalid),
+            "all" => Ok(ExportKind::All),
+            "errors" => Ok(ExportKind::Errors),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Export Kind: {}", other))),
+        }
+    }
+}
+
+/// The outputs of a split export, filled row by row.
+pub struct SplitExport {
+    valid: Output,
+    invalid: Output,
+    // Only with `ErrorFormat::Detail`
+    errors: Option<Output>,
+    error_format: ErrorFormat,
+    include_review: bool,
+}
+
+/// The finished outputs of a split export, for native callers and
+/// `generate_split_export_bytes` (where each becomes a `Uint8Array`).
+#[derive(Serialize)]
+pub struct SplitOutput {
+    #[serde(serialize_with = "as_bytes")]
+    pub valid: Vec<u8>,
+    #[serde(serialize_with = "as_bytes")]
+    pub invalid: Vec<u8>,
+    /// Only with `ErrorFormat::Detail`.
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "as_optional_bytes")]
+    pub errors: Option<Vec<u8>>,
+}
+
+/// What `generate_split_export` returns.
+#[derive(Serialize)]
+pub struct SplitExportResult {
+    pub valid: String,
+    pub invalid: String,
+    /// Only with `ErrorFormat::Detail`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<String>,
+}
+
+// Byte buffers as bytes rather than a sequence of numbers
+fn as_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(bytes)
+}
+
+fn as_optional_bytes<S: Serializer>(bytes: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serializer.serialize_bytes(bytes),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl SplitExport {
+    pub fn new(data: &Dataset, options: &ExportOptions) -> Result<SplitExport, ValidatorError> {
+        let review = review_headers(options.include_review);
+        let error_headers = data.error_headers(options.error_format);
+        let mut valid = data.row_output(options, options.columns.as_deref(), review.iter().copied())?;
+        let mut invalid = data.row_output(
+            options,
+            options.invalid_columns.as_deref(),
+            error_headers.iter().map(|h| h.as_str()).chain(review.iter().copied()),
+        )?;
+        valid.write_header()?;
+        invalid.write_header()?;
+
+        let errors = match options.error_format {
+            ErrorFormat::Detail => {
+                let mut errors = Output::new(options, &[], None, ERROR_DETAIL_HEADERS)?;
+                errors.write_header()?;
+                Some(errors)
+            }
+            ErrorFormat::Reason | ErrorFormat::Columns => None,
+        };
+        Ok(SplitExport { valid, invalid, errors, error_format: options.error_format, include_review: options.include_review })
+    }
+
+    pub fn into_output(self) -> Result<SplitOutput, ValidatorError> {
+        Ok(SplitOutput {
+            valid: self.valid.into_bytes()?,
+            invalid: self.invalid.into_bytes()?,
+            errors: self.errors.map(Output::into_bytes).transpose()?,
+        })
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn into_result(self) -> Result<SplitExportResult, ValidatorError> {
+        let to_string = |bytes: Vec<u8>| String::from_utf8(bytes).map_err(|e| export_error(e.to_string()));
+        Ok(SplitExportResult {
+            valid: to_string(self.valid.into_bytes()?)?,
+            invalid: to_string(self.invalid.into_bytes()?)?,
+            errors: self.errors.map(|errors| errors.into_bytes().and_then(to_string)).transpose()?,
+        })
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn into_js(self) -> Result<JsValue, JsValue> {
+        // json_compatible() so JS gets a plain object rather than a Map
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        self.into_result()?.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn into_js_bytes(self) -> Result<JsValue, JsValue> {
+        // The default serializer, which turns bytes into a `Uint8Array`
+        Ok(serde_wasm_bindgen::to_value(&self.into_output()?)?)
+    }
+}
+
+/// Position of a streaming export started by `start_export`. Holds its own
+/// snapshot of the data so edits between chunks can't tear the output.
+#[cfg(feature = "wasm")]
+pub struct ExportCursor {
+    kind: ExportKind,
+    options: ExportOptions,
+    data: Arc<Dataset>,
+    next_row: usize,
+    header_written: bool,
+}
+
+#[cfg(feature = "wasm")]
+impl ExportCursor {
+    pub fn new(kind: ExportKind, options: ExportOptions, data: Arc<Dataset>) -> Result<ExportCursor, ValidatorError> {
+        let cursor = ExportCursor { kind, options, data, next_row: 0, header_written: false };
+        // Fail on a bad column list now rather than at the first chunk
+        cursor.output()?;
+        Ok(cursor)
+    }
+
+    fn output(&self) -> Result<Output, ValidatorError> {
+        let (options, data) = (&self.options, &self.data);
+        let review = review_headers(options.include_review).iter().copied();
+        match self.kind {
+            ExportKind::Valid | ExportKind::All => data.row_output(options, options.columns.as_deref(), review),
+            ExportKind::Invalid => {
+                let error_headers = data.error_headers(options.error_format);
+                data.row_output(options, options.invalid_columns.as_deref(), error_headers.iter().map(|h| h.as_str()).chain(review))
+            }
+            ExportKind::Errors => Output::new(options, &[], None, ERROR_DETAIL_HEADERS),
+        }
+    }
+
+    /// Writes the next batch of rows, or returns `None` once everything
+    /// (including the header) has been handed out.
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, ValidatorError> {
+        let data = Arc::clone(&self.data);
+        let (format, include_review) = (self.options.error_format, self.options.include_review);
+        let total = data.records.len();
+        if self.header_written && self.next_row >= total {
+            return Ok(None);
+        }
+
+        let mut out = self.output()?;
+        if !self.header_written {
+            out.write_header()?;
+            self.header_written = true;
+        }
+
+        let mut written = 0;
+        while self.next_row < total && written < EXPORT_CHUNK_ROWS {
+            let idx = self.next_row;
+            self.next_row += 1;
+            let record = data.records.row(idx);
+            if self.kind == ExportKind::All {
+                let review = data.review_extra(include_review, idx);
+                out.write_row(record, review.iter().map(|c| c.as_str()))?;
+                written += 1;
+                continue;
+            }
+
+            let violations = data.violations(idx);
+            match self.kind {
+                ExportKind::Valid if violations.is_empty() => {
+                    let review = data.review_extra(include_review, idx);
+                    out.write_row(record, review.iter().map(|c| c.as_str()))?;
+                }
+                ExportKind::Invalid if !violations.is_empty() => {
+                    let mut cells = data.error_cells(format, idx, &violations);
+                    cells.extend(data.review_extra(include_review, idx));
+                    out.write_row(record, cells.iter().map(|c| c.as_str()))?;
+                }
+                ExportKind::Errors if !violations.is_empty() => data.write_error_detail(&mut out, idx, &violations)?,
+                _ => continue,
+            }
+            written += 1;
+        }
+        out.into_bytes().map(Some)
+    }
+}
+
+/// A run of records serialized as an array of `JsonRecord`s.
+pub struct JsonPage<'a> {
+    data: &'a Dataset,
+    rows: Range<usize>,
+    columns: Vec<usize>,
+    // Object keys for `columns`, distinct even when headers repeat
+    keys: Vec<String>,
+    with_errors: bool,
+}
+
+/// One record as a JSON object keyed by header, plus an `_errors` list
+/// when annotated and a `_review` list when the row has review annotations.
+struct JsonRecord<'a> {
+    keys: &'a [String],
+    columns: &'a [usize],
+    row: Row<'a>,
+    errors: Option<Vec<JsonError<'a>>>,
+    review: &'a [Annotation],
+}
+
+/// One failed rule in a `JsonRecord`'s `_errors`.
+#[derive(Serialize)]
+struct JsonError<'a> {
+    column: &'a str,
+    rule: &'static str,
+    code: &'static str,
+    error: &'static str,
+}
+
+impl Serialize for JsonPage<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.rows.len()))?;
+        for idx in self.rows.clone() {
+            seq.serialize_element(&self.data.json_record(idx, &self.columns, &self.keys, self.with_errors))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for JsonRecord<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (key, &col) in self.keys.iter().zip(self.columns) {
+            map.serialize_entry(key, self.row.get(col))?;
+        }
+        if let Some(errors) = &self.errors {
+            map.serialize_entry("_errors", errors)?;
+        }
+        if !self.review.is_empty() {
+            map.serialize_entry("_review", self.review)?;
+        }
+        map.end()
+    }
+}
+
+impl Dataset {
+    /// An `Output` for rows of this data, rewriting missing values as
+    /// `options.null_value` says.
+    fn row_output<'a>(&self, options: &ExportOptions, columns: Option<&[String]>, extra: impl IntoIterator<Item = &'a str>) -> Result<Output, ValidatorError> {
+        let mut out = Output::new(options, &self.headers, columns, extra)?;
+        if let Some(replacement) = &options.null_value {
+            out.nulls = Some((self.rule_set.null_values().to_vec(), replacement.clone()));
+        }
+        Ok(out)
+    }
+
+    /// Every row with its current values.
+    pub(crate) fn write_all(&self, options: &ExportOptions, progress: &Progress) -> Result<Vec<u8>, ValidatorError> {
+        self.write_rows(options, 0..self.records.len(), progress)
+    }
+
+    /// The rows at `indices`, in that order, with their current values.
+    pub(crate) fn write_rows(&self, options: &ExportOptions, indices: impl ExactSizeIterator<Item = usize>, progress: &Progress) -> Result<Vec<u8>, ValidatorError> {
+        let mut out = self.row_output(options, options.columns.as_deref(), review_headers(options.include_review).iter().copied())?;
+        out.write_header()?;
+
+        let total = indices.len();
+        for (done, idx) in indices.enumerate() {
+            let review = self.review_extra(options.include_review, idx);
+            out.write_row(self.records.row(idx), review.iter().map(|c| c.as_str()))?;
+            progress.row(done, total)?;
+        }
+        out.into_bytes()
+    }
+
+    /// Every row as a workbook with invalid cells highlighted and their
+    /// errors attached as comments. Only `columns` and `include_review`
+    /// apply from the options.
+    pub(crate) fn write_xlsx(&self, options: &ExportOptions, progress: &Progress) -> Result<Vec<u8>, ValidatorError> {
+        let columns = resolve_columns(&self.headers, options.columns.as_deref())?;
+        let mut sheet = SheetWriter::new();
+        let header = columns.iter().map(|&col| self.headers[col].as_str()).chain(review_headers(options.include_review).iter().copied());
+        sheet.write_row(header.map(|name| (name, CellStyle::Header, None)))
+            .map_err(export_error)?;
+
+        let total = self.records.len();
+        for (idx, record) in self.records.rows().enumerate() {
+            let violations = self.violations(idx);
+            let cells = columns.iter().map(|&col| {
+                let errors: Vec<&str> = violations.iter().filter(|v| v.column == col).map(|v| v.error_type).collect();
+                if errors.is_empty() {
+                    (record.get(col), CellStyle::Normal, None)
+                } else {
+                    (record.get(col), CellStyle::Highlight, Some(errors.join("\n")))
+                }
+            });
+            let review = self.review_extra(options.include_review, idx);
+            let cells = cells.chain(review.iter().map(|c| (c.as_str(), CellStyle::Normal, None)));
+            sheet.write_row(cells).map_err(export_error)?;
+            progress.row(idx, total)?;
+        }
+        sheet.finish().map_err(export_error)
+    }
+
+    pub(crate) fn write_split(&self, export: &mut SplitExport, rows: Range<usize>, progress: &Progress) -> Result<(), ValidatorError> {
+        let total = self.records.len();
+        for idx in rows {
+            let record = self.records.row(idx);
+            let violations = self.violations(idx);
+            if violations.is_empty() {
+                let review = self.review_extra(export.include_review, idx);
+                export.valid.write_row(record, review.iter().map(|c| c.as_str()))?;
+            } else {
+                let mut cells = self.error_cells(export.error_format, idx, &violations);
+                cells.extend(self.review_extra(export.include_review, idx));
+                export.invalid.write_row(record, cells.iter().map(|c| c.as_str()))?;
+                if let Some(errors) = export.errors.as_mut() {
+                    self.write_error_detail(errors, idx, &violations)?;
+                }
+            }
+            progress.row(idx, total)?;
+        }
+        Ok(())
+    }
+
+    /// The `review_headers` cells for row `idx`.
+    fn review_extra(&self, include_review: bool, idx: usize) -> Vec<String> {
+        if include_review { self.review_cells(idx).into() } else { Vec::new() }
+    }
+
+    /// Headers appended to invalid rows in the given format.
+    fn error_headers(&self, format: ErrorFormat) -> Vec<String> {
+        match format {
+            ErrorFormat::Reason => vec!["Error_Reason".to_string()],
+            ErrorFormat::Columns => self.validated_columns().map(|col| format!("{}_Error", self.headers[col])).collect(),
+            ErrorFormat::Detail => vec!["row_number".to_string()],
+        }
+    }
+
+    /// Cells appended to an invalid row, matching `error_headers`.
+    fn error_cells(&self, format: ErrorFormat, idx: usize, violations: &[Violation]) -> Vec<String> {
+        match format {
+            ErrorFormat::Reason => {
+                let reasons: Vec<String> = violations.iter().map(|v| format!("{}: Invalid", self.headers[v.column])).collect();
+                vec![reasons.join("; ")]
+            }
+            ErrorFormat::Columns => self.validated_columns()
+                .map(|col| {
+                    let codes: Vec<&str> = violations.iter().filter(|v| v.column == col).map(|v| error_code(v.error_type)).collect();
+                    codes.join("|")
+                })
+                .collect(),
+            ErrorFormat::Detail => vec![self.row_number(idx).to_string()],
+        }
+    }
+
+    /// One error detail line per violation in row `idx`.
+    fn write_error_detail(&self, out: &mut Output, idx: usize, violations: &[Violation]) -> Result<(), ValidatorError> {
+        let row_number = self.row_number(idx).to_string();
+        let record = self.records.row(idx);
+        for v in violations {
+            out.write_cells([
+                row_number.as_str(),
+                self.headers[v.column].as_str(),
+                error_code(v.error_type),
+                v.error_type,
+                record.get(v.column),
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Every violation in the file, one line each.
+    pub(crate) fn write_error_details(&self, options: &ExportOptions, progress: &Progress) -> Result<Vec<u8>, ValidatorError> {
+        let mut out = Output::new(options, &[], None, ERROR_DETAIL_HEADERS)?;
+        out.write_header()?;
+
+        let total = self.records.len();
+        for idx in 0..total {
+            let violations = self.violations(idx);
+            self.write_error_detail(&mut out, idx, &violations)?;
+            progress.row(idx, total)?;
+        }
+        out.into_bytes()
+    }
+
+    /// Records in `rows` (clamped to the data) as JSON objects holding the
+    /// given columns (all when `None`).
+    pub(crate) fn json_page(&self, rows: Range<usize>, columns: Option<&[String]>, with_errors: bool) -> Result<JsonPage<'_>, ValidatorError> {
+        let total = self.records.len();
+        let rows = rows.start.min(total)..rows.end.min(total);
+        let columns = resolve_columns(&self.headers, columns)?;
+        let names: Vec<String> = columns.iter().map(|&col| self.headers[col].clone()).collect();
+        Ok(JsonPage { data: self, rows, columns, keys: json_keys(&names), with_errors })
+    }
+
+    /// Record `idx` as a JSON object keyed by `keys`, annotated with `_errors` (empty for a
+    /// valid row) when `with_errors` is set. Errors cover every column, not
+    /// just the ones written.
+    fn json_record<'a>(&'a self, idx: usize, columns: &'a [usize], keys: &'a [String], with_errors: bool) -> JsonRecord<'a> {
+        let errors = with_errors.then(|| {
+            self.violations(idx).into_iter()
+                .map(|v| JsonError {
+                    column: &self.headers[v.column],
+                    rule: self.rule_set.rules_for(v.column)[v.rule].name(),
+                    code: error_code(v.error_type),
+                    error: v.error_type,
+                })
+                .collect()
+        });
+        JsonRecord { keys, columns, row: self.records.row(idx), errors, review: self.row_annotations(idx) }
+    }
+
+    fn validated_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.headers.len()).filter(|&col| self.rule_set.has_rules(col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_headers_get_distinct_json_keys() {
+        let headers: Vec<String> = ["name", "name", "name_2", "name"].iter().map(|h| h.to_string()).collect();
+        assert_eq!(json_keys(&headers), ["name", "name_3", "name_2", "name_4"]);
+
+        let options = ExportOptions { format: ExportFormat::Jsonl, ..ExportOptions::default() };
+        let mut out = Output::new(&options, &headers[..2], None, []).unwrap();
+        out.write_cells(["Ada", "Lovelace"]).unwrap();
+        let line = String::from_utf8(out.into_bytes().unwrap()).unwrap();
+        assert_eq!(line, "{\"name\":\"Ada\",\"name_2\":\"Lovelace\"}\n");
+    }
+
+    fn csv(options: &ExportOptions, rows: &[[&str; 3]]) -> String {
+        let headers: Vec<String> = ["id", "note", "=total"].iter().map(|h| h.to_string()).collect();
+        let mut out = Output::new(options, &headers, None, []).unwrap();
+        out.write_header().unwrap();
+        for row in rows {
+            out.write_cells(row.iter().copied()).unwrap();
+        }
+        String::from_utf8(out.into_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn cells_are_quoted_as_the_dialect_needs() {
+        let rows = [["1", "say \"hi\"", "a;b"], ["2", "two\nlines", ""]];
+        assert_eq!(csv(&ExportOptions::default(), &rows), "id,note,=total\n1,\"say \"\"hi\"\"\",a;b\n2,\"two\nlines\",\n");
+
+        let options = ExportOptions { delimiter: ';', line_ending: LineEnding::Crlf, ..ExportOptions::default() };
+        assert_eq!(csv(&options, &rows), "id;note;=total\r\n1;\"say \"\"hi\"\"\";\"a;b\"\r\n2;\"two\nlines\";\r\n");
+
+        let options = ExportOptions { quoting: Quoting::Always, ..ExportOptions::default() };
+        assert_eq!(csv(&options, &rows[1..]), "\"id\",\"note\",\"=total\"\n\"2\",\"two\nlines\",\"\"\n");
+    }
+
+    #[test]
+    fn formula_cells_are_escaped_on_request() {
+        let rows = [["=1+1", "+49 30 1234", "-5"], ["@SUM(A1)", "\tx", "\rx"], ["a=b", " =x", ""]];
+        assert_eq!(csv(&ExportOptions::default(), &rows[..1]), "id,note,=total\n=1+1,+49 30 1234,-5\n");
+
+        let options = ExportOptions { escape_formulas: true, ..ExportOptions::default() };
+        let expected = "id,note,'=total\n'=1+1,'+49 30 1234,'-5\n'@SUM(A1),'\tx,\"'\rx\"\na=b, =x,\n";
+        assert_eq!(csv(&options, &rows), expected);
+    }
+
+    #[test]
+    fn delimiters_that_break_the_csv_are_rejected() {
+        for delimiter in ["\\\"", "\\n", "\\r", "é"] {
+            let json = format!(r#"{{"delimiter": "{}"}}"#, delimiter);
+            let error = ExportOptions::from_json(Some(json)).err().unwrap();
+            assert_eq!(error.kind, ErrorKind::Options, "{}", delimiter);
+        }
+        let options = ExportOptions { delimiter: '"', ..ExportOptions::default() };
+        let error = Output::new(&options, &["a".to_string()], None, []).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::Options);
+        assert!(ExportOptions::from_json(Some(r#"{"delimiter": "\t"}"#.to_string())).is_ok());
+    }
+}
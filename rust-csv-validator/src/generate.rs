@@ -0,0 +1,671 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use regex_syntax::hir::{Class, Hir, HirKind};
+use semver::Version;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::countries::{alpha2, ISO_3166};
+use crate::engine::RuleSet;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::report::civil_from_days;
+use crate::national_id::national_id_check;
+use crate::postal::postal_pattern;
+use crate::rules::{date_bound, day_number, parse_date, parse_duration, today, Date};
+use crate::sample::SplitMix64;
+use crate::timezones::TIME_ZONES;
+use crate::{parse_rules, ColumnRule, CoordinateKind, DateConstraint, DurationFormat, HeaderMatching, MacFormat, PathStyle, RuleType};
+
+// --- Sample Data ---
+//
+// Fabricated files for testing an import pipeline or demoing a rule set.
+// Values are built from the rules (and, for columns with only `notempty`,
+// the column name), then checked against the compiled rules; custom rule
+// types are only known through that check, so their columns may not come
+// out valid. Columns whose rules read other cells of the record are filled
+// in last, once those cells are there.
+
+// Attempts at a value before settling for the last one
+const MAX_ATTEMPTS: usize = 10;
+// Longest run generated for an unbounded repetition (`*`, `+`, `{n,}`)
+const MAX_EXTRA_REPEATS: u32 = 3;
+
+const FIRST_NAMES: [&str; 12] = ["Alice", "Bruno", "Chloe", "Dmitri", "Elena", "Farid", "Grace", "Hiro", "Ines", "Jonas", "Kemi", "Luca"];
+const LAST_NAMES: [&str; 12] = ["Anderson", "Bianchi", "Chen", "Dubois", "Evans", "Fischer", "Garcia", "Haddad", "Ivanova", "Jensen", "Kowalski", "Lopez"];
+const CITIES: [&str; 8] = ["Amsterdam", "Berlin", "Chicago", "Dublin", "Lisbon", "Madrid", "Osaka", "Toronto"];
+const COUNTRIES: [&str; 8] = ["Canada", "France", "Germany", "Ireland", "Japan", "Portugal", "Spain", "United States"];
+const WORDS: [&str; 12] = ["amber", "birch", "cobalt", "delta", "ember", "fjord", "granite", "harbor", "indigo", "juniper", "kestrel", "lagoon"];
+const LANGUAGE_TAGS: [&str; 8] = ["de-DE", "en", "en-GB", "es-419", "fr", "ja", "pt-BR", "zh-Hant"];
+
+/// A CSV with a header of the rule columns and `rows` fabricated records
+/// that pass `rules_json`. With `invalid_fraction` (0 to 1, default 0) that
+/// share of records, picked at random, each break one rule of one column.
+/// The same `seed` (default 0) always gives the same file.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_sample_data(rules_json: &str, rows: usize, seed: Option<u32>, invalid_fraction: Option<f64>) -> Result<String, ValidatorError> {
+    let rules = parse_rules(rules_json)?;
+    let invalid_fraction = invalid_fraction.unwrap_or(0.0);
+    if !(0.0..=1.0).contains(&invalid_fraction) {
+        return Err(ValidatorError::new(ErrorKind::Options, "Invalid Fraction: must be between 0 and 1"));
+    }
+    let bytes = generate(&rules, rows, seed.unwrap_or(0), invalid_fraction)?;
+    String::from_utf8(bytes).map_err(|e| ValidatorError::new(ErrorKind::Data, e.to_string()))
+}
+
+pub(crate) fn generate(rules: &[ColumnRule], rows: usize, seed: u32, invalid_fraction: f64) -> Result<Vec<u8>, ValidatorError> {
+    let headers: Vec<String> = rules.iter().map(|r| r.column.clone()).collect();
+    let rule_set = RuleSet::new(&headers, rules, &HeaderMatching::default())
+        .map_err(|e| ValidatorError::new(ErrorKind::Rules, e))?;
+    let shapes = rules.iter().map(|rule| Shape::new(rule, &headers)).collect::<Result<Vec<_>, _>>()?;
+    let order: Vec<usize> = (0..headers.len()).filter(|&col| rule_set.reads(col).is_empty())
+        .chain((0..headers.len()).filter(|&col| !rule_set.reads(col).is_empty()))
+        .collect();
+    // Enabled rules by column, for the columns a record can be made invalid
+    // through. Whether a value is an anomaly or a dependency conflict
+    // depends on the rest of the column, so those rules are left out.
+    let breakable = |col: usize, rule: usize| rule_set.is_enabled(col, rule)
+        && !matches!(rules[col].rules[rule], RuleType::Anomaly { .. } | RuleType::DependsOn { .. });
+    let ruled: Vec<(usize, Vec<usize>)> = (0..headers.len())
+        .map(|col| (col, (0..rule_set.rules_for(col).len()).filter(|&rule| breakable(col, rule)).collect::<Vec<_>>()))
+        .filter(|(_, enabled)| !enabled.is_empty())
+        .collect();
+
+    let mut rng = SplitMix64(seed as u64);
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    wtr.write_record(&headers).map_err(export_error)?;
+    for row in 0..rows {
+        let mut record = vec![String::new(); headers.len()];
+        for &col in &order {
+            record[col] = valid_value(&rule_set, col, &shapes[col], row, &record, &mut rng);
+        }
+        if !ruled.is_empty() && rng.unit() < invalid_fraction {
+            let (col, enabled) = rng.pick(&ruled);
+            let (col, rule) = (*col, *rng.pick(enabled));
+            record[col] = invalid_value(&rule_set, col, &rules[col].rules[rule], rule, &record);
+        }
+        wtr.write_record(&record).map_err(export_error)?;
+    }
+    wtr.into_inner().map_err(|e| export_error(e.to_string()))
+}
+
+fn export_error(e: impl ToString) -> ValidatorError {
+    ValidatorError::new(ErrorKind::Export, e.to_string())
+}
+
+/// What a valid value of a column looks like, from the most specific of its rules.
+enum Shape {
+    OneOf(Vec<String>),
+    Pattern(Hir),
+    Email,
+    Number { min: Option<f64>, max: Option<f64> },
+    Percent { min: Option<f64>, max: Option<f64>, allow_symbol: bool, fraction: bool },
+    Coordinate(CoordinateKind),
+    /// ISO 3166-1 codes of this many letters.
+    CountryCode(u8),
+    LanguageCode,
+    Timezone,
+    /// National IDs of the country with this alpha-2 code.
+    NationalId(&'static str),
+    /// File paths, Windows style or not, ending in `.extension`.
+    Path { windows: bool, absolute: bool, extension: String },
+    Hostname,
+    /// Random bytes, at most this many.
+    Base64(usize),
+    MacAddress(MacFormat),
+    HexColor,
+    Semver { min: Version, max: Version },
+    /// Whole seconds in `[min, max]`, written in `format`.
+    Duration { min: u64, max: u64, format: DurationFormat },
+    /// Dates between two `day_number`s.
+    Date { first: i64, last: i64 },
+    /// Birth dates giving an age in `[min, max]` on `reference`.
+    Age { min: Option<u32>, max: Option<u32>, reference: Date },
+    /// Free text, picked by the column name.
+    Text(TextHint),
+    /// Postal codes of the country in the record's cell at `country`, or
+    /// `fallback` when it is blank or has no known format.
+    PostalCode { country: Option<usize>, fallback: Box<Shape> },
+    /// `shape`, the same for every record with the same cells at `keys`.
+    Keyed { keys: Vec<usize>, shape: Box<Shape> },
+}
+
+enum TextHint {
+    Email,
+    FirstName,
+    LastName,
+    FullName,
+    City,
+    Country,
+    Phone,
+    Date,
+    Id,
+    Word,
+}
+
+impl Shape {
+    fn new(rule: &ColumnRule, headers: &[String]) -> Result<Shape, ValidatorError> {
+        let shape = Shape::of_value(rule)?;
+        let shape = match rule.rules.iter().find(|r| matches!(r, RuleType::PostalCode { .. })) {
+            Some(RuleType::PostalCode { country_column: Some(name), .. }) => {
+                // An unknown country column already failed to compile
+                let country = headers.iter().position(|h| h == name);
+                Shape::PostalCode { country, fallback: Box::new(shape) }
+            }
+            _ => shape,
+        };
+        let Some(RuleType::DependsOn { key_columns }) = rule.rules.iter().find(|r| matches!(r, RuleType::DependsOn { .. })) else {
+            return Ok(shape);
+        };
+        // Unknown key columns already failed to compile
+        let keys = key_columns.iter().filter_map(|key| headers.iter().position(|h| h == key)).collect();
+        Ok(Shape::Keyed { keys, shape: Box::new(shape) })
+    }
+
+    /// The shape of the values themselves, from the rules that judge them alone.
+    fn of_value(rule: &ColumnRule) -> Result<Shape, ValidatorError> {
+        let find = |f: fn(&RuleType) -> bool| rule.rules.iter().find(|r| f(r));
+        if let Some(RuleType::OneOf { options }) = find(|r| matches!(r, RuleType::OneOf { .. })) {
+            return Ok(Shape::OneOf(options.clone()));
+        }
+        if let Some(RuleType::Regex { pattern, .. }) = find(|r| matches!(r, RuleType::Regex { .. })) {
+            let hir = regex_syntax::Parser::new().parse(pattern)
+                .map_err(|e| ValidatorError::new(ErrorKind::Rules, format!("Invalid pattern '{}': {}", pattern, e)))?;
+            return Ok(Shape::Pattern(hir));
+        }
+        if find(|r| matches!(r, RuleType::Email { .. })).is_some() {
+            return Ok(Shape::Email);
+        }
+        if let Some(&RuleType::Number { min, max, .. }) = find(|r| matches!(r, RuleType::Number { .. })) {
+            return Ok(Shape::Number { min, max });
+        }
+        if find(|r| matches!(r, RuleType::Anomaly { .. })).is_some() {
+            return Ok(Shape::Number { min: None, max: None });
+        }
+        if let Some(RuleType::NationalId { country, .. }) = find(|r| matches!(r, RuleType::NationalId { .. })) {
+            // An unsupported country already failed to compile
+            return Ok(Shape::NationalId(alpha2(country).unwrap_or("US")));
+        }
+        if let Some(RuleType::PostalCode { country, .. }) = find(|r| matches!(r, RuleType::PostalCode { .. })) {
+            // An unknown country already failed to compile
+            let pattern = country.as_deref().and_then(postal_pattern).unwrap_or(r"\d{5}");
+            let hir = regex_syntax::Parser::new().parse(pattern)
+                .map_err(|e| ValidatorError::new(ErrorKind::Rules, format!("Invalid pattern '{}': {}", pattern, e)))?;
+            return Ok(Shape::Pattern(hir));
+        }
+        if let Some(RuleType::Path { style, must_be_absolute, allowed_extensions, .. }) = find(|r| matches!(r, RuleType::Path { .. })) {
+            let extension = allowed_extensions.first().map_or("txt", |ext| ext.strip_prefix('.').unwrap_or(ext));
+            return Ok(Shape::Path { windows: *style == PathStyle::Windows, absolute: *must_be_absolute, extension: extension.to_string() });
+        }
+        if find(|r| matches!(r, RuleType::Hostname { .. })).is_some() {
+            return Ok(Shape::Hostname);
+        }
+        if let Some(&RuleType::Base64 { max_decoded_bytes, .. }) = find(|r| matches!(r, RuleType::Base64 { .. })) {
+            return Ok(Shape::Base64(max_decoded_bytes.unwrap_or(48).clamp(1, 48)));
+        }
+        if let Some(RuleType::MacAddress { formats, .. }) = find(|r| matches!(r, RuleType::MacAddress { .. })) {
+            return Ok(Shape::MacAddress(formats.first().copied().unwrap_or(MacFormat::Colon)));
+        }
+        if find(|r| matches!(r, RuleType::HexColor { .. })).is_some() {
+            return Ok(Shape::HexColor);
+        }
+        if let Some(RuleType::Semver { min, max, .. }) = find(|r| matches!(r, RuleType::Semver { .. })) {
+            let bound = |bound: &Option<String>| bound.as_deref().and_then(|bound| Version::parse(bound).ok());
+            let min = bound(min).unwrap_or(Version::new(0, 1, 0));
+            let max = bound(max).unwrap_or(Version::new(min.major.saturating_add(3), 20, 20));
+            let max = if max.cmp_precedence(&min).is_lt() { min.clone() } else { max };
+            return Ok(Shape::Semver { min, max });
+        }
+        if let Some(RuleType::Duration { min, max, formats, .. }) = find(|r| matches!(r, RuleType::Duration { .. })) {
+            // Bounds that don't parse already failed to compile
+            let bound = |bound: &Option<String>| bound.as_deref().and_then(|bound| parse_duration(bound, &[]));
+            let min = bound(min).map_or(0, |min| min.ceil() as u64);
+            let max = bound(max).map_or(min.saturating_add(8 * 3_600), |max| max.floor() as u64).max(min);
+            let format = formats.first().copied().unwrap_or(DurationFormat::Clock);
+            return Ok(Shape::Duration { min, max, format });
+        }
+        if let Some(&RuleType::Date { must_be, ref min, ref max, ref now, .. }) = find(|r| matches!(r, RuleType::Date { .. })) {
+            let (min, max, now) = date_bounds(min, max, now);
+            // Up to 3 years either side of `now` unless bounded
+            let (mut first, mut last) = (min.unwrap_or(now - 3 * 365), max.unwrap_or(now + 3 * 365));
+            match must_be {
+                Some(DateConstraint::Past) => last = last.min(now - 1),
+                Some(DateConstraint::TodayOrPast) => last = last.min(now),
+                Some(DateConstraint::Future) => first = first.max(now + 1),
+                None => {}
+            }
+            return Ok(Shape::Date { first, last: last.max(first) });
+        }
+        if let Some(RuleType::Age { min, max, reference_date, .. }) = find(|r| matches!(r, RuleType::Age { .. })) {
+            // An invalid reference date already failed to compile
+            let reference = reference_date.as_deref().and_then(parse_date).unwrap_or_else(today);
+            return Ok(Shape::Age { min: *min, max: *max, reference });
+        }
+        if find(|r| matches!(r, RuleType::Timezone { .. })).is_some() {
+            return Ok(Shape::Timezone);
+        }
+        if find(|r| matches!(r, RuleType::LanguageCode { .. })).is_some() {
+            return Ok(Shape::LanguageCode);
+        }
+        if let Some(&RuleType::CountryCode { alpha, .. }) = find(|r| matches!(r, RuleType::CountryCode { .. })) {
+            return Ok(Shape::CountryCode(alpha));
+        }
+        if let Some(&RuleType::Coordinate { kind, .. }) = find(|r| matches!(r, RuleType::Coordinate { .. })) {
+            return Ok(Shape::Coordinate(kind));
+        }
+        if let Some(&RuleType::Percent { min, max, allow_symbol, fraction, .. }) = find(|r| matches!(r, RuleType::Percent { .. })) {
+            return Ok(Shape::Percent { min, max, allow_symbol, fraction });
+        }
+        Ok(Shape::Text(TextHint::from_column(&rule.column)))
+    }
+}
+
+impl TextHint {
+    fn from_column(column: &str) -> TextHint {
+        let name = column.to_ascii_lowercase();
+        let has = |word: &str| name.contains(word);
+        if has("email") || has("e-mail") {
+            TextHint::Email
+        } else if has("first") && has("name") {
+            TextHint::FirstName
+        } else if (has("last") && has("name")) || has("surname") {
+            TextHint::LastName
+        } else if has("name") {
+            TextHint::FullName
+        } else if has("city") {
+            TextHint::City
+        } else if has("country") {
+            TextHint::Country
+        } else if has("phone") || has("mobile") {
+            TextHint::Phone
+        } else if has("date") || name.ends_with("_at") {
+            TextHint::Date
+        } else if name == "id" || name.ends_with("_id") || name.ends_with(" id") {
+            TextHint::Id
+        } else {
+            TextHint::Word
+        }
+    }
+}
+
+/// A value for record `row` that passes the column's rules, if one turns
+/// up in `MAX_ATTEMPTS` tries. `record` holds the cells filled in so far.
+fn valid_value(rule_set: &RuleSet, col: usize, shape: &Shape, row: usize, record: &Vec<String>, rng: &mut SplitMix64) -> String {
+    let mut value = String::new();
+    for _ in 0..MAX_ATTEMPTS {
+        value = shape_value(shape, row, record, rng);
+        if rule_set.validate_cell(col, &value, record).next().is_none() {
+            break;
+        }
+    }
+    value
+}
+
+fn shape_value(shape: &Shape, row: usize, record: &[String], rng: &mut SplitMix64) -> String {
+    match shape {
+        Shape::Keyed { keys, shape } => {
+            // FNV-1a over the length-prefixed key cells
+            let hash = keys.iter()
+                .flat_map(|&col| (record[col].len() as u64).to_le_bytes().into_iter().chain(record[col].bytes()))
+                .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3));
+            shape_value(shape, row, record, &mut SplitMix64(hash))
+        }
+        Shape::PostalCode { country, fallback } => {
+            let pattern = country.and_then(|col| postal_pattern(record[col].trim()));
+            match pattern.and_then(|pattern| regex_syntax::Parser::new().parse(pattern).ok()) {
+                Some(hir) => {
+                    let mut out = String::new();
+                    pattern_value(&hir, rng, &mut out);
+                    out
+                }
+                None => shape_value(fallback, row, record, rng),
+            }
+        }
+        Shape::OneOf(options) if !options.is_empty() => rng.pick(options).clone(),
+        Shape::OneOf(_) => String::new(),
+        Shape::Pattern(hir) => {
+            let mut out = String::new();
+            pattern_value(hir, rng, &mut out);
+            out
+        }
+        Shape::Email => email_value(rng),
+        &Shape::Number { min, max } => number_value(min, max, rng),
+        &Shape::Percent { min, max, allow_symbol, fraction } => percent_value(min, max, allow_symbol, fraction, rng),
+        Shape::Coordinate(CoordinateKind::Lat) => degrees_value(90.0, rng),
+        Shape::Coordinate(CoordinateKind::Lng) => degrees_value(180.0, rng),
+        Shape::LanguageCode => rng.pick(&LANGUAGE_TAGS).to_string(),
+        Shape::Timezone => rng.pick(&TIME_ZONES).to_string(),
+        &Shape::NationalId(country) => national_id_value(country, rng),
+        Shape::Path { windows, absolute, extension } => {
+            let (root, separator) = match (windows, absolute) {
+                (true, true) => ("C:\\", "\\"),
+                (true, false) => ("", "\\"),
+                (false, true) => ("/", "/"),
+                (false, false) => ("", "/"),
+            };
+            format!("{}assets{}{}-{}.{}", root, separator, rng.pick(&WORDS), 1 + rng.below(100), extension)
+        }
+        Shape::Hostname => format!("{}-{:02}.example.com", rng.pick(&WORDS), 1 + rng.below(20)),
+        &Shape::Base64(max) => {
+            let bytes: Vec<u8> = (0..1 + rng.below(max)).map(|_| rng.below(256) as u8).collect();
+            STANDARD.encode(bytes)
+        }
+        &Shape::MacAddress(format) => {
+            let bytes: Vec<String> = (0..6).map(|_| format!("{:02X}", rng.below(256))).collect();
+            match format {
+                MacFormat::Colon => bytes.join(":"),
+                MacFormat::Dash => bytes.join("-"),
+                MacFormat::Bare => bytes.concat(),
+            }
+        }
+        Shape::HexColor => format!("#{:06X}", rng.below(1 << 24)),
+        Shape::Semver { min, max } => semver_value(min, max, rng),
+        &Shape::Duration { min, max, format } => duration_value(rng.between(min, max), format),
+        &Shape::Date { first, last } => {
+            let (year, month, day) = civil_from_days(first + rng.between(0, (last - first) as u64) as i64);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        }
+        &Shape::Age { min, max, reference } => birth_date_value(min, max, reference, rng),
+        &Shape::CountryCode(alpha) => {
+            let (alpha2, alpha3) = *rng.pick(&ISO_3166);
+            if alpha == 2 { alpha2 } else { alpha3 }.to_string()
+        }
+        Shape::Coordinate(CoordinateKind::Pair) => format!("{},{}", degrees_value(90.0, rng), degrees_value(180.0, rng)),
+        Shape::Text(hint) => match hint {
+            TextHint::Email => email_value(rng),
+            TextHint::FirstName => rng.pick(&FIRST_NAMES).to_string(),
+            TextHint::LastName => rng.pick(&LAST_NAMES).to_string(),
+            TextHint::FullName => format!("{} {}", rng.pick(&FIRST_NAMES), rng.pick(&LAST_NAMES)),
+            TextHint::City => rng.pick(&CITIES).to_string(),
+            TextHint::Country => rng.pick(&COUNTRIES).to_string(),
+            // 555-01xx numbers are reserved for fiction
+            TextHint::Phone => format!("+1 555 01{:02}", rng.below(100)),
+            TextHint::Date => format!("2024-{:02}-{:02}", 1 + rng.below(12), 1 + rng.below(28)),
+            TextHint::Id => (row + 1).to_string(),
+            TextHint::Word => rng.pick(&WORDS).to_string(),
+        },
+    }
+}
+
+fn email_value(rng: &mut SplitMix64) -> String {
+    format!("{}.{}@example.com", rng.pick(&FIRST_NAMES).to_ascii_lowercase(), rng.pick(&LAST_NAMES).to_ascii_lowercase())
+}
+
+/// A national ID of `country` (alpha-2), random up to its check digit or
+/// letter, which is found by trying each in turn.
+fn national_id_value(country: &str, rng: &mut SplitMix64) -> String {
+    let digits = |rng: &mut SplitMix64, n: usize| -> String { (0..n).map(|_| char::from(b'0' + rng.below(10) as u8)).collect() };
+    let Some(check) = national_id_check(country) else { return String::new() };
+    loop {
+        let (prefix, finals): (String, &[u8]) = match country {
+            // Areas 001-665 are all issued
+            "US" => return format!("{:03}-{:02}-{:04}", 1 + rng.below(665), 1 + rng.below(99), 1 + rng.below(9999)),
+            "GB" => {
+                let prefix = rng.pick(&["AB", "CE", "JH", "PR", "WL"]);
+                let suffix = char::from(b'A' + rng.below(4) as u8);
+                let number = digits(rng, 6);
+                return format!("{} {} {} {} {}", prefix, &number[..2], &number[2..4], &number[4..], suffix);
+            }
+            "CA" => (format!("{}{}", 1 + rng.below(7), digits(rng, 7)), b"0123456789"),
+            "ES" => (digits(rng, 8), b"TRWAGMYFPDXBNJZSQVHLCKE"),
+            _ => (digits(rng, 8), b"0123456789"),
+        };
+        // Some prefixes have no valid completion (a BSN remainder of 10)
+        if let Some(value) = finals.iter().map(|&last| format!("{}{}", prefix, char::from(last))).find(|value| check(value)) {
+            return value;
+        }
+    }
+}
+
+/// A release between `min` and `max`, or one of them when they're
+/// pre-releases and nothing else fits.
+fn semver_value(min: &Version, max: &Version, rng: &mut SplitMix64) -> String {
+    let mut core = [0; 3];
+    // Whether the parts so far equal `min`'s or `max`'s, which then bound the next part
+    let (mut at_min, mut at_max) = (true, true);
+    for ((part, min_part), max_part) in core.iter_mut().zip([min.major, min.minor, min.patch]).zip([max.major, max.minor, max.patch]) {
+        let lo = if at_min { min_part } else { 0 };
+        let hi = if at_max { max_part } else { 20 }.max(lo);
+        *part = rng.between(lo, hi);
+        at_min &= *part == min_part;
+        at_max &= *part == max_part;
+    }
+    let version = Version::new(core[0], core[1], core[2]);
+    if version.cmp_precedence(min).is_lt() { min } else if version.cmp_precedence(max).is_gt() { max } else { &version }.to_string()
+}
+
+fn duration_value(seconds: u64, format: DurationFormat) -> String {
+    let (hours, minutes, seconds) = (seconds / 3_600, seconds / 60 % 60, seconds % 60);
+    let units = |h: &str, m: &str, s: &str| {
+        let parts: String = [(hours, h), (minutes, m), (seconds, s)].iter()
+            .filter(|&&(amount, _)| amount > 0)
+            .map(|(amount, unit)| format!("{}{}", amount, unit))
+            .collect();
+        if parts.is_empty() { format!("0{}", s) } else { parts }
+    };
+    match format {
+        DurationFormat::Clock => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+        DurationFormat::Units => units("h", "m", "s"),
+        DurationFormat::Iso8601 => format!("PT{}", units("H", "M", "S")),
+    }
+}
+
+/// A date rule's `min`, `max` and `now` as `day_number`s. Bounds that
+/// don't parse already failed to compile.
+fn date_bounds(min: &Option<String>, max: &Option<String>, now: &Option<String>) -> (Option<i64>, Option<i64>, i64) {
+    let now = now.as_deref().and_then(parse_date).unwrap_or_else(today);
+    let bound = |bound: &Option<String>| bound.as_deref().and_then(|bound| date_bound(bound, now)).map(day_number);
+    (bound(min), bound(max), day_number(now))
+}
+
+/// The day before the date rule's `min` or after its `max`, or a date on
+/// the wrong side of `now`.
+fn invalid_date(must_be: Option<DateConstraint>, min: &Option<String>, max: &Option<String>, now: &Option<String>) -> String {
+    let (min, max, now) = date_bounds(min, max, now);
+    let day = match (min, max, must_be) {
+        (_, Some(max), _) => max + 1,
+        (Some(min), _, _) => min - 1,
+        (_, _, Some(DateConstraint::Future)) => now,
+        (_, _, Some(_)) => now + 1,
+        (None, None, None) => return "31/12/2024".to_string(),
+    };
+    let (year, month, day) = civil_from_days(day);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A birth date giving an age in `[min, max]` (0 to 80 years when missing)
+/// on `reference`, falling on or before its day of the year.
+fn birth_date_value(min: Option<u32>, max: Option<u32>, reference: Date, rng: &mut SplitMix64) -> String {
+    let lo = min.unwrap_or(0);
+    let hi = max.unwrap_or(lo + 80).max(lo);
+    let age = lo + rng.below((hi - lo) as usize + 1) as u32;
+    let (year, month, day) = reference;
+    let birth_month = 1 + rng.below(month as usize) as u32;
+    let birth_day = 1 + rng.below(if birth_month == month { day as usize } else { 28 }) as u32;
+    format!("{:04}-{:02}-{:02}", year - i64::from(age), birth_month, birth_day)
+}
+
+/// Decimal degrees in `[-limit, limit]`, to 4 places (about 10 m).
+fn degrees_value(limit: f64, rng: &mut SplitMix64) -> String {
+    format!("{:.4}", (rng.unit() * 2.0 - 1.0) * limit)
+}
+
+/// A percentage in `[min, max]` (0 to 100 when missing), with a `%` when
+/// allowed and otherwise as a fraction if that's how bare numbers are read.
+fn percent_value(min: Option<f64>, max: Option<f64>, allow_symbol: bool, fraction: bool, rng: &mut SplitMix64) -> String {
+    let (lo, hi) = (min.unwrap_or(0.0), max.unwrap_or(100.0));
+    let percent = whole_steps(lo, hi, rng);
+    if allow_symbol {
+        format!("{}%", percent)
+    } else if fraction {
+        format!("{}", percent / 100.0)
+    } else {
+        format!("{}", percent)
+    }
+}
+
+/// A number in `[min, max]`: a whole number when the bounds are (or are
+/// missing), otherwise two decimals. A missing bound is 1000 from the other.
+fn number_value(min: Option<f64>, max: Option<f64>, rng: &mut SplitMix64) -> String {
+    let (lo, hi) = match (min, max) {
+        (Some(lo), Some(hi)) => (lo, hi.max(lo)),
+        (Some(lo), None) => (lo, lo + 1000.0),
+        (None, Some(hi)) => (hi - 1000.0, hi),
+        (None, None) => (0.0, 1000.0),
+    };
+    if lo.fract() == 0.0 && hi.fract() == 0.0 {
+        format!("{}", whole_steps(lo, hi, rng))
+    } else {
+        let value = between(lo, hi, rng);
+        // Rounding can step over a bound that isn't itself on two decimals
+        let rounded = (value * 100.0).round() / 100.0;
+        let value = if (lo..=hi).contains(&rounded) { rounded } else { value };
+        format!("{}", value)
+    }
+}
+
+/// `lo` plus a whole number up to `hi - lo`, or any value in `[lo, hi]`
+/// once the span is too wide to count in whole steps.
+fn whole_steps(lo: f64, hi: f64, rng: &mut SplitMix64) -> f64 {
+    // Past 2^53 not every whole number is a distinct `f64`
+    const MAX_STEPS: f64 = (1u64 << 53) as f64;
+    let span = (hi - lo).max(0.0);
+    if span < MAX_STEPS {
+        (lo + rng.between(0, span as u64) as f64).min(hi.max(lo))
+    } else {
+        between(lo, hi, rng)
+    }
+}
+
+/// Uniform in `[lo, hi]`, without computing `hi - lo`, which can overflow.
+fn between(lo: f64, hi: f64, rng: &mut SplitMix64) -> f64 {
+    let t = rng.unit();
+    (lo * (1.0 - t) + hi * t).clamp(lo, hi)
+}
+
+/// Appends a string matching `hir`, preferring readable ASCII characters.
+fn pattern_value(hir: &Hir, rng: &mut SplitMix64, out: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.push_str(&String::from_utf8_lossy(&literal.0)),
+        HirKind::Class(Class::Unicode(class)) => {
+            let ranges: Vec<(char, char)> = class.ranges().iter().map(|r| (r.start(), r.end())).collect();
+            if let Some(c) = class_char(&ranges, rng) {
+                out.push(c);
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges: Vec<(char, char)> = class.ranges().iter()
+                .filter(|r| r.start().is_ascii())
+                .map(|r| (r.start() as char, r.end().min(0x7f) as char))
+                .collect();
+            if let Some(c) = class_char(&ranges, rng) {
+                out.push(c);
+            }
+        }
+        HirKind::Repetition(rep) => {
+            let max = rep.max.unwrap_or(u32::MAX).min(rep.min.saturating_add(MAX_EXTRA_REPEATS));
+            let count = rep.min + rng.below((max - rep.min) as usize + 1) as u32;
+            for _ in 0..count {
+                pattern_value(&rep.sub, rng, out);
+            }
+        }
+        HirKind::Capture(capture) => pattern_value(&capture.sub, rng, out),
+        HirKind::Concat(parts) => {
+            for part in parts {
+                pattern_value(part, rng, out);
+            }
+        }
+        HirKind::Alternation(branches) => pattern_value(rng.pick(branches), rng, out),
+    }
+}
+
+/// A character from the class, drawn from its ASCII letters and digits if
+/// it has any, then from printable ASCII, then its first character.
+fn class_char(ranges: &[(char, char)], rng: &mut SplitMix64) -> Option<char> {
+    let in_class = |c: char| ranges.iter().any(|&(start, end)| (start..=end).contains(&c));
+    let printable: Vec<char> = (' '..='~').filter(|&c| in_class(c)).collect();
+    let alphanumeric: Vec<char> = printable.iter().copied().filter(char::is_ascii_alphanumeric).collect();
+    if !alphanumeric.is_empty() {
+        Some(*rng.pick(&alphanumeric))
+    } else if !printable.is_empty() {
+        Some(*rng.pick(&printable))
+    } else {
+        ranges.first().map(|&(start, _)| start)
+    }
+}
+
+/// A value that breaks rule `rule` (of the column's rules), for deliberately
+/// invalid records. Other rules of the column may fail too.
+fn invalid_value(rule_set: &RuleSet, col: usize, rule: &RuleType, rule_index: usize, record: &Vec<String>) -> String {
+    let specific = match rule {
+        RuleType::NotEmpty => String::new(),
+        RuleType::Number { max: Some(max), .. } => format!("{}", max + 1.0),
+        RuleType::Number { min: Some(min), .. } => format!("{}", min - 1.0),
+        RuleType::Number { .. } | RuleType::Percent { .. } => "n/a".to_string(),
+        RuleType::Email { .. } => "not-an-email".to_string(),
+        RuleType::OneOf { .. } => "not-an-option".to_string(),
+        RuleType::Coordinate { .. } => "999".to_string(),
+        // User-assigned, so never an official code
+        RuleType::CountryCode { .. } => "XX".to_string(),
+        // The usual mistake: a locale name rather than a tag
+        RuleType::LanguageCode { .. } => "en_US".to_string(),
+        RuleType::Timezone { .. } => "Europe/Springfield".to_string(),
+        RuleType::NationalId { .. } => "000-00-0000".to_string(),
+        // Too short for any country's format
+        RuleType::PostalCode { .. } => "1".to_string(),
+        // Mixes separators, so fails in either style
+        RuleType::Path { .. } => "assets\\mixed/up.txt".to_string(),
+        RuleType::Hostname { .. } => "-web-.example.com".to_string(),
+        RuleType::Base64 { .. } => "not base64!".to_string(),
+        // Five bytes
+        RuleType::MacAddress { .. } => "00:1A:2B:3C:4D".to_string(),
+        // One digit short
+        RuleType::HexColor { .. } => "#12345".to_string(),
+        // No patch number
+        RuleType::Semver { .. } => "1.2".to_string(),
+        // Minutes over 59 aren't a clock time
+        RuleType::Duration { .. } => "1:75".to_string(),
+        RuleType::Date { must_be, min, max, now, .. } => invalid_date(*must_be, min, max, now),
+        // Day zero of Excel's date serials
+        RuleType::Age { .. } => "1899-12-30".to_string(),
+        RuleType::Regex { .. } | RuleType::Anomaly { .. } | RuleType::DependsOn { .. } | RuleType::Custom { .. } => "!!invalid!!".to_string(),
+    };
+    let breaks = |value: &str| rule_set.validate_cell(col, value, record).any(|v| v.rule == rule_index);
+    [specific, String::new(), "invalid".to_string(), "0".to_string()]
+        .into_iter()
+        .find(|value| breaks(value))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validator;
+
+    fn generated_errors(rules_json: &str) -> usize {
+        let rules = parse_rules(rules_json).unwrap();
+        let csv = String::from_utf8(generate(&rules, 50, 7, 0.0).unwrap()).unwrap();
+        Validator::new(&csv, &rules, Default::default()).unwrap().summary().unwrap().total_errors
+    }
+
+    #[test]
+    fn extreme_bounds_generate_valid_values() {
+        let rules = r#"[
+            {"column": "n", "rules": [{"type": "number", "min": -1e300, "max": 1e300}]},
+            {"column": "f", "rules": [{"type": "number", "min": -1.5e308, "max": 1.5e308}]},
+            {"column": "p", "rules": [{"type": "percent", "min": -1e300, "max": 1e300}]},
+            {"column": "d", "rules": [{"type": "duration", "min": "0s", "max": "99999999999999999999999h"}]}
+        ]"#;
+        assert_eq!(generated_errors(rules), 0);
+    }
+
+    #[test]
+    fn narrow_bounds_generate_valid_values() {
+        let rules = r#"[
+            {"column": "n", "rules": [{"type": "number", "min": 5, "max": 5}]},
+            {"column": "d", "rules": [{"type": "duration", "min": "1h", "max": "1h"}]},
+            {"column": "day", "rules": [{"type": "date", "min": "2024-02-29", "max": "2024-02-29"}]}
+        ]"#;
+        assert_eq!(generated_errors(rules), 0);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::countries::ISO_3166;
+
+// --- BCP 47 Language Tags ---
+//
+// Structural check of language tags (RFC 5646) for the `languagecode` rule,
+// e.g. `en`, `pt-BR`, `zh-Hant`, `es-419`. Two-letter languages and regions
+// are also looked up in the ISO 639-1 and ISO 3166-1 lists; longer language
+// subtags, scripts, variants and extensions are only checked for shape, as
+// the full IANA registry is too large to compile in. Subtags are
+// case-insensitive.
+
+/// ISO 639-1 language codes, sorted.
+const ISO_639_1: [&str; 184] = [
+    "aa", "ab", "ae", "af", "ak", "am", "an", "ar", "as", "av", "ay", "az", "ba", "be", "bg", "bh",
+    "bi", "bm", "bn", "bo", "br", "bs", "ca", "ce", "ch", "co", "cr", "cs", "cu", "cv", "cy", "da",
+    "de", "dv", "dz", "ee", "el", "en", "eo", "es", "et", "eu", "fa", "ff", "fi", "fj", "fo", "fr",
+    "fy", "ga", "gd", "gl", "gn", "gu", "gv", "ha", "he", "hi", "ho", "hr", "ht", "hu", "hy", "hz",
+    "ia", "id", "ie", "ig", "ii", "ik", "io", "is", "it", "iu", "ja", "jv", "ka", "kg", "ki", "kj",
+    "kk", "kl", "km", "kn", "ko", "kr", "ks", "ku", "kv", "kw", "ky", "la", "lb", "lg", "li", "ln",
+    "lo", "lt", "lu", "lv", "mg", "mh", "mi", "mk", "ml", "mn", "mr", "ms", "mt", "my", "na", "nb",
+    "nd", "ne", "ng", "nl", "nn", "no", "nr", "nv", "ny", "oc", "oj", "om", "or", "os", "pa", "pi",
+    "pl", "ps", "pt", "qu", "rm", "rn", "ro", "ru", "rw", "sa", "sc", "sd", "se", "sg", "si", "sk",
+    "sl", "sm", "sn", "so", "sq", "sr", "ss", "st", "su", "sv", "sw", "ta", "te", "tg", "th", "ti",
+    "tk", "tl", "tn", "to", "tr", "ts", "tt", "tw", "ty", "ug", "uk", "ur", "uz", "ve", "vi", "vo",
+    "wa", "wo", "xh", "yi", "yo", "za", "zh", "zu",
+];
+
+/// Whether `tag` is a well-formed language tag with a known two-letter
+/// language and region.
+pub(crate) fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-').peekable();
+    let Some(language) = subtags.next() else { return false };
+    if language.eq_ignore_ascii_case("x") {
+        return is_private_use(subtags);
+    }
+    let known = match language.len() {
+        2 => is_alpha(language) && ISO_639_1.binary_search(&language.to_ascii_lowercase().as_str()).is_ok(),
+        // 4 letters is reserved
+        3 | 5..=8 => is_alpha(language),
+        _ => false,
+    };
+    if !known {
+        return false;
+    }
+    // Up to three extended language subtags, e.g. `zh-yue`
+    let mut extlangs = 0;
+    while language.len() <= 3 && extlangs < 3 && subtags.next_if(|s| s.len() == 3 && is_alpha(s)).is_some() {
+        extlangs += 1;
+    }
+    subtags.next_if(|s| s.len() == 4 && is_alpha(s));
+    subtags.next_if(|s| is_region(s));
+    while subtags.next_if(|s| is_variant(s)).is_some() {}
+    // Extensions: a singleton other than `x` and at least one subtag
+    while subtags.next_if(|s| s.len() == 1 && is_alphanumeric(s) && !s.eq_ignore_ascii_case("x")).is_some() {
+        if subtags.next_if(|s| is_extension(s)).is_none() {
+            return false;
+        }
+        while subtags.next_if(|s| is_extension(s)).is_some() {}
+    }
+    match subtags.next() {
+        None => true,
+        Some(s) if s.eq_ignore_ascii_case("x") => is_private_use(subtags),
+        Some(_) => false,
+    }
+}
+
+/// Two letters in ISO 3166-1, registered as an exception (`EU`, `EZ`, `UN`)
+/// or set aside for private use (`AA`, `QM`-`QZ`, `XA`-`XZ`, `ZZ`), or a
+/// three-digit UN M.49 area like `419`.
+fn is_region(subtag: &str) -> bool {
+    match subtag.len() {
+        2 => {
+            let code = subtag.to_ascii_uppercase();
+            let private_use = matches!(code.as_bytes(), [b'Q', b'M'..=b'Z'] | [b'X', b'A'..=b'Z']) || code == "AA" || code == "ZZ";
+            private_use
+                || matches!(code.as_str(), "EU" | "EZ" | "UN")
+                || ISO_3166.binary_search_by(|&(alpha2, _)| alpha2.cmp(code.as_str())).is_ok()
+        }
+        3 => subtag.bytes().all(|b| b.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+/// 5-8 letters or digits, or a digit and three more, e.g. `1996`.
+fn is_variant(subtag: &str) -> bool {
+    is_alphanumeric(subtag)
+        && match subtag.len() {
+            5..=8 => true,
+            4 => subtag.as_bytes()[0].is_ascii_digit(),
+            _ => false,
+        }
+}
+
+fn is_extension(subtag: &str) -> bool {
+    (2..=8).contains(&subtag.len()) && is_alphanumeric(subtag)
+}
+
+/// The subtags after an `x`: at least one, each 1-8 letters or digits.
+fn is_private_use<'a>(subtags: impl Iterator<Item = &'a str>) -> bool {
+    let mut count = 0;
+    for subtag in subtags {
+        if !(1..=8).contains(&subtag.len()) || !is_alphanumeric(subtag) {
+            return false;
+        }
+        count += 1;
+    }
+    count > 0
+}
+
+fn is_alpha(subtag: &str) -> bool {
+    subtag.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+fn is_alphanumeric(subtag: &str) -> bool {
+    subtag.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_5646_examples() {
+        // Section 2.1 and appendix A, with subtags outside the checked lists
+        let valid = [
+            "de", "EN", "fr-CA", "zh-Hant", "zh-Hans-CN", "sr-Latn-RS", "es-419", "pt-br", "zh-yue-HK", "ast",
+            "sl-rozaj-biske", "de-CH-1901", "hy-Latn-IT-arevela", "en-US-u-islamcal", "en-a-myext-b-another",
+            "de-CH-x-phonebk", "az-Arab-x-AZE-derbend", "x-whatever", "qaa-Qaaa-QM-x-southern", "en-QM", "en-EU",
+        ];
+        for tag in valid {
+            assert!(is_language_tag(tag), "{}", tag);
+        }
+        let invalid = [
+            "", "e", "englishes", "abcd", "zz", "en-", "-en", "en--US", "en-Q1", "en-XK1", "de-419-DE", "en-x",
+            "en-a", "a-DE", "ar-a-aaa-b-bbb-a-ccc-", "en-US-u", "en-123456789", "x-toolongsubtag", "fr_CA", "en-US ",
+        ];
+        for tag in invalid {
+            assert!(!is_language_tag(tag), "{}", tag);
+        }
+    }
+}
@@ -1,252 +1,2287 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+#[cfg(feature = "wasm")]
+use wasm_bindgen_futures::future_to_promise;
 use regex::Regex;
 
+mod arrow;
+mod bench;
+mod blake3;
+mod builder;
+mod compare;
+mod countries;
+mod encoding;
+mod engine;
+mod error;
+mod export;
+mod generate;
+mod languages;
+mod progress;
+mod mapping;
+mod mask;
+mod merge;
+mod national_id;
+mod outliers;
+mod parquet;
+mod pii;
+mod postal;
+mod query;
+mod report;
+mod review;
+mod rules;
+mod sample;
+mod session;
+mod sha256;
+mod sort;
+mod sql;
+mod store;
+mod stream;
+mod timezones;
+mod types;
+mod validator;
+mod verdict;
+mod xlsx;
+
+use engine::{RuleSet, Violation};
+use mapping::{combine_header_rows, map_headers};
+use review::Annotation;
+use progress::{now_ms, Cancelled, Progress};
+
+use mask::AppliedMask;
+// Only `CsvProcessor` and its helpers reach these
+#[cfg(feature = "wasm")]
+use {
+    encoding::decode,
+    export::{ExportCursor, ExportKind, SplitExport},
+    progress::{batches, yield_to_event_loop},
+    std::sync::Arc,
+};
+
+pub use bench::{benchmark, BenchmarkResult};
+pub use builder::CsvProcessorBuilder;
+pub use compare::{CellChange, ChangedRow, CompareReport, RemovedRow, RowRef};
+pub use encoding::EncodingIssue;
+pub use engine::{register_rule, CompiledRule, Record, Rule};
+pub use error::{ErrorKind, ValidatorError};
+pub use export::{ExportOptions, SplitExportResult, SplitOutput};
+pub use generate::generate_sample_data;
+pub use mapping::{ColumnMapping, HeaderJoin, HeaderMatching};
+pub use mask::{HashAlgorithm, MaskStrategy};
+pub use merge::MergeMode;
+pub use outliers::{Outlier, OutlierMethod, OutlierReport};
+pub use pii::{PiiFinding, PiiKind};
+pub use progress::{CancellationToken, ProgressFn};
+pub use query::{ConflictingValue, DependencyConflict, DuplicateGroup, Predicate, RowQuery, ValueCount};
+pub use review::{AnnotationEntry, ReviewStatus};
+pub use sample::SampleRows;
+pub use session::ValidationSession;
+pub use sort::{SortDirection, SortType};
+pub use sql::SqlDialect;
+pub use stream::{ChunkStats, StreamRow, StreamSummary, StreamValidator};
+pub use validator::{RowError, TypedRows, Validator};
+pub use verdict::{ThresholdCheck, Thresholds, Verdict};
+use store::RecordStore;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[cfg(all(feature = "parallel", feature = "wasm", target_arch = "wasm32"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+// Rows handed to each worker when validating in parallel
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNK_ROWS: usize = 16 * 1024;
+
+// Rows validated between two yields to the event loop in the async variants
+#[cfg(feature = "wasm")]
+const ASYNC_BATCH_ROWS: usize = 20_000;
+
 // --- Data Structures ---
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum RuleType {
     NotEmpty,
-    Number { min: Option<f64>, max: Option<f64> },
-    Email,
-    Regex { pattern: String },
+    /// `allow_empty` (here and on `email` and `regex`) passes blank cells,
+    /// for optional columns.
+    Number {
+        min: Option<f64>,
+        max: Option<f64>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    Email {
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    Regex {
+        pattern: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
     OneOf { options: Vec<String> },
+    /// A percentage, range-checked in percent (0-100 scale) however it is
+    /// written.
+    Percent {
+        min: Option<f64>,
+        max: Option<f64>,
+        /// Accept a trailing `%`, e.g. `85%`.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_symbol: bool,
+        /// Read bare numbers as fractions, so `0.85` is 85%; otherwise `85` is.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        fraction: bool,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// Decimal degrees, e.g. `51.5074` or, for a `pair`, `51.5074,-0.1278`.
+    Coordinate {
+        kind: CoordinateKind,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// An ISO 3166-1 country code, `alpha` (2 or 3) letters long and in
+    /// upper case, e.g. `GB` or `GBR`.
+    CountryCode {
+        alpha: u8,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A BCP 47 language tag, e.g. `en`, `pt-BR` or `zh-Hant`.
+    LanguageCode {
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// An IANA time zone name, e.g. `Europe/Berlin` or `UTC`. Names are
+    /// case-sensitive, as the tz database's are.
+    Timezone {
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A date, `YYYY-MM-DD` with any time after it ignored.
+    Date {
+        /// Where the date must fall relative to `now`, by calendar day.
+        must_be: Option<DateConstraint>,
+        /// Earliest and latest dates allowed, inclusive: `YYYY-MM-DD`, or
+        /// an offset from `now` in days, weeks, months or years such as
+        /// `-90d` or `+1y`, so a rules file for a recurring feed never goes
+        /// stale.
+        min: Option<String>,
+        max: Option<String>,
+        /// The `YYYY-MM-DD` the constraints are checked against; today
+        /// (UTC) by default.
+        now: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A birth date (`YYYY-MM-DD`, any time after it ignored) giving an age
+    /// in whole years within `min` and `max` on `reference_date` (the same
+    /// format; today by default), e.g. 18 to 120 to catch typo years and
+    /// Excel's `1899-12-30`.
+    Age {
+        min: Option<u32>,
+        max: Option<u32>,
+        reference_date: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A length of time such as `01:30:00`, `90m` or `PT1H30M`, within
+    /// `min` and `max` (durations in any of the formats).
+    Duration {
+        min: Option<String>,
+        max: Option<String>,
+        /// The formats accepted; all of them when empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        formats: Vec<DurationFormat>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A semantic version (`1.4.0`, `2.0.0-rc.1+build.5`) within `min` and
+    /// `max`, compared by SemVer precedence: build metadata is ignored and a
+    /// pre-release comes before its release.
+    Semver {
+        min: Option<String>,
+        max: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A `#RRGGBB` color in either case, e.g. `#1E90FF`.
+    HexColor {
+        /// Also `#RRGGBBAA`, and `#RGBA` with `allow_shorthand`.
+        #[serde(default)]
+        allow_alpha: bool,
+        /// Also `#RGB`.
+        #[serde(default)]
+        allow_shorthand: bool,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A 48-bit MAC address in either case, e.g. `00:1A:2B:3C:4D:5E`.
+    MacAddress {
+        /// The formats accepted; all of them when empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        formats: Vec<MacFormat>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// Standard base64 (`+` and `/`, padding optional) that decodes
+    /// cleanly, to at most `max_decoded_bytes`.
+    Base64 {
+        max_decoded_bytes: Option<usize>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A host name per RFC 1123: dot-separated labels of 1-63 letters,
+    /// digits and hyphens, not starting or ending with a hyphen, 253
+    /// characters at most. A trailing dot is allowed.
+    Hostname {
+        /// At least two labels, the last not all digits, so `localhost`
+        /// and IPv4 addresses fail.
+        #[serde(default)]
+        require_tld: bool,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A file path in the given `style`, e.g. `assets/logo.png`.
+    Path {
+        #[serde(default)]
+        style: PathStyle,
+        /// Unix paths from `/`; Windows paths from a drive root (`C:\`)
+        /// or a UNC share (`\\server\share`).
+        #[serde(default)]
+        must_be_absolute: bool,
+        /// Extensions the file name must end in, with or without the dot
+        /// and in any case; any (or none) when empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allowed_extensions: Vec<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A postal code in the format of `country` (ISO 3166-1 alpha-2 or
+    /// alpha-3). Letters may be in either case. One of `country` and
+    /// `country_column` is needed.
+    PostalCode {
+        country: Option<String>,
+        /// A column holding each row's country, as an ISO 3166-1 code,
+        /// whose format is used instead of `country`'s; rows where it is
+        /// blank fall back to `country`. Codes that are unknown or have no
+        /// known format (and blank ones with no `country`) aren't checked.
+        country_column: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A national ID number of `country`: a US SSN, UK National Insurance
+    /// number, Canadian SIN, Spanish DNI/NIE or Dutch BSN. Only the format
+    /// is checked (layout, ranges never issued, check digits), not whether
+    /// the number was issued or to whom.
+    NationalId {
+        country: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_empty: bool,
+    },
+    /// A number far from the rest of its column by `method`, e.g. a
+    /// 1,000,000 kg shipment among ones of 10-500 kg. `threshold` defaults
+    /// as in `detect_outliers`. The bounds are computed from the column
+    /// once the data is loaded, or when the rules change; edits are judged
+    /// against them without moving them. Blank and non-numeric cells pass.
+    /// Streams never hold the whole column, so `StreamValidator` rejects it.
+    Anomaly {
+        method: OutlierMethod,
+        threshold: Option<f64>,
+    },
+    /// The column is determined by `key_columns`: rows with the same key
+    /// must agree on it, e.g. every row of a `product_id` has the same
+    /// `product_name`. When they don't, all of the key's cells fail. Rows
+    /// with a blank key cell and blank cells aren't compared. The keys are
+    /// learnt again after every fix to the column or a key column, so
+    /// fixing the odd cell out clears the rest. Streams never hold the
+    /// whole column, so `StreamValidator` rejects it.
+    DependsOn {
+        key_columns: Vec<String>,
+    },
+    /// Any other `type`, compiled by the factory registered for it with
+    /// `register_rule`; `params` holds the rule's other keys.
+    #[serde(untagged)]
+    Custom {
+        #[serde(rename = "type")]
+        type_tag: String,
+        #[serde(flatten)]
+        params: serde_json::Map<String, serde_json::Value>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinateKind {
+    /// Latitude, -90 to 90.
+    Lat,
+    /// Longitude, -180 to 180.
+    Lng,
+    /// Latitude and longitude in one cell, separated by a comma.
+    Pair,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationFormat {
+    /// `H:MM:SS` or `H:MM`, hours unbounded, e.g. `37:30` for a week's
+    /// timesheet. Two parts are always hours and minutes, so give media
+    /// lengths as `0:04:05`.
+    Clock,
+    /// Amounts of days, hours, minutes and seconds, largest first and
+    /// optionally spaced, e.g. `90m`, `1h30m` or `1.5h`.
+    Units,
+    /// ISO 8601, e.g. `PT1H30M` or `P1DT12H`. Years and months aren't
+    /// accepted as their length varies.
+    Iso8601,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MacFormat {
+    /// `00:1A:2B:3C:4D:5E`
+    Colon,
+    /// `00-1A-2B-3C-4D-5E`
+    Dash,
+    /// `001A2B3C4D5E`
+    Bare,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PathStyle {
+    /// `/`-separated; a backslash is taken for a Windows path and fails.
+    Unix,
+    /// `\`-separated, without `<>:"|?*` outside a drive letter; a `/` is
+    /// taken for a Unix path and fails.
+    Windows,
+    /// Either, as long as one path doesn't mix them.
+    #[default]
+    Any,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DateConstraint {
+    /// Before today, e.g. a birth date.
+    Past,
+    /// After today, e.g. an expiry date.
+    Future,
+    /// Today or before, e.g. a delivery date; today itself passes.
+    TodayOrPast,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ColumnRule {
     pub column: String,
     pub rules: Vec<RuleType>,
+    /// Indices into `rules` that are switched off; see `set_rule_enabled`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<usize>,
 }
 
-#[derive(Serialize)]
+/// How the processor lays out cells in memory.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageLayout {
+    /// Row by row; best for row-oriented exports.
+    #[default]
+    Rows,
+    /// Column by column; per-column scans (validation, fixes, profiling)
+    /// touch contiguous memory, which pays off on wide files.
+    Columns,
+}
+
+/// Construction options, passed as JSON to `CsvProcessor::with_options`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ProcessorOptions {
+    pub layout: StorageLayout,
+    pub validation: ValidationOptions,
+    /// Lines making up the header (at least 1), e.g. 2 for a group row
+    /// above the field names. They are combined per column according to
+    /// `header_join`.
+    pub header_rows: usize,
+    pub header_join: HeaderJoin,
+    /// Trailing lines to set aside as footer (totals, summaries) instead of
+    /// loading them as records.
+    pub skip_footer_rows: usize,
+    /// Also set aside trailing rows whose first non-empty cell matches this
+    /// regex, e.g. `"^(Total|Closing balance)"`.
+    pub footer_pattern: Option<String>,
+    /// Drop unnamed columns that hold no values and ignore empty fields past
+    /// the last header, as left by tools that end every line with a
+    /// delimiter.
+    pub drop_empty_columns: bool,
+    /// Renames incoming headers (source -> canonical name) before rules are
+    /// applied, e.g. `{"E-mail": "email"}`. Also applies to `append_csv`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub column_mapping: ColumnMapping,
+    /// How rule `column` names find their header; exact by default.
+    pub header_matching: HeaderMatching,
+    /// What to do at load with columns no rule refers to.
+    pub unknown_columns: UnknownColumns,
+    /// Load `from_bytes` input that isn't valid UTF-8 by replacing the bad
+    /// bytes with U+FFFD; the affected cells are then reported as
+    /// "Encoding" issues. Off by default, refusing such files.
+    pub lossy_utf8: bool,
+    /// Accept a file with no header, or with a header but no records.
+    /// Refused by default with an "Empty File" error, since every summary
+    /// of it would be empty.
+    pub allow_empty: bool,
+    /// Refuse inputs over these sizes instead of running out of memory.
+    pub limits: Limits,
+}
+
+impl Default for ProcessorOptions {
+    fn default() -> ProcessorOptions {
+        ProcessorOptions {
+            layout: StorageLayout::default(),
+            validation: ValidationOptions::default(),
+            header_rows: 1,
+            header_join: HeaderJoin::default(),
+            skip_footer_rows: 0,
+            footer_pattern: None,
+            drop_empty_columns: false,
+            column_mapping: ColumnMapping::new(),
+            header_matching: HeaderMatching::default(),
+            unknown_columns: UnknownColumns::default(),
+            lossy_utf8: false,
+            allow_empty: false,
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Knobs for how validation runs. Set at construction (`validation` key of
+/// the options) or later with `set_validation_options`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ValidationOptions {
+    /// Stop once this many errors are found and mark the summary truncated.
+    pub max_errors: Option<usize>,
+    /// Report at most this many errors from any one row, listing rows with
+    /// more in `malformed_rows`, so a garbled row (wrong delimiter, shifted
+    /// columns) doesn't drown out the rest.
+    pub max_errors_per_row: Option<usize>,
+    /// Time every rule on every column and report it in `timings`. Rules
+    /// are then evaluated one at a time over each column, so a truncated
+    /// summary stops in rule order rather than row order.
+    pub instrument: bool,
+    /// How columns without any rules are reported in the summary.
+    pub unvalidated_columns: UnvalidatedColumns,
+    /// Report only the first failing rule of each cell, skipping the rest,
+    /// so an empty cell is "Required" and not also "Not a Number" and
+    /// "Invalid Email". Rules run in the order listed for the column.
+    pub short_circuit: bool,
+    /// Tokens meaning "no value", e.g. `["NA", "N/A", "-", "NULL"]`. Cells
+    /// holding exactly one fail `notempty` as if empty and are skipped by
+    /// every other rule. Exports can rewrite them, with empty cells, as
+    /// `null_value`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub null_values: Vec<String>,
+}
+
+/// Strictness about columns the rules don't cover, e.g. a field a supplier
+/// added without telling anyone.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnvalidatedColumns {
+    /// Pass them through silently.
+    #[default]
+    Ignore,
+    /// List them in the summary's `warnings`.
+    Warn,
+    /// Count each as one "Unvalidated Column" error.
+    Error,
+}
+
+/// Caps on what a load (or `append_csv`) accepts, for hosts that take files
+/// from untrusted uploads. Exceeding one fails with a "Limit Exceeded"
+/// error of kind `data`. All off by default.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct Limits {
+    /// Size of each input passed in, in bytes.
+    pub max_bytes: Option<usize>,
+    /// Records held in total, footer lines included while reading.
+    pub max_rows: Option<usize>,
+    pub max_columns: Option<usize>,
+    /// Size of any one cell, in bytes.
+    pub max_cell_bytes: Option<usize>,
+}
+
+impl Limits {
+    fn check_bytes(&self, bytes: usize) -> Result<(), ValidatorError> {
+        match self.max_bytes {
+            Some(max) if bytes > max => Err(limit_exceeded(format!("the input is {} bytes, over max_bytes ({})", bytes, max))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_rows(&self, rows: usize) -> Result<(), ValidatorError> {
+        match self.max_rows {
+            Some(max) if rows > max => Err(limit_exceeded(format!("more than {} records (max_rows)", max))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_columns(&self, columns: usize) -> Result<(), ValidatorError> {
+        match self.max_columns {
+            Some(max) if columns > max => Err(limit_exceeded(format!("the header has {} columns, over max_columns ({})", columns, max))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks every cell of a record; the error is for the first one over.
+    fn check_cells<'a>(&self, cells: impl IntoIterator<Item = &'a str>) -> Result<(), ValidatorError> {
+        let Some(max) = self.max_cell_bytes else { return Ok(()) };
+        match cells.into_iter().enumerate().find(|(_, cell)| cell.len() > max) {
+            Some((col, cell)) => Err(limit_exceeded(format!("field {} is {} bytes, over max_cell_bytes ({})", col + 1, cell.len(), max))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn limit_exceeded(detail: String) -> ValidatorError {
+    ValidatorError::new(ErrorKind::Data, format!("Limit Exceeded: {}", detail))
+}
+
+/// Policy for columns that no rule refers to, applied once at load so junk
+/// fields can be stripped before anything is exported.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownColumns {
+    /// Keep them.
+    #[default]
+    Ignore,
+    /// Keep them and list them in the summary's `warnings`.
+    Warn,
+    /// Remove them from the data (and from later `append_csv` parts), noting
+    /// each in the summary's `warnings`.
+    Drop,
+    /// Refuse to load the file.
+    Reject,
+}
+
+#[derive(Serialize, Default)]
 pub struct ErrorSummary {
     // column_name -> { error_type -> count }
     pub stats: HashMap<String, HashMap<String, usize>>,
     // column_name -> { error_type -> example_value }
     pub examples: HashMap<String, HashMap<String, String>>,
+    // Violations: one per failed rule per cell, so a cell failing three
+    // rules counts three times
     pub total_errors: usize,
+    // Cells and rows with at least one error. Like `stats`, they stop where
+    // a `truncated` summary stops; "Unvalidated Column" errors belong to
+    // no cell
+    pub invalid_cells: usize,
+    pub invalid_rows: usize,
+    // Set when validation stopped early at `max_errors`
+    pub truncated: bool,
+    // Problems with the file as a whole rather than any one cell
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    // Cells that held invalid UTF-8 at load (see `lossy_utf8`), also
+    // counted as "Encoding" errors
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub encoding_issues: Vec<EncodingIssue>,
+    // 1-based numbers of rows with more errors than `max_errors_per_row`,
+    // of which only that many were counted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub malformed_rows: Vec<usize>,
+    // Only present when `instrument` is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Vec<RuleTiming>>,
+    // Records covered, the denominator of `of_rows` rates
+    pub row_count: usize,
+    // column_name -> the `stats` counts as rates, for columns with errors
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub rates: HashMap<String, ErrorRates>,
+}
+
+/// A column's errors as shares of its non-empty cells and of all rows.
+/// `of_non_empty` can pass 1 when empty cells fail (e.g. `notempty`), and
+/// both only cover the errors counted when the summary is `truncated`.
+#[derive(Serialize)]
+pub struct ErrorRates {
+    pub non_empty_cells: usize,
+    pub of_non_empty: f64,
+    pub of_rows: f64,
+    /// error type -> rates of that error alone
+    pub by_type: HashMap<String, ErrorRate>,
 }
 
+#[derive(Serialize)]
+pub struct ErrorRate {
+    pub of_non_empty: f64,
+    pub of_rows: f64,
+}
+
+impl ErrorRate {
+    fn new(errors: usize, non_empty_cells: usize, row_count: usize) -> ErrorRate {
+        let share = |total: usize| if total == 0 { 0.0 } else { errors as f64 / total as f64 };
+        ErrorRate { of_non_empty: share(non_empty_cells), of_rows: share(row_count) }
+    }
+}
+
+/// Time spent evaluating one rule of one column.
+#[derive(Serialize)]
+pub struct RuleTiming {
+    pub column: String,
+    /// Position of the rule in the column's rule list.
+    pub rule_index: usize,
+    /// The rule's `type` tag, e.g. "regex".
+    pub rule: &'static str,
+    pub millis: f64,
+    pub errors: usize,
+}
+
+impl ErrorSummary {
+    fn record(&mut self, col_name: &str, error_type: &str, value: &str) {
+        self.total_errors += 1;
+        let col_stats = self.stats.entry(col_name.to_string()).or_default();
+        *col_stats.entry(error_type.to_string()).or_insert(0) += 1;
+
+        // Only save the first example for this error type
+        let col_examples = self.examples.entry(col_name.to_string()).or_default();
+        col_examples.entry(error_type.to_string()).or_insert_with(|| value.to_string());
+    }
+
+    /// Counts a newly invalid cell in row `row`, and the row too unless
+    /// `invalid` shows it already had one.
+    fn mark_invalid(&mut self, invalid: &mut [bool], row: usize) {
+        self.invalid_cells += 1;
+        if !invalid[row] {
+            invalid[row] = true;
+            self.invalid_rows += 1;
+        }
+    }
+
+    /// Counts an error in row `row` against `max_errors_per_row` for the
+    /// column-at-a-time summaries, returning false once the row is over it
+    /// and listing it in `malformed_rows` the first time.
+    fn within_row_cap(&mut self, row_errors: &mut [usize], row: usize, row_number: usize, options: &ValidationOptions) -> bool {
+        let Some(max) = options.max_errors_per_row else { return true };
+        row_errors[row] += 1;
+        if row_errors[row] == max + 1 {
+            self.malformed_rows.push(row_number);
+        }
+        row_errors[row] <= max
+    }
+
+    /// Sets `row_count` and fills `rates` from the counts in `stats`.
+    fn set_rates(&mut self, row_count: usize, non_empty_cells: impl Fn(&str) -> usize) {
+        self.row_count = row_count;
+        self.rates = self.stats.iter()
+            .map(|(col, counts)| {
+                let non_empty = non_empty_cells(col);
+                let total = ErrorRate::new(counts.values().sum(), non_empty, row_count);
+                let by_type = counts.iter()
+                    .map(|(error_type, &count)| (error_type.clone(), ErrorRate::new(count, non_empty, row_count)))
+                    .collect();
+                (col.clone(), ErrorRates { non_empty_cells: non_empty, of_non_empty: total.of_non_empty, of_rows: total.of_rows, by_type })
+            })
+            .collect();
+    }
+
+    /// Folds in the summary of a later block of rows. Examples already
+    /// recorded win, so the result matches a single sequential pass.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    fn merge(mut self, other: ErrorSummary) -> ErrorSummary {
+        for (col, counts) in other.stats {
+            let col_stats = self.stats.entry(col).or_default();
+            for (etype, count) in counts {
+                *col_stats.entry(etype).or_insert(0) += count;
+            }
+        }
+        for (col, samples) in other.examples {
+            let col_examples = self.examples.entry(col).or_default();
+            for (etype, value) in samples {
+                col_examples.entry(etype).or_insert(value);
+            }
+        }
+        self.total_errors += other.total_errors;
+        self.invalid_cells += other.invalid_cells;
+        self.invalid_rows += other.invalid_rows;
+        self.malformed_rows.extend(other.malformed_rows);
+        self.truncated |= other.truncated;
+        self
+    }
+}
+
+/// Dataset shape and memory footprint, so the host can warn before the
+/// WASM heap runs out.
+#[derive(Serialize)]
+pub struct Metrics {
+    pub row_count: usize,
+    pub column_count: usize,
+    pub rule_count: usize,
+    /// Approximate bytes held by the record storage.
+    pub record_bytes: usize,
+    /// Bytes held by the incremental revalidation cache (0 until the first fix).
+    pub cache_bytes: usize,
+}
+
+/// What `apply_bulk_fix_detailed` returns.
+#[derive(Serialize)]
+pub struct FixResult {
+    pub column: String,
+    /// Cells replaced; 0 when the column doesn't exist.
+    pub rows_changed: usize,
+    /// Errors left in the whole file after the fix.
+    pub total_errors: usize,
+}
+
+/// Snapshot of a processor (data, rules and any fixes already applied),
+/// used to persist a review session and resume it later.
+#[cfg(feature = "wasm")]
+#[derive(Serialize, Deserialize)]
+struct ProcessorState {
+    version: u32,
+    headers: Vec<String>,
+    records: RecordStore,
+    rules: Vec<ColumnRule>,
+    #[serde(default)]
+    options: ProcessorOptions,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    origins: Vec<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<u32, Vec<Annotation>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    footer: Vec<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    unknown_columns: Vec<String>,
+    #[serde(default)]
+    lossy_decoded: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    masks: Vec<AppliedMask>,
+}
+
+#[cfg(feature = "wasm")]
+const STATE_VERSION: u32 = 1;
+
 // --- The Stateful Processor ---
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub struct CsvProcessor {
-    headers: Vec<String>,
-    records: Vec<Vec<String>>, 
+    // Shared with in-flight async calls; edits copy it if one is running
+    data: Arc<Dataset>,
     rules: Vec<ColumnRule>,
-    rule_map: HashMap<String, Vec<RuleType>>,
+    options: ProcessorOptions,
+    // Built lazily on the first edit, then patched cell by cell
+    error_cache: Option<ErrorCache>,
+    on_progress: Option<js_sys::Function>,
+    cancel_token: CancellationToken,
+    export_cursor: Option<ExportCursor>,
 }
 
+/// The loaded table and the rules compiled against it. Kept apart from the
+/// JS-facing session state so worker threads and async tasks can share it.
+#[derive(Clone)]
+struct Dataset {
+    headers: Vec<String>,
+    records: RecordStore,
+    rule_set: RuleSet,
+    // Original index of each row once they've been reordered; empty until then
+    origins: Vec<u32>,
+    // Review annotations by original row index
+    annotations: BTreeMap<u32, Vec<Annotation>>,
+    // Footer lines set aside at load, kept out of validation and exports
+    footer: Vec<Vec<String>>,
+    // Columns found at load that no rule refers to (kept or dropped per the
+    // `unknown_columns` policy); empty under `ignore`
+    unknown_columns: Vec<String>,
+    // Set when invalid UTF-8 was replaced at load, so U+FFFD in a cell marks
+    // an encoding issue
+    lossy_decoded: bool,
+    // Every `mask_column` call so far, in order
+    masks: Vec<AppliedMask>,
+}
+
+/// Per-cell failed-rule counts, stored per column (`None` for columns without
+/// rules) so edits only revalidate the cells they touch.
+#[cfg(feature = "wasm")]
+struct ErrorCache {
+    columns: Vec<Option<Vec<u16>>>,
+    total: usize,
+}
+
+#[cfg(feature = "wasm")]
+impl ErrorCache {
+    /// Recounts `rows` of column `col` after their cells changed, and the
+    /// cells in those rows whose rules read `col`. Columns in `refit` (see
+    /// `Dataset::refit_edited`) are recounted in full.
+    fn revalidate(&mut self, data: &Dataset, col: usize, rows: &[usize], refit: &[usize]) {
+        let all: Vec<usize> = if refit.is_empty() { Vec::new() } else { (0..data.records.len()).collect() };
+        for col in std::iter::once(col).chain(data.rule_set.readers(col)) {
+            let Some(counts) = self.columns[col].as_mut() else { continue };
+            let rows = if refit.contains(&col) { &all[..] } else { rows };
+            for &row in rows {
+                let record = data.records.row(row);
+                let count = data.rule_set.validate_cell(col, record.get(col), &record).count() as u16;
+                self.total = self.total - counts[row] as usize + count as usize;
+                counts[row] = count;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 impl CsvProcessor {
 
-    pub fn get_content_as_csv(&self) -> Result<String, JsValue> {
-        let mut wtr = csv::Writer::from_writer(vec![]);
-        
-        // Write headers
-        wtr.write_record(&self.headers).map_err(|e| JsValue::from_str(&e.to_string()))?;
-        
-        // Write all records (including fixed ones)
-        for record in &self.records {
-            wtr.write_record(record).map_err(|e| JsValue::from_str(&e.to_string()))?;
-        }
-        
-        // Return string
-        String::from_utf8(wtr.into_inner().unwrap()).map_err(|e| JsValue::from_str(&e.to_string()))
-    }
-    
+    /// Every record with its current values (fixes included), valid or not,
+    /// as CSV. `options_json` is an optional `ExportOptions` object.
+    pub fn to_csv(&self, options_json: Option<String>) -> Result<String, JsValue> {
+        String::from_utf8(self.content_as_bytes(options_json)?).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Older name of `to_csv`.
+    pub fn get_content_as_csv(&self, options_json: Option<String>) -> Result<String, JsValue> {
+        self.to_csv(options_json)
+    }
+
+    /// `to_csv` as UTF-8 bytes (a `Uint8Array` in JS).
+    pub fn get_content_as_bytes(&self, options_json: Option<String>) -> Result<Vec<u8>, JsValue> {
+        self.content_as_bytes(options_json)
+    }
+
+    fn content_as_bytes(&self, options_json: Option<String>) -> Result<Vec<u8>, JsValue> {
+        let options = ExportOptions::from_json(options_json)?;
+        let progress = self.progress("export");
+        let bytes = self.data.write_all(&options, &progress)?;
+        progress.finish();
+        Ok(bytes)
+    }
+
     #[wasm_bindgen(constructor)]
     pub fn new(csv_data: &str, rules_json: &str) -> Result<CsvProcessor, JsValue> {
-        let rules: Vec<ColumnRule> = serde_json::from_str(rules_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid Rules JSON: {}", e)))?;
+        CsvProcessor::with_options(csv_data, rules_json, "{}", None)
+    }
 
-        let mut rule_map = HashMap::new();
-        for r in &rules {
-            rule_map.insert(r.column.clone(), r.rules.clone());
-        }
+    /// Like the constructor, with a `ProcessorOptions` JSON object as the
+    /// third argument (e.g. `{"layout": "columns"}`) and an optional
+    /// `on_progress(percent, phase)` callback, which is kept for later calls.
+    ///
+    /// Failures are thrown as `Error` objects with a `kind` and, for a
+    /// malformed file, the `byte_offset`, `line`, `record` and `snippet` of
+    /// the offending record (see `error.rs`).
+    pub fn with_options(csv_data: &str, rules_json: &str, options_json: &str, on_progress: Option<js_sys::Function>) -> Result<CsvProcessor, JsValue> {
+        let options = parse_options(options_json)?;
+        CsvProcessor::load(csv_data, parse_rules(rules_json)?, options, on_progress)
+    }
+
+    /// Like `with_options`, for the raw file bytes (a `Uint8Array`). They
+    /// must be UTF-8 unless the `lossy_utf8` option is set.
+    pub fn from_bytes(bytes: &[u8], rules_json: &str, options_json: &str, on_progress: Option<js_sys::Function>) -> Result<CsvProcessor, JsValue> {
+        let options = parse_options(options_json)?;
+        CsvProcessor::load_bytes(bytes, parse_rules(rules_json)?, options, on_progress)
+    }
 
-        let mut reader = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(csv_data.as_bytes());
+    /// Parses more rows onto the end of the data, e.g. the next part of a
+    /// multi-part upload. The header must have the same columns as the loaded
+    /// one, in any order. On failure nothing is appended. Returns the number
+    /// of rows added.
+    pub fn append_csv(&mut self, csv_data: &str) -> Result<usize, JsValue> {
+        self.options.limits.check_bytes(csv_data.len())?;
+        let mut reader = csv_reader(csv_data, &self.options);
+        let headers = read_headers(&mut reader, &self.options).map_err(|e| e.with_snippet(csv_data))?;
+        let options = &self.options;
+        let columns = match_headers(&self.data.headers, &headers, |h| {
+            (options.drop_empty_columns && is_unnamed(h))
+                || (options.unknown_columns == UnknownColumns::Drop && !is_known(h, &self.rules, options))
+        })?;
 
-        let headers = reader
-            .headers()
-            .map_err(|e| JsValue::from_str(&format!("Header Error: {}", e)))?
-            .iter()
-            .map(|h| h.to_string())
+        self.cancel_token.reset();
+        let progress = Progress::new(self.on_progress.as_ref(), "parse").cancellable(&self.cancel_token);
+        let data = Arc::make_mut(&mut self.data);
+        let start = data.records.len();
+        let footer = match read_body(&mut reader, csv_data.len(), &mut data.records, Some(&columns), &self.options, &progress) {
+            Ok(footer) => footer,
+            Err(e) => {
+                data.records.truncate(start);
+                return Err(e.with_snippet(csv_data).into());
+            }
+        };
+        data.footer.extend(footer);
+        progress.finish();
+
+        let end = data.records.len();
+        if !data.origins.is_empty() {
+            data.origins.extend(start as u32..end as u32);
+        }
+        let refit: Vec<usize> = (0..data.headers.len())
+            .filter(|&col| data.rule_set.refit_edited(col, &data.records))
             .collect();
+        if let Some(cache) = self.error_cache.as_mut() {
+            for (col, counts) in cache.columns.iter_mut().enumerate() {
+                let Some(counts) = counts else { continue };
+                let rows = if refit.contains(&col) { 0..end } else { start..end };
+                if rows.start == 0 {
+                    cache.total -= counts.drain(..).map(|n| n as usize).sum::<usize>();
+                }
+                for row in rows {
+                    let count = data.rule_set.validate_cell(col, data.records.get(row, col), &data.records.row(row)).count() as u16;
+                    cache.total += count as usize;
+                    counts.push(count);
+                }
+            }
+        }
+        Ok(end - start)
+    }
+
+    /// A new processor holding this processor's rows followed by `other`'s,
+    /// e.g. to clean files split by region as one. `mode` is `strict`
+    /// (default; both need the same columns, in any order) or `union`
+    /// (columns missing from one side are left empty). Rules are combined
+    /// per column and must agree where both define a column. Review
+    /// annotations carry over; row numbers start afresh.
+    pub fn merge(&self, other: &CsvProcessor, mode: Option<String>) -> Result<CsvProcessor, JsValue> {
+        let mode = mode.as_deref().map_or(Ok(MergeMode::Strict), MergeMode::parse)?;
+        let (data, rules) = merge::merge((&self.data, &self.rules), (&other.data, &other.rules), &self.options, mode)?;
+        Ok(CsvProcessor::from_dataset(data, rules, self.options.clone()))
+    }
+
+    /// Sets (or clears, when called with no argument) the
+    /// `on_progress(percent, phase)` callback used by long operations.
+    pub fn set_progress_callback(&mut self, on_progress: Option<js_sys::Function>) {
+        self.on_progress = on_progress;
+    }
+
+    pub fn get_headers(&self) -> Vec<String> {
+        self.data.headers.clone()
+    }
+
+    pub fn get_row_count(&self) -> usize {
+        self.data.records.len()
+    }
+
+    /// Whether there are no records, as a file loaded with `allow_empty`
+    /// may have.
+    pub fn is_empty(&self) -> bool {
+        self.data.records.is_empty()
+    }
+
+    pub fn get_column_count(&self) -> usize {
+        self.data.headers.len()
+    }
 
-        let mut records = Vec::new();
-        for result in reader.records() {
-            let record = result.map_err(|e| JsValue::from_str(&format!("CSV Parse Error: {}", e)))?;
-            records.push(record.iter().map(|s| s.to_string()).collect());
+    #[wasm_bindgen(unchecked_return_type = "Metrics")]
+    pub fn get_metrics(&self) -> Result<JsValue, JsValue> {
+        let cache_bytes = self.error_cache.as_ref().map_or(0, |cache| {
+            cache.columns.iter().flatten().map(|c| c.capacity() * std::mem::size_of::<u16>()).sum()
+        });
+        let metrics = Metrics {
+            row_count: self.data.records.len(),
+            column_count: self.data.headers.len(),
+            rule_count: self.rules.iter().map(|r| r.rules.len()).sum(),
+            record_bytes: self.data.records.byte_size(),
+            cache_bytes,
+        };
+        Ok(serde_wasm_bindgen::to_value(&metrics)?)
+    }
+
+    /// Replaces the `ValidationOptions` used by subsequent calls.
+    pub fn set_validation_options(&mut self, options_json: &str) -> Result<(), JsValue> {
+        let validation: ValidationOptions = serde_json::from_str(options_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid Options JSON: {}", e)))?;
+        if validation.short_circuit != self.data.rule_set.short_circuit() || validation.null_values != self.data.rule_set.null_values() {
+            let data = Arc::make_mut(&mut self.data);
+            data.rule_set.set_options(&validation);
+            // Null tokens are left out of column statistics
+            data.rule_set.fit(&data.records);
+            self.error_cache = None;
         }
+        self.options.validation = validation;
+        Ok(())
+    }
 
-        Ok(CsvProcessor {
-            headers,
-            records,
-            rules,
-            rule_map,
+    /// Renames loaded headers with a `{"source": "canonical"}` JSON object
+    /// (see `ProcessorOptions::column_mapping`) and re-applies the rules to
+    /// the new names. The mapping is kept for later `append_csv` calls.
+    /// Returns the resulting headers.
+    pub fn apply_column_mapping(&mut self, mapping_json: &str) -> Result<Vec<String>, JsValue> {
+        let mapping: ColumnMapping = serde_json::from_str(mapping_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid Column Mapping JSON: {}", e)))?;
+        let headers = map_headers(&self.data.headers, &mapping).map_err(|e| JsValue::from_str(&e))?;
+        let mut rule_set = RuleSet::new(&headers, &self.rules, &self.options.header_matching).map_err(|e| JsValue::from_str(&e))?;
+        rule_set.set_options(&self.options.validation);
+        rule_set.fit(&self.data.records);
+
+        let data = Arc::make_mut(&mut self.data);
+        for annotation in data.annotations.values_mut().flatten() {
+            if let Some(column) = annotation.column.as_mut() {
+                if let Some(col) = data.headers.iter().position(|h| h == column) {
+                    *column = headers[col].clone();
+                }
+            }
+        }
+        data.headers = headers;
+        data.rule_set = rule_set;
+        // Columns may have gained or lost rules
+        self.error_cache = None;
+        self.options.column_mapping.extend(mapping);
+        Ok(self.data.headers.clone())
+    }
+
+    /// Switches rule `rule_index` (its position in the column's `rules`
+    /// list) of `column` off or back on, e.g. to silence a noisy rule while
+    /// reviewing. Later summaries, exports and edits use the new setting
+    /// without reloading; it is kept in `save_state` as the rule's
+    /// `disabled` list.
+    pub fn set_rule_enabled(&mut self, column: &str, rule_index: usize, enabled: bool) -> Result<(), JsValue> {
+        self.edit_rules(column, false, |rule| {
+            check_rule_index(rule, rule_index)?;
+            rule.disabled.retain(|&idx| idx != rule_index);
+            if !enabled {
+                rule.disabled.push(rule_index);
+                rule.disabled.sort_unstable();
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Appends a rule, a `RuleType` JSON object such as `{"type": "number",
+    /// "min": 0}`, to the rules of `column` (which needn't have any yet).
+    /// Only that column is revalidated. Returns its error count afterwards.
+    pub fn add_rule(&mut self, column: &str, rule_json: &str) -> Result<usize, JsValue> {
+        let new_rule: RuleType = serde_json::from_str(rule_json)
+            .map_err(|e| ValidatorError::new(ErrorKind::Rules, format!("Invalid Rule JSON: {}", e)))?;
+        self.edit_rules(column, true, |rule| {
+            rule.rules.push(new_rule);
+            Ok(())
         })
     }
 
+    /// Removes rule `rule_index` (its position in the column's `rules` list;
+    /// later rules move up one) from `column`. Only that column is
+    /// revalidated. Returns its error count afterwards.
+    pub fn remove_rule(&mut self, column: &str, rule_index: usize) -> Result<usize, JsValue> {
+        self.edit_rules(column, false, |rule| {
+            check_rule_index(rule, rule_index)?;
+            rule.rules.remove(rule_index);
+            rule.disabled = rule.disabled.iter()
+                .filter(|&&idx| idx != rule_index)
+                .map(|&idx| if idx > rule_index { idx - 1 } else { idx })
+                .collect();
+            Ok(())
+        })
+    }
+
+    /// Footer lines set aside at load by `skip_footer_rows` or
+    /// `footer_pattern`, as arrays of cells. They are neither validated nor
+    /// exported.
+    pub fn get_footer_rows(&self) -> Vec<js_sys::Array> {
+        self.data.footer.iter()
+            .map(|row| row.iter().map(|cell| JsValue::from_str(cell)).collect())
+            .collect()
+    }
+
+    /// Columns found at load that no rule refers to: the ones kept under the
+    /// `warn` policy, or the ones removed under `drop`.
+    pub fn get_unknown_columns(&self) -> Vec<String> {
+        self.data.unknown_columns.clone()
+    }
+
+    /// Rule `column` names that match no header, so their rules never run.
+    pub fn get_unmatched_rule_columns(&self) -> Vec<String> {
+        let matching = &self.options.header_matching;
+        self.rules.iter()
+            .filter(|r| !self.data.headers.iter().any(|h| matching.matches(h, &r.column)))
+            .map(|r| r.column.clone())
+            .collect()
+    }
+
+    /// Aborts the running validation or export at the next row batch. The
+    /// interrupted call throws "Operation Cancelled"; loaded data is kept.
+    pub fn cancel(&self) {
+        self.cancel_token.cancel();
+    }
+
+    /// The token behind `cancel()`, for code that has no processor handle
+    /// (e.g. another worker or an abort button's closure).
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel_token.clone()
+    }
+
+    /// Serializes the current data, rules and applied fixes into a JSON blob
+    /// that can be stored (e.g. in IndexedDB) and passed to `restore` later.
+    pub fn save_state(&self) -> Result<String, JsValue> {
+        let state = ProcessorState {
+            version: STATE_VERSION,
+            headers: self.data.headers.clone(),
+            records: self.data.records.clone(),
+            rules: self.rules.clone(),
+            options: self.options.clone(),
+            origins: self.data.origins.clone(),
+            annotations: self.data.annotations.clone(),
+            footer: self.data.footer.clone(),
+            unknown_columns: self.data.unknown_columns.clone(),
+            lossy_decoded: self.data.lossy_decoded,
+            masks: self.data.masks.clone(),
+        };
+        serde_json::to_string(&state).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Rebuilds a processor from a blob produced by `save_state`.
+    pub fn restore(blob: &str) -> Result<CsvProcessor, JsValue> {
+        let state: ProcessorState = serde_json::from_str(blob)
+            .map_err(|e| JsValue::from_str(&format!("Invalid State: {}", e)))?;
+        if state.version != STATE_VERSION {
+            return Err(JsValue::from_str(&format!("Unsupported State Version: {}", state.version)));
+        }
+        if !state.origins.is_empty() && state.origins.len() != state.records.len() {
+            return Err(JsValue::from_str("Row origins do not match records"));
+        }
+        let mut processor = CsvProcessor::from_parts(state.headers, state.records, state.rules, state.options)?;
+        let data = Arc::make_mut(&mut processor.data);
+        data.origins = state.origins;
+        data.annotations = state.annotations;
+        data.footer = state.footer;
+        data.unknown_columns = state.unknown_columns;
+        data.lossy_decoded = state.lossy_decoded;
+        data.masks = state.masks;
+        Ok(processor)
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "ErrorSummary")]
     pub fn get_error_summary(&self) -> Result<JsValue, JsValue> {
-        let mut stats: HashMap<String, HashMap<String, usize>> = HashMap::new();
-        let mut examples: HashMap<String, HashMap<String, String>> = HashMap::new();
-        let mut total_errors = 0;
-
-        let email_regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
-
-        for record in self.records.iter() {
-            for (col_idx, value) in record.iter().enumerate() {
-                if let Some(col_name) = self.headers.get(col_idx) {
-                    if let Some(rules) = self.rule_map.get(col_name) {
-                        for rule in rules {
-                            let error_type = match rule {
-                                RuleType::NotEmpty => if value.trim().is_empty() { Some("Required") } else { None },
-                                RuleType::Number { min, max } => {
-                                    match value.parse::<f64>() {
-                                        Ok(num) => {
-                                            if min.map_or(false, |m| num < m) { Some("Min Value") }
-                                            else if max.map_or(false, |m| num > m) { Some("Max Value") }
-                                            else { None }
-                                        },
-                                        Err(_) => Some("Not a Number")
-                                    }
-                                },
-                                RuleType::Email => if !email_regex.is_match(value) { Some("Invalid Email") } else { None },
-                                RuleType::Regex { pattern } => {
-                                     if let Ok(re) = Regex::new(pattern) {
-                                         if !re.is_match(value) { Some("Pattern Mismatch") } else { None }
-                                     } else { None }
-                                },
-                                RuleType::OneOf { options } => if !options.contains(value) { Some("Invalid Option") } else { None },
-                            };
-
-                            if let Some(etype) = error_type {
-                                total_errors += 1;
-                                let col_stats = stats.entry(col_name.clone()).or_insert_with(HashMap::new);
-                                *col_stats.entry(etype.to_string()).or_insert(0) += 1;
-
-                                // Only save the first example for this error type
-                                let col_examples = examples.entry(col_name.clone()).or_insert_with(HashMap::new);
-                                col_examples.entry(etype.to_string()).or_insert(value.clone());
-                            }
-                        }
-                    }
-                }
+        let progress = self.progress("validate");
+        let summary = self.data.error_summary(&self.options, &progress, &self.cancel_token)?;
+        progress.finish();
+
+        //New: Use json_compatible() to force HashMaps into Objects
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        summary.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Accepts or rejects the file against `thresholds_json`, a `Thresholds`
+    /// object such as `{"max_invalid_fraction": 0.005, "columns": {"id": 0},
+    /// "no_structural_errors": true}`. Returns `{accepted, row_count,
+    /// invalid_rows, total_errors, checks, structural_errors}`, with one
+    /// `{check, column?, limit, actual, passed}` per configured limit; the
+    /// file is accepted when every check passes.
+    #[wasm_bindgen(unchecked_return_type = "Verdict")]
+    pub fn evaluate(&self, thresholds_json: &str) -> Result<JsValue, JsValue> {
+        let thresholds = Thresholds::from_json(thresholds_json)?;
+        let progress = self.progress("validate");
+        let verdict = self.data.evaluate(&thresholds, &self.options, &progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        verdict.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Like `get_error_summary`, but runs only the rules of `columns`, e.g.
+    /// to recheck one column while tuning its rules. Columns are validated
+    /// one after another, so with `max_errors` the cut-off follows column
+    /// order rather than row order. File-level `warnings` and encoding
+    /// issues are left out.
+    #[wasm_bindgen(unchecked_return_type = "ErrorSummary")]
+    pub fn get_error_summary_for(&self, columns: Vec<String>) -> Result<JsValue, JsValue> {
+        let cols = columns.iter()
+            .map(|column| self.data.column_index(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let progress = self.progress("validate");
+        let summary = self.data.summarize_columns(&cols, &self.options.validation, &progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        summary.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// A self-contained HTML page summarizing the validation results, for
+    /// sending to people who won't open the app.
+    pub fn generate_html_report(&self) -> Result<String, JsValue> {
+        let progress = self.progress("validate");
+        let html = self.data.html_report(&progress)?;
+        progress.finish();
+        Ok(html)
+    }
+
+    /// A versioned JSON report (see `report::REPORT_VERSION`) with file
+    /// metadata, rule set hash, per-column results, violation samples and
+    /// rule timings, for archiving alongside each import.
+    pub fn generate_report(&self) -> Result<String, JsValue> {
+        let progress = self.progress("validate");
+        let report = self.data.audit_report(&self.rules, &progress)?;
+        progress.finish();
+        Ok(report)
+    }
+
+    /// Compares the loaded rows with an earlier version of the file (e.g.
+    /// last month's snapshot), matching rows by `key_column`. Returns
+    /// `{key_column, added, removed, changed, unchanged, columns_added,
+    /// columns_removed, duplicate_keys}`; `changed` rows list each differing
+    /// cell as `{column, previous, current}`.
+    #[wasm_bindgen(unchecked_return_type = "CompareReport")]
+    pub fn compare_with(&self, previous_csv: &str, key_column: &str) -> Result<JsValue, JsValue> {
+        let progress = self.progress("compare");
+        let report = self.data.compare(previous_csv, key_column, &self.options, &progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        report.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Indices of the rows matching a `RowQuery` JSON object, e.g.
+    /// `{"column": "status", "op": "equals", "value": "pending"}`. Ops are
+    /// `equals`, `not_equals`, `contains`, `regex` (with `pattern`),
+    /// `empty`, `not_empty` and the numeric `gt`, `gte`, `lt`, `lte`.
+    pub fn find_rows(&self, query_json: &str) -> Result<Vec<u32>, JsValue> {
+        let query: RowQuery = serde_json::from_str(query_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid Query JSON: {}", e)))?;
+        let progress = self.progress("search");
+        let rows = self.data.find_rows(&query, &progress)?;
+        progress.finish();
+        Ok(rows)
+    }
+
+    /// Groups of rows with the same values in `key_columns` (the whole row
+    /// when empty) as `[{key, count, rows, row_numbers}]`, ordered by their
+    /// first row. Nothing is removed, so duplicates can be reviewed first.
+    #[wasm_bindgen(unchecked_return_type = "DuplicateGroup[]")]
+    pub fn find_duplicates(&self, key_columns: Vec<String>) -> Result<JsValue, JsValue> {
+        let cols = key_columns.iter()
+            .map(|column| self.data.column_index(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let progress = self.progress("search");
+        let groups = self.data.find_duplicates(&cols, &progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        groups.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Checks that `key_columns` determine each of `dependent_columns`, e.g.
+    /// that rows with the same `product_id` have the same `product_name`.
+    /// Returns `[{column, key, values: [{value, rows, row_numbers}]}]` for
+    /// every key whose rows disagree on a dependent column: the cells the
+    /// `dependson` rule would fail. Rows with a blank key cell and blank
+    /// cells aren't compared.
+    #[wasm_bindgen(unchecked_return_type = "DependencyConflict[]")]
+    pub fn find_dependency_conflicts(&self, key_columns: Vec<String>, dependent_columns: Vec<String>) -> Result<JsValue, JsValue> {
+        let position = |column: &String| self.data.column_index(column);
+        let key_cols = key_columns.iter().map(position).collect::<Result<Vec<_>, _>>()?;
+        let dependent_cols = dependent_columns.iter().map(position).collect::<Result<Vec<_>, _>>()?;
+        if key_cols.is_empty() {
+            return Err(JsValue::from_str("Invalid Key: at least one key column is needed"));
+        }
+        let progress = self.progress("search");
+        let conflicts = self.data.find_dependency_conflicts(&key_cols, &dependent_cols, &progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        conflicts.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Scans every column, with or without rules, for cells that look like
+    /// credit card numbers, national IDs (US SSN, UK NI number), emails or
+    /// phone numbers. Returns `[{column, kind, matches, cells, fraction}]`
+    /// for each kind found in a column, where `cells` counts its non-empty
+    /// cells. Values are never included.
+    #[wasm_bindgen(unchecked_return_type = "PiiFinding[]")]
+    pub fn detect_pii(&self) -> Result<JsValue, JsValue> {
+        let progress = self.progress("scan");
+        let findings = self.data.detect_pii(&progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        findings.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// A versioned JSON report (see `pii::PRIVACY_REPORT_VERSION`) of the
+    /// columns holding personal data, as `detect_pii` finds them, and the
+    /// masks applied to each with `mask_column`, for attaching to GDPR
+    /// processing records.
+    pub fn generate_privacy_report(&self) -> Result<String, JsValue> {
+        let progress = self.progress("scan");
+        let report = self.data.privacy_report(&progress)?;
+        progress.finish();
+        Ok(report)
+    }
+
+    /// The `n` most common values of `column` as `[{value, count}]`, most
+    /// frequent first, for profiling and for filling bulk-fix pickers.
+    #[wasm_bindgen(unchecked_return_type = "ValueCount[]")]
+    pub fn get_top_values(&self, column: &str, n: usize) -> Result<JsValue, JsValue> {
+        let col = self.data.column_index(column)?;
+        let progress = self.progress("profile");
+        let values = self.data.top_values(col, n, &progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        values.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Numeric values of `column` far from the rest. `method` is `iqr`
+    /// (default), flagging values more than `threshold` (1.5) interquartile
+    /// ranges outside the quartiles, or `zscore`, flagging ones more than
+    /// `threshold` (3) standard deviations from the mean. Returns `{column,
+    /// method, threshold, numeric_cells, lower, upper, outliers: [{row,
+    /// row_number, value}]}`; cells that aren't numbers are ignored.
+    #[wasm_bindgen(unchecked_return_type = "OutlierReport")]
+    pub fn detect_outliers(&self, column: &str, method: Option<String>, threshold: Option<f64>) -> Result<JsValue, JsValue> {
+        let method = method.as_deref().map_or(Ok(OutlierMethod::Iqr), OutlierMethod::parse)?;
+        let col = self.data.column_index(column)?;
+        let progress = self.progress("profile");
+        let report = self.data.outliers(col, method, threshold, &progress)?;
+        progress.finish();
+
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        report.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Sorts the records by `column`. `direction` is `asc` (default) or
+    /// `desc`; `as_type` is `text` (default), `number` or `date`. Empty cells
+    /// and ones that don't parse as the type go last. Each row keeps its
+    /// original row number, which error exports and reports keep using.
+    pub fn sort_by(&mut self, column: &str, direction: Option<String>, as_type: Option<String>) -> Result<(), JsValue> {
+        let direction = direction.as_deref().map_or(Ok(SortDirection::Asc), SortDirection::parse)?;
+        let sort_type = as_type.as_deref().map_or(Ok(SortType::Text), SortType::parse)?;
+        let col = self.data.column_index(column)?;
+
+        self.cancel_token.reset();
+        let progress = Progress::new(self.on_progress.as_ref(), "sort").cancellable(&self.cancel_token);
+        let order = Arc::make_mut(&mut self.data).sort(col, direction, sort_type, &progress)?;
+        progress.finish();
+
+        if let Some(cache) = self.error_cache.as_mut() {
+            for counts in cache.columns.iter_mut().flatten() {
+                *counts = order.iter().map(|&row| counts[row]).collect();
             }
         }
+        Ok(())
+    }
 
-        let summary = ErrorSummary { stats, examples, total_errors };
-        //New: Use json_compatible() to force HashMaps into Objects
+    /// Masks the sensitive values of `column` in place, e.g. before sharing
+    /// a cleaned file with a vendor. `strategy` is `partial`
+    /// (`j***@example.com`), `token` (every value replaced by `param`, or
+    /// `[REDACTED]`), `hash` (hex SHA-256, salted with `param`; see
+    /// `hash_column` for BLAKE3) or `last4` (`****1234`). Empty cells are
+    /// left empty. Returns the number of cells changed; masked cells are
+    /// revalidated like fixed ones.
+    pub fn mask_column(&mut self, column: &str, strategy: &str, param: Option<String>) -> Result<usize, JsValue> {
+        let strategy = MaskStrategy::parse(strategy, param)?;
+        self.mask(column, &strategy)
+    }
+
+    /// Replaces the values of `column` with stable hex hashes of `salt`
+    /// followed by the value, so files exported with the same algorithm and
+    /// salt can still be joined on the column without exposing the raw
+    /// identifiers. `algorithm` is `sha256` or `blake3`. Keep the salt
+    /// secret: without it, guessable identifiers can be recovered by hashing
+    /// guesses. Empty cells are left empty. Returns the number of cells changed.
+    pub fn hash_column(&mut self, column: &str, algorithm: &str, salt: Option<String>) -> Result<usize, JsValue> {
+        let algorithm = HashAlgorithm::parse(algorithm)?;
+        self.mask(column, &MaskStrategy::Hash { algorithm, salt: salt.unwrap_or_default() })
+    }
+
+    /// The original (load order) index of each record in its current position.
+    pub fn get_original_indices(&self) -> Vec<u32> {
+        if self.data.origins.is_empty() {
+            (0..self.data.records.len() as u32).collect()
+        } else {
+            self.data.origins.clone()
+        }
+    }
+
+    /// Marks row `row` as `reviewed`, `accepted` (as-is) or `follow_up`,
+    /// with an optional note. Annotations follow the row through `sort_by`,
+    /// are saved by `save_state`, and are exported with `include_review`.
+    pub fn annotate_row(&mut self, row: usize, status: &str, note: Option<String>) -> Result<(), JsValue> {
+        let status = ReviewStatus::parse(status)?;
+        Ok(Arc::make_mut(&mut self.data).annotate(row, None, status, note)?)
+    }
+
+    /// Like `annotate_row`, for a single cell.
+    pub fn annotate_cell(&mut self, row: usize, column: &str, status: &str, note: Option<String>) -> Result<(), JsValue> {
+        let status = ReviewStatus::parse(status)?;
+        Ok(Arc::make_mut(&mut self.data).annotate(row, Some(column.to_string()), status, note)?)
+    }
+
+    /// Removes the annotation of row `row`, or of its cell in `column`.
+    /// Returns whether there was one.
+    pub fn clear_annotation(&mut self, row: usize, column: Option<String>) -> Result<bool, JsValue> {
+        Ok(Arc::make_mut(&mut self.data).clear_annotation(row, column.as_deref())?)
+    }
+
+    /// Every annotation as `[{row, row_number, column?, status, note?}]` in
+    /// row order, where `row` is the current index and `row_number` the
+    /// 1-based number as loaded.
+    #[wasm_bindgen(unchecked_return_type = "AnnotationEntry[]")]
+    pub fn get_annotations(&self) -> Result<JsValue, JsValue> {
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        self.data.annotation_entries().serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Replaces every `target_val` cell of `col_name` with `replace_val` and
+    /// returns the total number of errors left.
+    pub fn apply_bulk_fix(&mut self, col_name: &str, target_val: &str, replace_val: &str) -> Result<usize, JsValue> {
+        Ok(self.bulk_fix(col_name, target_val, replace_val)?.total_errors)
+    }
+
+    /// `apply_bulk_fix`, also reporting how many cells changed.
+    #[wasm_bindgen(unchecked_return_type = "FixResult")]
+    pub fn apply_bulk_fix_detailed(&mut self, col_name: &str, target_val: &str, replace_val: &str) -> Result<JsValue, JsValue> {
+        let result = self.bulk_fix(col_name, target_val, replace_val)?;
         let serializer = serde_wasm_bindgen::Serializer::json_compatible();
-        Ok(summary.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))?)
+        result.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Splits the rows into `{valid, invalid}` CSV (or JSONL) strings;
+    /// invalid rows get an extra `Error_Reason` column, or the columns and
+    /// `errors` output picked by `error_format`. `options_json` is an
+    /// optional `ExportOptions` object, as for every export method.
+    #[wasm_bindgen(unchecked_return_type = "SplitExport")]
+    pub fn generate_split_export(&self, options_json: Option<String>) -> Result<JsValue, JsValue> {
+        let options = ExportOptions::from_json(options_json)?;
+        let progress = self.progress("export");
+        let mut export = SplitExport::new(&self.data, &options)?;
+        self.data.write_split(&mut export, 0..self.data.records.len(), &progress)?;
+        progress.finish();
+        export.into_js()
+    }
+
+    /// Same as `generate_split_export`, but `valid` and `invalid` are UTF-8
+    /// `Uint8Array`s that can go straight into a `Blob` without re-encoding.
+    #[wasm_bindgen(unchecked_return_type = "SplitExportBytes")]
+    pub fn generate_split_export_bytes(&self, options_json: Option<String>) -> Result<JsValue, JsValue> {
+        let options = ExportOptions::from_json(options_json)?;
+        let progress = self.progress("export");
+        let mut export = SplitExport::new(&self.data, &options)?;
+        self.data.write_split(&mut export, 0..self.data.records.len(), &progress)?;
+        progress.finish();
+        export.into_js_bytes()
+    }
+
+    /// A reproducible random sample of up to `n` records as CSV, in file
+    /// order, for a quick look before approving the full load. `rows` is
+    /// `valid`, `invalid` or `mixed` (default); the same `seed` over the same
+    /// data always gives the same sample. `options_json` is an optional
+    /// `ExportOptions` object.
+    pub fn export_sample(&self, n: usize, seed: u32, rows: Option<String>, options_json: Option<String>) -> Result<String, JsValue> {
+        let rows = SampleRows::parse(rows.as_deref().unwrap_or("mixed"))?;
+        let options = ExportOptions::from_json(options_json)?;
+        let progress = self.progress("sample");
+        let sample = self.data.sample_rows(n, seed, rows, &progress)?;
+        progress.finish();
+
+        let progress = Progress::new(self.on_progress.as_ref(), "export").cancellable(&self.cancel_token);
+        let bytes = self.data.write_rows(&options, sample.into_iter(), &progress)?;
+        progress.finish();
+        String::from_utf8(bytes).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Like `export_sample`, but up to `per_value` records for every
+    /// distinct value of `column` (e.g. 5 per `country`), so a review covers
+    /// each category however rare. Values are compared exactly; empty cells
+    /// form their own group.
+    pub fn export_stratified_sample(&self, column: &str, per_value: usize, seed: u32, rows: Option<String>, options_json: Option<String>) -> Result<String, JsValue> {
+        let col = self.data.column_index(column)?;
+        let rows = SampleRows::parse(rows.as_deref().unwrap_or("mixed"))?;
+        let options = ExportOptions::from_json(options_json)?;
+        let progress = self.progress("sample");
+        let sample = self.data.stratified_sample_rows(col, per_value, seed, rows, &progress)?;
+        progress.finish();
+
+        let progress = Progress::new(self.on_progress.as_ref(), "export").cancellable(&self.cancel_token);
+        let bytes = self.data.write_rows(&options, sample.into_iter(), &progress)?;
+        progress.finish();
+        String::from_utf8(bytes).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Every record as an `.xlsx` workbook (a `Uint8Array`): bold header,
+    /// invalid cells filled red with their error types as cell comments.
+    /// From the optional `ExportOptions`, only `columns` applies.
+    pub fn to_xlsx(&self, options_json: Option<String>) -> Result<Vec<u8>, JsValue> {
+        let options = ExportOptions::from_json(options_json)?;
+        let progress = self.progress("export");
+        let bytes = self.data.write_xlsx(&options, &progress)?;
+        progress.finish();
+        Ok(bytes)
+    }
+
+    /// The valid rows as an Arrow IPC stream (a `Uint8Array`), e.g. for
+    /// DuckDB-WASM's `insertArrowFromIPCStream`. Columns with a `number`
+    /// rule are Float64, with a `date` rule Date32, and the rest Utf8;
+    /// blank cells and `null_values` tokens are nulls.
+    pub fn to_arrow(&self) -> Result<Vec<u8>, JsValue> {
+        let progress = self.progress("export");
+        let bytes = self.data.write_arrow(&progress)?;
+        progress.finish();
+        Ok(bytes)
+    }
+
+    /// The valid rows as a Parquet file (a `Uint8Array`), e.g. to register
+    /// with DuckDB-WASM and query with `read_parquet`. Columns are typed as
+    /// in `to_arrow`.
+    pub fn to_parquet(&self) -> Result<Vec<u8>, JsValue> {
+        let progress = self.progress("export");
+        let bytes = self.data.write_parquet(&progress)?;
+        progress.finish();
+        Ok(bytes)
+    }
+
+    /// The valid rows as SQL loading them into `table_name` (which may be
+    /// schema-qualified). `dialect` is `"postgres"` (default), `"mysql"`,
+    /// `"sqlite"` or `"copy"` for a Postgres `COPY ... FROM STDIN` payload.
+    pub fn to_sql(&self, table_name: &str, dialect: Option<String>) -> Result<String, JsValue> {
+        let dialect = SqlDialect::parse(dialect.as_deref().unwrap_or("postgres"))?;
+        let progress = self.progress("export");
+        let sql = self.data.write_sql(table_name, dialect, &progress)?;
+        progress.finish();
+        Ok(sql)
+    }
+
+    /// Every record as an array of objects keyed by header. With
+    /// `with_errors`, each object also carries an `_errors` array of
+    /// `{column, rule, code, error}` (empty for valid rows).
+    #[wasm_bindgen(unchecked_return_type = "JsonRecord[]")]
+    pub fn to_json(&self, with_errors: Option<bool>) -> Result<JsValue, JsValue> {
+        self.to_json_page(0, self.data.records.len(), with_errors)
+    }
+
+    /// `to_json` for at most `limit` records starting at `offset`, for grids
+    /// that page through large files.
+    #[wasm_bindgen(unchecked_return_type = "JsonRecord[]")]
+    pub fn to_json_page(&self, offset: usize, limit: usize, with_errors: Option<bool>) -> Result<JsValue, JsValue> {
+        self.records_page(offset, limit, None, with_errors.unwrap_or(false))
+    }
+
+    /// Up to `limit` records from `offset` as objects keyed by header, with
+    /// only the named `columns` when given, for virtualized grids that read
+    /// straight from the processor instead of keeping a copy in JS.
+    #[wasm_bindgen(unchecked_return_type = "JsonRecord[]")]
+    pub fn get_records_page(&self, offset: usize, limit: usize, columns: Option<Vec<String>>) -> Result<JsValue, JsValue> {
+        self.records_page(offset, limit, columns.as_deref(), false)
+    }
+
+    /// One line per violation: `row_number, column, error_code, message,
+    /// value`, where `row_number` is the 1-based record number as loaded (the
+    /// header line not counted, and unaffected by `sort_by`). Accepts the usual optional `ExportOptions`.
+    pub fn generate_error_detail(&self, options_json: Option<String>) -> Result<String, JsValue> {
+        let options = ExportOptions::from_json(options_json)?;
+        let progress = self.progress("export");
+        let bytes = self.data.write_error_details(&options, &progress)?;
+        progress.finish();
+        String::from_utf8(bytes).map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
-    pub fn apply_bulk_fix(&mut self, col_name: &str, target_val: &str, replace_val: &str) -> usize {
-        let col_idx = self.headers.iter().position(|h| h == col_name);
-        
+    /// Starts a streaming export of `"valid"`, `"invalid"` or `"all"` rows,
+    /// or of the `"errors"` detail file (see `ExportOptions::error_format`).
+    /// Pull the output with `next_export_chunk` until it returns `undefined`.
+    /// Starting a new export discards any unfinished one.
+    pub fn start_export(&mut self, kind: &str, options_json: Option<String>) -> Result<(), JsValue> {
+        let kind = ExportKind::parse(kind)?;
+        let options = ExportOptions::from_json(options_json)?;
+        self.export_cursor = Some(ExportCursor::new(kind, options, Arc::clone(&self.data))?);
+        Ok(())
+    }
+
+    /// Next piece of the export as UTF-8 bytes (a `Uint8Array`), holding up
+    /// to a few thousand rows, or `undefined` once the export is complete.
+    pub fn next_export_chunk(&mut self) -> Result<Option<Vec<u8>>, JsValue> {
+        let cursor = self.export_cursor.as_mut().ok_or_else(|| JsValue::from_str("No Export In Progress"))?;
+        let chunk = cursor.next_chunk()?;
+        if chunk.is_none() {
+            self.export_cursor = None;
+        }
+        Ok(chunk)
+    }
+
+    /// `get_error_summary` in batches of rows, yielding to the event loop
+    /// between batches so the page stays responsive without a Web Worker.
+    /// Validates a snapshot: edits made while it runs are not seen.
+    #[wasm_bindgen(unchecked_return_type = "Promise<ErrorSummary>")]
+    pub fn get_error_summary_async(&self) -> js_sys::Promise {
+        let data = Arc::clone(&self.data);
+        let options = self.options.clone();
+        let on_progress = self.on_progress.clone();
+        let token = self.cancel_token.clone();
+        token.reset();
+
+        future_to_promise(async move {
+            let progress = Progress::new(on_progress.as_ref(), "validate").cancellable(&token);
+            let mut summary = ErrorSummary::default();
+            for rows in batches(data.records.len(), ASYNC_BATCH_ROWS) {
+                data.summarize_into(&mut summary, rows, &options.validation, &progress)?;
+                if summary.truncated {
+                    break;
+                }
+                yield_to_event_loop().await;
+            }
+            data.report_file(&mut summary, &options);
+            progress.finish();
+
+            let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+            summary.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+        })
+    }
+
+    /// Batched, yielding variant of `generate_split_export`.
+    #[wasm_bindgen(unchecked_return_type = "Promise<SplitExport>")]
+    pub fn generate_split_export_async(&self, options_json: Option<String>) -> js_sys::Promise {
+        let options = match ExportOptions::from_json(options_json) {
+            Ok(options) => options,
+            Err(e) => return js_sys::Promise::reject(&e.into()),
+        };
+        let data = Arc::clone(&self.data);
+        let on_progress = self.on_progress.clone();
+        let token = self.cancel_token.clone();
+        token.reset();
+
+        future_to_promise(async move {
+            let progress = Progress::new(on_progress.as_ref(), "export").cancellable(&token);
+            let mut export = SplitExport::new(&data, &options)?;
+            for rows in batches(data.records.len(), ASYNC_BATCH_ROWS) {
+                data.write_split(&mut export, rows, &progress)?;
+                yield_to_event_loop().await;
+            }
+            progress.finish();
+            export.into_js()
+        })
+    }
+
+    fn bulk_fix(&mut self, col_name: &str, target_val: &str, replace_val: &str) -> Result<FixResult, JsValue> {
+        if self.error_cache.is_none() {
+            self.error_cache = Some(self.data.build_error_cache(&self.progress("validate"))?);
+        }
+        let cache = self.error_cache.as_mut().unwrap();
+        let data = Arc::make_mut(&mut self.data);
+        let col_idx = data.headers.iter().position(|h| h == col_name);
+        let mut rows_changed = 0;
+
         if let Some(idx) = col_idx {
-            for record in &mut self.records {
-                if let Some(val) = record.get_mut(idx) {
-                    if val == target_val {
-                        *val = replace_val.to_string();
-                    }
+            let changed = data.records.replace_all(idx, target_val, replace_val).map_err(|e| JsValue::from_str(&e))?;
+            rows_changed = changed.len();
+            let refit = if changed.is_empty() { Vec::new() } else { data.refit_edited(idx) };
+            cache.revalidate(data, idx, &changed, &refit);
+        }
+        Ok(FixResult { column: col_name.to_string(), rows_changed, total_errors: cache.total })
+    }
+
+    fn records_page(&self, offset: usize, limit: usize, columns: Option<&[String]>, with_errors: bool) -> Result<JsValue, JsValue> {
+        let page = self.data.json_page(offset..offset.saturating_add(limit), columns, with_errors)?;
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        page.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    fn load(csv_data: &str, rules: Vec<ColumnRule>, options: ProcessorOptions, on_progress: Option<js_sys::Function>) -> Result<CsvProcessor, JsValue> {
+        let progress = Progress::new(on_progress.as_ref(), "parse");
+        let data = Dataset::load(csv_data, &rules, &options, &progress)?;
+        progress.finish();
+
+        let mut processor = CsvProcessor::from_dataset(data, rules, options);
+        processor.on_progress = on_progress;
+        Ok(processor)
+    }
+
+    fn load_bytes(bytes: &[u8], rules: Vec<ColumnRule>, options: ProcessorOptions, on_progress: Option<js_sys::Function>) -> Result<CsvProcessor, JsValue> {
+        options.limits.check_bytes(bytes.len())?;
+        let (csv_data, replaced) = decode(bytes, options.lossy_utf8)
+            .map_err(|e| e.with_snippet(&String::from_utf8_lossy(bytes)))?;
+        let mut processor = CsvProcessor::load(&csv_data, rules, options, on_progress)?;
+        Arc::make_mut(&mut processor.data).lossy_decoded = replaced;
+        Ok(processor)
+    }
+
+    fn from_parts(headers: Vec<String>, records: RecordStore, rules: Vec<ColumnRule>, options: ProcessorOptions) -> Result<CsvProcessor, JsValue> {
+        let data = Dataset::new(headers, records, &rules, &options)?;
+        Ok(CsvProcessor::from_dataset(data, rules, options))
+    }
+
+    fn from_dataset(data: Dataset, rules: Vec<ColumnRule>, options: ProcessorOptions) -> CsvProcessor {
+        CsvProcessor {
+            data: Arc::new(data),
+            rules,
+            options,
+            error_cache: None,
+            on_progress: None,
+            cancel_token: CancellationToken::new(),
+            export_cursor: None,
+        }
+    }
+
+    /// Applies `strategy` to `column` and revalidates the changed cells.
+    fn mask(&mut self, column: &str, strategy: &MaskStrategy) -> Result<usize, JsValue> {
+        let col = self.data.column_index(column)?;
+
+        self.cancel_token.reset();
+        let progress = Progress::new(self.on_progress.as_ref(), "mask").cancellable(&self.cancel_token);
+        let data = Arc::make_mut(&mut self.data);
+        let changed = data.mask_column(col, strategy, &progress)?;
+        progress.finish();
+
+        let refit = if changed.is_empty() { Vec::new() } else { data.refit_edited(col) };
+        if let Some(cache) = self.error_cache.as_mut() {
+            cache.revalidate(data, col, &changed, &refit);
+        }
+        Ok(changed.len())
+    }
+
+    /// Applies `edit` to the rules entry of `column` (the last one matching
+    /// it, as `RuleSet::new` applies), or with `create` to a new entry if it
+    /// has none, then recompiles and revalidates just the columns the entry
+    /// covers. Nothing changes if `edit` or compiling fails. Returns the
+    /// error count of `column`.
+    fn edit_rules(&mut self, column: &str, create: bool, edit: impl FnOnce(&mut ColumnRule) -> Result<(), JsValue>) -> Result<usize, JsValue> {
+        self.data.column_index(column)?;
+        let matching = &self.options.header_matching;
+        let position = self.rules.iter().rposition(|r| matching.matches(column, &r.column));
+        let mut rule = match position {
+            Some(pos) => self.rules[pos].clone(),
+            None if create => ColumnRule { column: column.to_string(), rules: Vec::new(), disabled: Vec::new() },
+            None => return Err(JsValue::from_str(&format!("Unknown Rule: column '{}' has 0 rules", column))),
+        };
+        edit(&mut rule)?;
+        let compiled = RuleSet::compile(&rule).map_err(|e| ValidatorError::new(ErrorKind::Rules, e))?;
+
+        let columns: Vec<usize> = (0..self.data.headers.len())
+            .filter(|&col| matching.matches(&self.data.headers[col], &rule.column))
+            .collect();
+        let mut rule_set = self.data.rule_set.clone();
+        for &col in &columns {
+            rule_set.set_rules(col, compiled.clone(), rule.disabled.clone())
+                .map_err(|e| ValidatorError::new(ErrorKind::Rules, format!("Invalid Rule for column '{}': {}", self.data.headers[col], e)))?;
+        }
+
+        let data = Arc::make_mut(&mut self.data);
+        data.rule_set = rule_set;
+        let mut errors = 0;
+        for col in columns {
+            data.rule_set.fit_column(col, &data.records);
+            let counts = data.column_error_counts(col);
+            let count = counts.as_ref().map_or(0, |c| c.iter().map(|&n| n as usize).sum());
+            if data.headers[col] == column {
+                errors = count;
+            }
+            if let Some(cache) = self.error_cache.as_mut() {
+                let previous: usize = cache.columns[col].as_ref().map_or(0, |c| c.iter().map(|&n| n as usize).sum());
+                cache.total = cache.total - previous + count;
+                cache.columns[col] = counts;
+            }
+        }
+        match position {
+            Some(pos) => self.rules[pos] = rule,
+            None => self.rules.push(rule),
+        }
+        Ok(errors)
+    }
+
+    /// Progress for a new operation; clears any cancel left over from the last one.
+    fn progress(&self, phase: &'static str) -> Progress<'_> {
+        self.cancel_token.reset();
+        Progress::new(self.on_progress.as_ref(), phase).cancellable(&self.cancel_token)
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn check_rule_index(rule: &ColumnRule, rule_index: usize) -> Result<(), JsValue> {
+    if rule_index >= rule.rules.len() {
+        return Err(JsValue::from_str(&format!("Unknown Rule: column '{}' has {} rules", rule.column, rule.rules.len())));
+    }
+    Ok(())
+}
+
+fn parse_rules(rules_json: &str) -> Result<Vec<ColumnRule>, ValidatorError> {
+    serde_json::from_str(rules_json)
+        .map_err(|e| ValidatorError::new(ErrorKind::Rules, format!("Invalid Rules JSON: {}", e)))
+}
+
+fn parse_options(options_json: &str) -> Result<ProcessorOptions, ValidatorError> {
+    serde_json::from_str(options_json)
+        .map_err(|e| ValidatorError::new(ErrorKind::Options, format!("Invalid Options JSON: {}", e)))
+}
+
+/// A reader for `csv_data` with a header. Footer lines often have fewer
+/// fields than the data, and trailing delimiters add empty ones, so row
+/// lengths are left to `read_body` when either is configured for.
+fn csv_reader<'a>(csv_data: &'a str, options: &ProcessorOptions) -> csv::Reader<&'a [u8]> {
+    csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(options.skip_footer_rows > 0 || options.footer_pattern.is_some() || options.drop_empty_columns)
+        .from_reader(csv_data.as_bytes())
+}
+
+/// Reads the `header_rows` header lines and returns the final column names,
+/// combined and mapped as the options say.
+fn read_headers(reader: &mut csv::Reader<&[u8]>, options: &ProcessorOptions) -> Result<Vec<String>, ValidatorError> {
+    let header_error = |e: csv::Error| ValidatorError::csv(ErrorKind::Header, "Header Error", &e);
+    let mut rows = vec![reader.headers().map_err(header_error)?.iter().map(|h| h.to_string()).collect::<Vec<_>>()];
+    let mut record = csv::StringRecord::new();
+    for _ in 1..options.header_rows {
+        if !reader.read_record(&mut record).map_err(header_error)? {
+            break;
+        }
+        rows.push(record.iter().map(|h| h.to_string()).collect());
+    }
+    let headers = combine_header_rows(&rows, &options.header_join);
+    map_headers(&headers, &options.column_mapping).map_err(|e| ValidatorError::new(ErrorKind::Header, e))
+}
+
+/// Reads the remaining records onto `records` (see `read_records`) and sets
+/// the footer lines apart, returning them.
+fn read_body(
+    reader: &mut csv::Reader<&[u8]>,
+    total_bytes: usize,
+    records: &mut RecordStore,
+    columns: Option<&[usize]>,
+    options: &ProcessorOptions,
+    progress: &Progress,
+) -> Result<Vec<Vec<String>>, ValidatorError> {
+    let start = records.len();
+    let width = reader.headers().map_or(0, |h| h.len());
+    let ragged = read_records(reader, total_bytes, records, columns, options.drop_empty_columns, &options.limits, progress)?;
+    let footer = split_footer(records, start, options)?;
+    if let Some((idx, len, position)) = ragged.filter(|&(idx, _, _)| idx < records.len()) {
+        let message = format!("CSV Parse Error: record {} has {} fields, but the header has {}", idx - start + 1, len, width);
+        return Err(ValidatorError::new(ErrorKind::Parse, message).at(position.as_ref()));
+    }
+    Ok(footer)
+}
+
+/// Reads every remaining record into `records`, taking the cells at
+/// `columns` (in that order) when given. `total_bytes` is the input size,
+/// for progress. Returns the index, length and input position of the first
+/// record whose length differs from the header's (only possible with a
+/// flexible reader), not counting extra empty fields when `trailing_empty`
+/// allows them. Stops at `limits.max_rows`.
+fn read_records(
+    reader: &mut csv::Reader<&[u8]>,
+    total_bytes: usize,
+    records: &mut RecordStore,
+    columns: Option<&[usize]>,
+    trailing_empty: bool,
+    limits: &Limits,
+    progress: &Progress,
+) -> Result<Option<(usize, usize, Option<csv::Position>)>, ValidatorError> {
+    let width = reader.headers().map_or(0, |h| h.len());
+    let mut ragged = None;
+    let mut record = csv::StringRecord::new();
+    loop {
+        let fits = record.len() == width
+            || (trailing_empty && record.len() > width && record.iter().skip(width).all(|v| v.is_empty()));
+        if ragged.is_none() && !record.is_empty() && !fits {
+            ragged = Some((records.len() - 1, record.len(), record.position().cloned()));
+        }
+        match reader.read_record(&mut record) {
+            Ok(true) => {
+                limits.check_cells(record.iter()).map_err(|e| e.at(record.position()))?;
+                match columns {
+                    Some(columns) => records.push_row(columns.iter().map(|&i| record.get(i).unwrap_or(""))),
+                    None => records.push_row(record.iter()),
                 }
+                .map_err(|e| ValidatorError::new(ErrorKind::Data, e))?
             }
+            Ok(false) => break,
+            Err(e) => return Err(ValidatorError::csv(ErrorKind::Parse, "CSV Parse Error", &e)),
         }
-        self.count_total_errors()
-    }
-
-    pub fn generate_split_export(&self) -> Result<JsValue, JsValue> {
-        let mut valid_wtr = csv::Writer::from_writer(vec![]);
-        let mut invalid_wtr = csv::Writer::from_writer(vec![]);
-
-        let mut invalid_headers = self.headers.clone();
-        invalid_headers.push("Error_Reason".to_string());
-        
-        valid_wtr.write_record(&self.headers).map_err(|e| JsValue::from_str(&e.to_string()))?;
-        invalid_wtr.write_record(&invalid_headers).map_err(|e| JsValue::from_str(&e.to_string()))?;
-
-        let email_regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
-
-        for record in &self.records {
-            let mut row_errors = Vec::new();
-            for (col_idx, value) in record.iter().enumerate() {
-                if let Some(col_name) = self.headers.get(col_idx) {
-                    if let Some(rules) = self.rule_map.get(col_name) {
-                        for rule in rules {
-                             let is_err = match rule {
-                                RuleType::NotEmpty => value.trim().is_empty(),
-                                RuleType::Number { min, max } => {
-                                    match value.parse::<f64>() {
-                                        Ok(num) => min.map_or(false, |m| num < m) || max.map_or(false, |m| num > m),
-                                        Err(_) => true
-                                    }
-                                },
-                                RuleType::Email => !email_regex.is_match(value),
-                                RuleType::Regex { pattern } => Regex::new(pattern).map_or(false, |re| !re.is_match(value)),
-                                RuleType::OneOf { options } => !options.contains(value),
-                            };
-                            if is_err {
-                                row_errors.push(format!("{}: Invalid", col_name));
-                            }
-                        }
-                    }
+        limits.check_rows(records.len())?;
+        progress.step(records.len(), || reader.position().byte() as f64 * 100.0 / total_bytes.max(1) as f64)?;
+    }
+    Ok(ragged)
+}
+
+/// Removes the footer lines (per `skip_footer_rows` and `footer_pattern`)
+/// from the end of `records`, never reaching above row `from`, and returns
+/// them top to bottom.
+fn split_footer(records: &mut RecordStore, from: usize, options: &ProcessorOptions) -> Result<Vec<Vec<String>>, ValidatorError> {
+    let pattern = options.footer_pattern.as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| ValidatorError::new(ErrorKind::Options, format!("Invalid Options: footer_pattern: {}", e)))?;
+
+    let mut start = records.len().saturating_sub(options.skip_footer_rows).max(from);
+    if let Some(pattern) = &pattern {
+        while start > from {
+            let first = records.row(start - 1).iter().find(|cell| !cell.trim().is_empty()).unwrap_or("");
+            if !pattern.is_match(first) {
+                break;
+            }
+            start -= 1;
+        }
+    }
+    let footer = (start..records.len())
+        .map(|idx| records.row(idx).iter().map(|cell| cell.to_string()).collect())
+        .collect();
+    records.truncate(start);
+    Ok(footer)
+}
+
+/// For each loaded header, its position in `incoming`. Both must hold the
+/// same set of columns, apart from incoming ones the loaded data lacks and
+/// `skip` accepts.
+fn match_headers(headers: &[String], incoming: &[String], skip: impl Fn(&str) -> bool) -> Result<Vec<usize>, ValidatorError> {
+    let named: Vec<&String> = incoming.iter().filter(|i| headers.contains(i) || !skip(i)).collect();
+    let missing: Vec<&str> = headers.iter().filter(|h| !named.contains(h)).map(|h| h.as_str()).collect();
+    let unexpected: Vec<&str> = named.iter().filter(|i| !headers.contains(i)).map(|i| i.as_str()).collect();
+    if !missing.is_empty() || !unexpected.is_empty() || named.len() != headers.len() {
+        let message = format!("Header Mismatch: missing [{}], unexpected [{}]", missing.join(", "), unexpected.join(", "));
+        return Err(ValidatorError::new(ErrorKind::Header, message));
+    }
+    Ok(headers.iter().map(|h| incoming.iter().position(|i| i == h).unwrap_or(0)).collect())
+}
+
+fn is_unnamed(header: &str) -> bool {
+    header.trim().is_empty()
+}
+
+/// Whether any rule refers to `header`.
+fn is_known(header: &str, rules: &[ColumnRule], options: &ProcessorOptions) -> bool {
+    rules.iter().any(|r| options.header_matching.matches(header, &r.column))
+}
+
+/// Applies `options.unknown_columns` to freshly loaded data, returning it
+/// with the unknown columns it found (none under `ignore`).
+fn apply_unknown_columns(headers: Vec<String>, records: RecordStore, rules: &[ColumnRule], options: &ProcessorOptions) -> Result<(Vec<String>, RecordStore, Vec<String>), ValidatorError> {
+    if options.unknown_columns == UnknownColumns::Ignore {
+        return Ok((headers, records, Vec::new()));
+    }
+    let unknown: Vec<String> = headers.iter().filter(|h| !is_known(h, rules, options)).cloned().collect();
+    match options.unknown_columns {
+        UnknownColumns::Reject if !unknown.is_empty() => {
+            Err(ValidatorError::new(ErrorKind::Header, format!("Unknown Columns: {}", unknown.join(", "))))
+        }
+        UnknownColumns::Drop if !unknown.is_empty() => {
+            let keep: Vec<usize> = (0..headers.len()).filter(|&col| !unknown.contains(&headers[col])).collect();
+            let records = records.select_columns(&keep).map_err(|e| ValidatorError::new(ErrorKind::Data, e))?;
+            Ok((keep.iter().map(|&col| headers[col].clone()).collect(), records, unknown))
+        }
+        _ => Ok((headers, records, unknown)),
+    }
+}
+
+/// Drops unnamed columns without a single value, e.g. the phantom column a
+/// trailing delimiter on every line creates.
+fn drop_empty_columns(headers: Vec<String>, records: RecordStore) -> Result<(Vec<String>, RecordStore), ValidatorError> {
+    let keep: Vec<usize> = (0..headers.len())
+        .filter(|&col| !is_unnamed(&headers[col]) || records.column(col).any(|v| !v.is_empty()))
+        .collect();
+    if keep.len() == headers.len() {
+        return Ok((headers, records));
+    }
+    let records = records.select_columns(&keep).map_err(|e| ValidatorError::new(ErrorKind::Data, e))?;
+    Ok((keep.iter().map(|&col| headers[col].clone()).collect(), records))
+}
+
+impl Dataset {
+    /// Position of the header `name`.
+    fn column_index(&self, name: &str) -> Result<usize, ValidatorError> {
+        self.headers.iter().position(|h| h == name)
+            .ok_or_else(|| ValidatorError::new(ErrorKind::Options, format!("Unknown Column: {}", name)))
+    }
+
+    /// Fits the rules that follow edits again after `col` changed, in
+    /// `col` and the columns that read it. Returns the columns refit, all
+    /// of whose cells need revalidating.
+    fn refit_edited(&mut self, col: usize) -> Vec<usize> {
+        let columns: Vec<usize> = std::iter::once(col).chain(self.rule_set.readers(col)).collect();
+        columns.into_iter().filter(|&col| self.rule_set.refit_edited(col, &self.records)).collect()
+    }
+
+    fn new(headers: Vec<String>, records: RecordStore, rules: &[ColumnRule], options: &ProcessorOptions) -> Result<Dataset, ValidatorError> {
+        let records = if records.is_empty() {
+            RecordStore::with_layout(headers.len(), options.layout)
+        } else if records.width() != headers.len() {
+            return Err(ValidatorError::new(ErrorKind::Data, "Record width does not match headers"));
+        } else {
+            records.into_layout(options.layout)
+        };
+        let mut rule_set = RuleSet::new(&headers, rules, &options.header_matching).map_err(|e| ValidatorError::new(ErrorKind::Rules, e))?;
+        rule_set.set_options(&options.validation);
+        rule_set.fit(&records);
+        Ok(Dataset {
+            headers,
+            records,
+            rule_set,
+            origins: Vec::new(),
+            annotations: BTreeMap::new(),
+            footer: Vec::new(),
+            unknown_columns: Vec::new(),
+            lossy_decoded: false,
+            masks: Vec::new(),
+        })
+    }
+
+    /// Parses `csv_data` as the options say and compiles `rules` against it.
+    fn load(csv_data: &str, rules: &[ColumnRule], options: &ProcessorOptions, progress: &Progress) -> Result<Dataset, ValidatorError> {
+        options.limits.check_bytes(csv_data.len())?;
+        let parse = || {
+            let mut reader = csv_reader(csv_data, options);
+            let headers = read_headers(&mut reader, options)?;
+            options.limits.check_columns(headers.len())?;
+            let mut records = RecordStore::with_layout(headers.len(), options.layout);
+            let footer = read_body(&mut reader, csv_data.len(), &mut records, None, options, progress)?;
+            Ok((headers, records, footer))
+        };
+        let (headers, records, footer) = parse().map_err(|e: ValidatorError| e.with_snippet(csv_data))?;
+
+        let (headers, records) = if options.drop_empty_columns {
+            drop_empty_columns(headers, records)?
+        } else {
+            (headers, records)
+        };
+        let (headers, records, unknown) = apply_unknown_columns(headers, records, rules, options)?;
+        if !options.allow_empty {
+            if headers.is_empty() {
+                return Err(ValidatorError::new(ErrorKind::Empty, "Empty File: no header"));
+            }
+            if records.is_empty() {
+                return Err(ValidatorError::new(ErrorKind::Empty, "Empty File: no records after the header"));
+            }
+        }
+
+        let mut data = Dataset::new(headers, records, rules, options)?;
+        data.footer = footer;
+        data.unknown_columns = unknown;
+        Ok(data)
+    }
+
+    /// The summary of every row, file-level findings included, as
+    /// `get_error_summary` and `Validator::summary` return it. `token` stops
+    /// the worker threads of a parallel run.
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    fn error_summary(&self, options: &ProcessorOptions, progress: &Progress, token: &CancellationToken) -> Result<ErrorSummary, Cancelled> {
+        let validation = &options.validation;
+
+        // Worker threads can't call back into JS, so only the end is reported.
+        // Fail-fast runs sequentially so it stops at exactly `max_errors`.
+        #[cfg(feature = "parallel")]
+        let mut summary = if validation.instrument {
+            self.summarize_instrumented(validation, progress)?
+        } else if validation.max_errors.is_none() {
+            (0..self.records.len().div_ceil(PARALLEL_CHUNK_ROWS))
+                .into_par_iter()
+                .map(|chunk| {
+                    let start = chunk * PARALLEL_CHUNK_ROWS;
+                    let rows = start..(start + PARALLEL_CHUNK_ROWS).min(self.records.len());
+                    self.summarize(rows, validation, &Progress::detached(token))
+                })
+                .try_reduce(ErrorSummary::default, |a, b| Ok(a.merge(b)))?
+        } else {
+            self.summarize(0..self.records.len(), validation, progress)?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let mut summary = if validation.instrument {
+            self.summarize_instrumented(validation, progress)?
+        } else {
+            self.summarize(0..self.records.len(), validation, progress)?
+        };
+        self.report_file(&mut summary, options);
+        Ok(summary)
+    }
+
+    fn summarize(&self, rows: Range<usize>, options: &ValidationOptions, progress: &Progress) -> Result<ErrorSummary, Cancelled> {
+        let mut summary = ErrorSummary::default();
+        self.summarize_into(&mut summary, rows, options, progress)?;
+        Ok(summary)
+    }
+
+    /// Adds the violations in `rows` to an existing summary.
+    fn summarize_into(&self, summary: &mut ErrorSummary, rows: Range<usize>, options: &ValidationOptions, progress: &Progress) -> Result<(), Cancelled> {
+        let total = self.records.len();
+
+        'rows: for idx in rows {
+            let record = self.records.row(idx);
+            // Violations come in column order
+            let mut last_column = None;
+            for (n, violation) in self.rule_set.validate_record(&record).enumerate() {
+                if options.max_errors.is_some_and(|max| summary.total_errors >= max) {
+                    summary.truncated = true;
+                    break 'rows;
+                }
+                if options.max_errors_per_row.is_some_and(|max| n >= max) {
+                    summary.malformed_rows.push(self.row_number(idx));
+                    break;
                 }
+                if last_column != Some(violation.column) {
+                    summary.invalid_rows += usize::from(last_column.is_none());
+                    summary.invalid_cells += 1;
+                    last_column = Some(violation.column);
+                }
+                summary.record(&self.headers[violation.column], violation.error_type, record.get(violation.column));
             }
+            progress.row(idx, total)?;
+        }
+        Ok(())
+    }
 
-            if row_errors.is_empty() {
-                valid_wtr.write_record(record).map_err(|e| JsValue::from_str(&e.to_string()))?;
-            } else {
-                let mut dirty_row = record.clone();
-                dirty_row.push(row_errors.join("; "));
-                invalid_wtr.write_record(&dirty_row).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    /// A summary of the rules of `cols` alone, column by column.
+    fn summarize_columns(&self, cols: &[usize], options: &ValidationOptions, progress: &Progress) -> Result<ErrorSummary, Cancelled> {
+        let mut summary = ErrorSummary::default();
+        let rows = self.records.len();
+        let total = rows * cols.len();
+        let mut invalid = vec![false; rows];
+        let mut row_errors = vec![0; rows];
+        'columns: for (done, &col) in cols.iter().enumerate() {
+            for (idx, value) in self.records.column(col).enumerate() {
+                for (n, violation) in self.rule_set.validate_cell(col, value, &self.records.row(idx)).enumerate() {
+                    if options.max_errors.is_some_and(|max| summary.total_errors >= max) {
+                        summary.truncated = true;
+                        break 'columns;
+                    }
+                    if !summary.within_row_cap(&mut row_errors, idx, self.row_number(idx), options) {
+                        break;
+                    }
+                    if n == 0 {
+                        summary.mark_invalid(&mut invalid, idx);
+                    }
+                    summary.record(&self.headers[col], violation.error_type, value);
+                }
+                progress.row(done * rows + idx, total)?;
             }
         }
+        summary.malformed_rows.sort_unstable();
+        summary.set_rates(rows, |header| self.non_empty_cells(header));
+        Ok(summary)
+    }
 
-        let valid_csv = String::from_utf8(valid_wtr.into_inner().unwrap()).unwrap();
-        let invalid_csv = String::from_utf8(invalid_wtr.into_inner().unwrap()).unwrap();
+    /// Index row `idx` had when loaded.
+    fn origin(&self, idx: usize) -> u32 {
+        self.origins.get(idx).map_or(idx as u32, |&origin| origin)
+    }
 
-        let result = serde_json::json!({
-            "valid": valid_csv,
-            "invalid": invalid_csv
-        });
+    /// 1-based row number of row `idx` as originally loaded, for error references.
+    fn row_number(&self, idx: usize) -> usize {
+        self.origin(idx) as usize + 1
+    }
+
+    /// Adds what concerns the file as a whole, rather than single cells, to
+    /// a finished summary.
+    fn report_file(&self, summary: &mut ErrorSummary, options: &ProcessorOptions) {
+        if self.headers.is_empty() {
+            summary.warnings.push("The file has no header".to_string());
+        } else if self.records.is_empty() {
+            summary.warnings.push("The file has no records".to_string());
+        }
+        self.report_unvalidated(summary, &options.validation);
+        self.report_unknown(summary, options.unknown_columns);
+        self.report_encoding(summary);
+        summary.set_rates(self.records.len(), |header| self.non_empty_cells(header));
+    }
+
+    /// Non-empty cells in the column named `header`.
+    fn non_empty_cells(&self, header: &str) -> usize {
+        self.headers.iter().position(|h| h == header)
+            .map_or(0, |col| self.records.column(col).filter(|value| !value.is_empty() && !self.rule_set.is_null(value)).count())
+    }
+
+    /// Adds the columns that have no rules to `summary`, as warnings or
+    /// errors per `options.unvalidated_columns`.
+    fn report_unvalidated(&self, summary: &mut ErrorSummary, options: &ValidationOptions) {
+        let columns = self.headers.iter().enumerate().filter(|&(col, _)| !self.rule_set.has_rules(col)).map(|(_, h)| h);
+        match options.unvalidated_columns {
+            UnvalidatedColumns::Ignore => {}
+            UnvalidatedColumns::Warn => summary.warnings.extend(columns.map(|h| format!("Column '{}' has no rules", h))),
+            UnvalidatedColumns::Error => columns.for_each(|h| summary.record(h, "Unvalidated Column", "")),
+        }
+    }
+
+    /// Adds the columns the `unknown_columns` policy kept or dropped at load
+    /// to the summary's `warnings`.
+    fn report_unknown(&self, summary: &mut ErrorSummary, policy: UnknownColumns) {
+        let columns = self.unknown_columns.iter();
+        match policy {
+            UnknownColumns::Warn => summary.warnings.extend(columns.map(|h| format!("Column '{}' is not in the rules", h))),
+            UnknownColumns::Drop => summary.warnings.extend(columns.map(|h| format!("Dropped column '{}': not in the rules", h))),
+            UnknownColumns::Ignore | UnknownColumns::Reject => {}
+        }
+    }
+
+    /// Every rule failure in a row, in column order; empty when it is valid.
+    fn violations(&self, idx: usize) -> Vec<Violation> {
+        self.rule_set.validate_record(&self.records.row(idx)).collect()
+    }
 
-        Ok(serde_wasm_bindgen::to_value(&result)?)
-    }
-
-    fn count_total_errors(&self) -> usize {
-        let mut count = 0;
-        let email_regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
-
-        for record in &self.records {
-            for (col_idx, value) in record.iter().enumerate() {
-                if let Some(col_name) = self.headers.get(col_idx) {
-                    if let Some(rules) = self.rule_map.get(col_name) {
-                        for rule in rules {
-                             let is_err = match rule {
-                                RuleType::NotEmpty => value.trim().is_empty(),
-                                RuleType::Number { min, max } => {
-                                    match value.parse::<f64>() {
-                                        Ok(num) => min.map_or(false, |m| num < m) || max.map_or(false, |m| num > m),
-                                        Err(_) => true
-                                    }
-                                },
-                                RuleType::Email => !email_regex.is_match(value),
-                                RuleType::Regex { pattern } => Regex::new(pattern).map_or(false, |re| !re.is_match(value)),
-                                RuleType::OneOf { options } => !options.contains(value),
-                            };
-                            if is_err { count += 1; }
+    /// Rule-at-a-time variant of `summarize` that times each rule.
+    fn summarize_instrumented(&self, options: &ValidationOptions, progress: &Progress) -> Result<ErrorSummary, Cancelled> {
+        let mut summary = ErrorSummary::default();
+        let mut timings = Vec::new();
+        let mut invalid = vec![false; self.records.len()];
+        let mut row_errors = vec![0; self.records.len()];
+
+        'columns: for (col_idx, col_name) in self.headers.iter().enumerate() {
+            // Cells an earlier rule already failed
+            let mut failed = vec![false; self.records.len()];
+            for (rule_index, rule) in self.rule_set.rules_for(col_idx).iter().enumerate() {
+                if !self.rule_set.is_enabled(col_idx, rule_index) {
+                    continue;
+                }
+                progress.check()?;
+                let started = now_ms();
+                let mut errors = 0;
+                for (row, value) in self.records.column(col_idx).enumerate() {
+                    if failed[row] && self.rule_set.short_circuit() {
+                        continue;
+                    }
+                    if let Some(error_type) = self.rule_set.check(rule, value, &self.records.row(row)) {
+                        if options.max_errors.is_some_and(|max| summary.total_errors >= max) {
+                            summary.truncated = true;
+                            break 'columns;
                         }
+                        if !summary.within_row_cap(&mut row_errors, row, self.row_number(row), options) {
+                            continue;
+                        }
+                        if !failed[row] {
+                            failed[row] = true;
+                            summary.mark_invalid(&mut invalid, row);
+                        }
+                        errors += 1;
+                        summary.record(col_name, error_type, value);
                     }
                 }
+                timings.push(RuleTiming {
+                    column: col_name.clone(),
+                    rule_index,
+                    rule: rule.name(),
+                    millis: now_ms() - started,
+                    errors,
+                });
+            }
+        }
+        summary.malformed_rows.sort_unstable();
+        summary.timings = Some(timings);
+        Ok(summary)
+    }
+
+    #[cfg(feature = "wasm")]
+    fn build_error_cache(&self, progress: &Progress) -> Result<ErrorCache, Cancelled> {
+        let mut columns = vec![None; self.headers.len()];
+        let mut total = 0;
+
+        for (col_idx, slot) in columns.iter_mut().enumerate() {
+            if self.rule_set.has_rules(col_idx) {
+                progress.check()?;
+                *slot = self.column_error_counts(col_idx);
+                total += slot.iter().flatten().map(|&c| c as usize).sum::<usize>();
             }
         }
-        count
+        Ok(ErrorCache { columns, total })
     }
-}
\ No newline at end of file
+
+    /// Failed-rule counts for each cell of column `col`, as the error cache
+    /// holds them (`None` when it has no rules).
+    #[cfg(feature = "wasm")]
+    fn column_error_counts(&self, col: usize) -> Option<Vec<u16>> {
+        if !self.rule_set.has_rules(col) {
+            return None;
+        }
+        #[cfg(feature = "parallel")]
+        let counts = (0..self.records.len()).into_par_iter()
+            .map(|row| self.rule_set.validate_cell(col, self.records.get(row, col), &self.records.row(row)).count() as u16)
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let counts = self.records.column(col).enumerate()
+            .map(|(row, value)| self.rule_set.validate_cell(col, value, &self.records.row(row)).count() as u16)
+            .collect();
+        Some(counts)
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// --- Column Mapping ---
+//
+// Suppliers name the same field differently ("E-mail", "email_address", ...).
+// A mapping renames incoming headers to the canonical names the rules use,
+// before anything else sees them.
+
+/// Source header -> canonical name.
+pub type ColumnMapping = BTreeMap<String, String>;
+
+/// `headers` renamed per `mapping`. Sources match ignoring case and
+/// surrounding whitespace; headers without a mapping keep their name. Fails
+/// if two columns would end up with the same name.
+pub fn map_headers(headers: &[String], mapping: &ColumnMapping) -> Result<Vec<String>, String> {
+    let normalize = |name: &str| name.trim().to_lowercase();
+    let lookup: BTreeMap<String, &String> = mapping.iter().map(|(source, canonical)| (normalize(source), canonical)).collect();
+
+    let mapped: Vec<String> = headers.iter()
+        .map(|h| lookup.get(&normalize(h)).map_or_else(|| h.clone(), |canonical| (*canonical).clone()))
+        .collect();
+    for (i, name) in mapped.iter().enumerate() {
+        if let Some(j) = mapped[..i].iter().position(|other| other == name) {
+            if mapped[i] != headers[i] || mapped[j] != headers[j] {
+                return Err(format!("Invalid Column Mapping: '{}' and '{}' both map to '{}'", headers[j], headers[i], name));
+            }
+        }
+    }
+    Ok(mapped)
+}
+
+/// How the lines of a multi-line header combine into one name per column.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "strategy", rename_all = "lowercase")]
+pub enum HeaderJoin {
+    /// The non-empty parts joined with `separator`, e.g. "Billing Email".
+    /// A blank cell in an upper line takes the value to its left, as a
+    /// group name spanning several columns is usually exported that way.
+    Join { separator: String },
+    /// Only the lowest non-empty part.
+    Last,
+}
+
+impl Default for HeaderJoin {
+    fn default() -> HeaderJoin {
+        HeaderJoin::Join { separator: " ".to_string() }
+    }
+}
+
+/// One name per column from the header lines, top line first.
+pub fn combine_header_rows(rows: &[Vec<String>], join: &HeaderJoin) -> Vec<String> {
+    if rows.len() == 1 {
+        return rows[0].clone();
+    }
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let last = rows.len() - 1;
+
+    // Upper lines with group names carried right across blank cells
+    let filled: Vec<Vec<&str>> = rows.iter().enumerate()
+        .map(|(line, row)| {
+            let mut current = "";
+            (0..width)
+                .map(|col| {
+                    let cell = row.get(col).map_or("", |c| c.trim());
+                    if !cell.is_empty() || line == last {
+                        current = cell;
+                    }
+                    current
+                })
+                .collect()
+        })
+        .collect();
+
+    (0..width)
+        .map(|col| {
+            let mut parts = filled.iter().map(|line| line[col]).filter(|part| !part.is_empty());
+            match join {
+                HeaderJoin::Join { separator } => parts.collect::<Vec<_>>().join(separator),
+                HeaderJoin::Last => parts.next_back().unwrap_or("").to_string(),
+            }
+        })
+        .collect()
+}
+
+/// How rule `column` names are matched against headers. All off (the
+/// default) means exact matching.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HeaderMatching {
+    pub ignore_case: bool,
+    /// Ignore leading and trailing whitespace.
+    pub trim: bool,
+    /// Ignore everything but letters and digits, so "E-mail Address"
+    /// matches "email_address" (with `ignore_case`).
+    pub ignore_punctuation: bool,
+}
+
+impl HeaderMatching {
+    pub fn matches(&self, header: &str, column: &str) -> bool {
+        header == column || self.normalize(header) == self.normalize(column)
+    }
+
+    fn normalize(&self, name: &str) -> String {
+        let name = if self.trim { name.trim() } else { name };
+        let name: String = if self.ignore_punctuation { name.chars().filter(|c| c.is_alphanumeric()).collect() } else { name.to_string() };
+        if self.ignore_case { name.to_lowercase() } else { name }
+    }
+}
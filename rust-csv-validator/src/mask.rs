@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use crate::blake3::blake3_hex;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::sha256::sha256_hex;
+use crate::Dataset;
+
+// --- Masking ---
+//
+// Sensitive columns are rewritten in place before a cleaned file goes to an
+// outside vendor. Masking can't be undone (short of reloading the file), and
+// empty cells stay empty so "missing" still reads as missing.
+
+// Stands in for the hidden part of a value; fixed so it doesn't leak length
+const MASK: &str = "***";
+const DEFAULT_TOKEN: &str = "[REDACTED]";
+
+/// How `mask_column` rewrites each cell.
+pub enum MaskStrategy {
+    /// First character kept, e.g. "j***@example.com" for an email (the
+    /// domain is kept) or "J***" otherwise. Values under 3 characters are
+    /// masked entirely.
+    Partial,
+    /// Every cell replaced by the same token.
+    Token(String),
+    /// Hex digest of the salt followed by the value, so equal values stay
+    /// joinable without being readable. Without a salt, common values can be
+    /// recovered by hashing guesses.
+    Hash { algorithm: HashAlgorithm, salt: String },
+    /// Only the last 4 characters kept, e.g. "****1234". Values of 4
+    /// characters or fewer are masked entirely.
+    Last4,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn parse(algorithm: &str) -> Result<HashAlgorithm, ValidatorError> {
+        match algorithm.to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Hash Algorithm: {}", other))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    pub fn hex(self, data: &[u8]) -> String {
+        match self {
+            HashAlgorithm::Sha256 => sha256_hex(data),
+            HashAlgorithm::Blake3 => blake3_hex(data),
+        }
+    }
+}
+
+/// A `mask_column` (or `hash_column`) call, kept for the privacy report and saved with the state.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct AppliedMask {
+    pub(crate) column: String,
+    pub(crate) strategy: String,
+    /// The digest of a `hash` mask.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) algorithm: Option<String>,
+    /// Whether a `hash` mask was salted.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) salted: bool,
+    /// Cells changed.
+    pub(crate) cells: usize,
+}
+
+impl MaskStrategy {
+    /// `param` is the token for `token` and the salt for `hash`.
+    pub fn parse(strategy: &str, param: Option<String>) -> Result<MaskStrategy, ValidatorError> {
+        match strategy {
+            "partial" => Ok(MaskStrategy::Partial),
+            "token" => Ok(MaskStrategy::Token(param.unwrap_or_else(|| DEFAULT_TOKEN.to_string()))),
+            "hash" => Ok(MaskStrategy::Hash { algorithm: HashAlgorithm::Sha256, salt: param.unwrap_or_default() }),
+            "last4" => Ok(MaskStrategy::Last4),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Mask Strategy: {}", other))),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MaskStrategy::Partial => "partial",
+            MaskStrategy::Token(_) => "token",
+            MaskStrategy::Hash { .. } => "hash",
+            MaskStrategy::Last4 => "last4",
+        }
+    }
+
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            MaskStrategy::Partial => match value.rsplit_once('@') {
+                Some((local, domain)) => format!("{}@{}", keep_first(local), domain),
+                None => keep_first(value),
+            },
+            MaskStrategy::Token(token) => token.clone(),
+            MaskStrategy::Hash { algorithm, salt } => algorithm.hex(format!("{}{}", salt, value).as_bytes()),
+            MaskStrategy::Last4 => {
+                let len = value.chars().count();
+                if len <= 4 {
+                    return "****".to_string();
+                }
+                let tail: String = value.chars().skip(len - 4).collect();
+                format!("****{}", tail)
+            }
+        }
+    }
+}
+
+fn keep_first(value: &str) -> String {
+    match value.chars().next() {
+        Some(first) if value.chars().count() >= 3 => format!("{}{}", first, MASK),
+        _ => MASK.to_string(),
+    }
+}
+
+impl Dataset {
+    /// Masks every non-empty cell of column `col`, returning the rows that
+    /// changed. Nothing is written if cancelled part way.
+    pub(crate) fn mask_column(&mut self, col: usize, strategy: &MaskStrategy, progress: &Progress) -> Result<Vec<usize>, ValidatorError> {
+        let total = self.records.len();
+        let mut masked = Vec::new();
+        for (idx, value) in self.records.column(col).enumerate() {
+            progress.row(idx, total)?;
+            if value.is_empty() {
+                continue;
+            }
+            let replacement = strategy.apply(value);
+            if replacement != value {
+                masked.push((idx, replacement));
+            }
+        }
+        for (idx, replacement) in &masked {
+            self.records.set(*idx, col, replacement).map_err(|e| ValidatorError::new(ErrorKind::Data, e))?;
+        }
+        self.masks.push(AppliedMask {
+            column: self.headers[col].clone(),
+            strategy: strategy.name().to_string(),
+            algorithm: match strategy {
+                MaskStrategy::Hash { algorithm, .. } => Some(algorithm.name().to_string()),
+                _ => None,
+            },
+            salted: matches!(strategy, MaskStrategy::Hash { salt, .. } if !salt.is_empty()),
+            cells: masked.len(),
+        });
+        Ok(masked.into_iter().map(|(idx, _)| idx).collect())
+    }
+}
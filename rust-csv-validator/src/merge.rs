@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::store::RecordStore;
+use crate::{match_headers, ColumnRule, Dataset, ProcessorOptions};
+
+// --- Merging Datasets ---
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MergeMode {
+    /// Both sides have the same columns, possibly in a different order.
+    Strict,
+    /// Every column of either side; cells a side doesn't have are empty.
+    Union,
+}
+
+impl MergeMode {
+    pub fn parse(mode: &str) -> Result<MergeMode, ValidatorError> {
+        match mode {
+            "strict" => Ok(MergeMode::Strict),
+            "union" => Ok(MergeMode::Union),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Merge Mode: {}", other))),
+        }
+    }
+}
+
+/// `left`'s rows followed by `right`'s in `left`'s column order (plus, for
+/// a union, `right`'s extra columns), with their rules combined. `options`
+/// are the left side's.
+pub(crate) fn merge(
+    (left, left_rules): (&Dataset, &[ColumnRule]),
+    (right, right_rules): (&Dataset, &[ColumnRule]),
+    options: &ProcessorOptions,
+    mode: MergeMode,
+) -> Result<(Dataset, Vec<ColumnRule>), ValidatorError> {
+    let headers = match mode {
+        MergeMode::Strict => {
+            match_headers(&left.headers, &right.headers, |_| false)?;
+            left.headers.clone()
+        }
+        MergeMode::Union => left.headers.iter()
+            .chain(right.headers.iter().filter(|h| !left.headers.contains(h)))
+            .cloned()
+            .collect(),
+    };
+    let rules = merge_rules(left_rules, right_rules)?;
+
+    // Position of each merged column on the right, if it has it
+    let right_columns: Vec<Option<usize>> = headers.iter().map(|h| right.headers.iter().position(|r| r == h)).collect();
+    let mut records = RecordStore::with_layout(headers.len(), options.layout);
+    let mut annotations = BTreeMap::new();
+    for (side, data) in [left, right].into_iter().enumerate() {
+        for (idx, row) in data.records.rows().enumerate() {
+            let merged_idx = records.len() as u32;
+            if side == 0 {
+                // Extra union columns are past the left's own; `push_row` leaves them empty
+                records.push_row(row.iter())
+            } else {
+                records.push_row(right_columns.iter().map(|col| col.map_or("", |c| row.get(c))))
+            }
+            .map_err(|e| ValidatorError::new(ErrorKind::Data, e))?;
+            if let Some(row_annotations) = data.annotations.get(&data.origin(idx)) {
+                annotations.insert(merged_idx, row_annotations.clone());
+            }
+        }
+    }
+
+    let mut data = Dataset::new(headers, records, &rules, options)?;
+    data.annotations = annotations;
+    data.footer = left.footer.iter().chain(&right.footer).cloned().collect();
+    data.lossy_decoded = left.lossy_decoded || right.lossy_decoded;
+    data.masks = left.masks.iter().chain(&right.masks).cloned().collect();
+    data.unknown_columns = left.unknown_columns.clone();
+    for column in &right.unknown_columns {
+        if !data.unknown_columns.contains(column) {
+            data.unknown_columns.push(column.clone());
+        }
+    }
+    Ok((data, rules))
+}
+
+/// Both rule lists, one entry per column. A column with rules on both sides
+/// must have the same rules on each.
+fn merge_rules(a: &[ColumnRule], b: &[ColumnRule]) -> Result<Vec<ColumnRule>, ValidatorError> {
+    // Later entries for a column win, as in `RuleSet::new`
+    let effective = |rules: &[ColumnRule]| -> Vec<ColumnRule> {
+        let mut columns: Vec<ColumnRule> = Vec::new();
+        for rule in rules {
+            match columns.iter_mut().find(|r| r.column == rule.column) {
+                Some(existing) => *existing = rule.clone(),
+                None => columns.push(rule.clone()),
+            }
+        }
+        columns
+    };
+
+    let mut merged = effective(a);
+    for rule in effective(b) {
+        match merged.iter().find(|r| r.column == rule.column) {
+            Some(existing) if rules_json(existing)? != rules_json(&rule)? => {
+                let message = format!("Rule Conflict: column '{}' has different rules on each side", rule.column);
+                return Err(ValidatorError::new(ErrorKind::Rules, message));
+            }
+            Some(_) => {}
+            None => merged.push(rule),
+        }
+    }
+    Ok(merged)
+}
+
+fn rules_json(rule: &ColumnRule) -> Result<serde_json::Value, ValidatorError> {
+    serde_json::to_value(&rule.rules).map_err(|e| ValidatorError::new(ErrorKind::Rules, e.to_string()))
+}
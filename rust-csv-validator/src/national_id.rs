@@ -0,0 +1,158 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::countries::alpha2;
+
+// --- National ID Formats ---
+//
+// Structural checks on national identification numbers for the
+// `nationalid` rule: layout, ranges never issued and check digits. They are
+// format-only. A number that passes may still be unassigned or belong to
+// someone else, and only the issuing authority can say otherwise.
+
+/// The check for `country` (ISO 3166-1 alpha-2 or alpha-3, either case),
+/// if there is one.
+pub(crate) fn national_id_check(country: &str) -> Option<fn(&str) -> bool> {
+    match alpha2(country)? {
+        "US" => Some(is_ssn),
+        "GB" => Some(is_nino),
+        "CA" => Some(is_sin),
+        "ES" => Some(is_dni),
+        "NL" => Some(is_bsn),
+        _ => None,
+    }
+}
+
+/// A US Social Security number, `123-45-6789` or `123456789`. Area 000,
+/// 666 and 900-999, group 00 and serial 0000 are never issued.
+pub(crate) fn is_ssn(value: &str) -> bool {
+    let digits: String = match value.len() {
+        11 if value.as_bytes()[3] == b'-' && value.as_bytes()[6] == b'-' => value.replace('-', ""),
+        9 => value.to_string(),
+        _ => return false,
+    };
+    if digits.len() != 9 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let (area, group, serial) = (&digits[..3], &digits[3..5], &digits[5..]);
+    area != "000" && area != "666" && area < "900" && group != "00" && serial != "0000"
+}
+
+/// A UK National Insurance number, e.g. `QQ 12 34 56 C`, spaced or not and
+/// in either case.
+pub(crate) fn is_nino(value: &str) -> bool {
+    static NINO_REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = NINO_REGEX.get_or_init(|| {
+        Regex::new(r"^(?i)[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]$").unwrap()
+    });
+    // Prefixes the letter classes allow but HMRC never issues
+    let prefix = value.get(..2).map(str::to_ascii_uppercase);
+    regex.is_match(value) && !prefix.is_some_and(|prefix| ["BG", "GB", "KN", "NK", "NT", "TN", "ZZ"].contains(&prefix.as_str()))
+}
+
+/// A Canadian Social Insurance Number: 9 digits, spaced or dashed in
+/// threes or not, passing the Luhn check. None start with 0 or 8.
+fn is_sin(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let digits = match bytes.len() {
+        11 if matches!(bytes[3], b' ' | b'-') && bytes[7] == bytes[3] => value.replace(char::from(bytes[3]), ""),
+        9 => value.to_string(),
+        _ => return false,
+    };
+    let digits: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    digits.len() == 9 && !matches!(digits[0], 0 | 8) && luhn(&digits)
+}
+
+/// A Spanish DNI (`12345678Z`) or NIE (`X1234567L`), the letter checking
+/// the number.
+fn is_dni(value: &str) -> bool {
+    const LETTERS: &[u8; 23] = b"TRWAGMYFPDXBNJZSQVHLCKE";
+    let value = value.to_ascii_uppercase();
+    // A NIE's leading X, Y or Z stands for 0, 1 or 2
+    let number = match value.as_bytes().first() {
+        Some(&b @ b'X'..=b'Z') => format!("{}{}", b - b'X', &value[1..]),
+        _ => value,
+    };
+    let Some((digits, letter)) = number.split_at_checked(8) else { return false };
+    match (digits.parse::<u32>(), letter.as_bytes()) {
+        (Ok(n), &[letter]) => digits.bytes().all(|b| b.is_ascii_digit()) && LETTERS[(n % 23) as usize] == letter,
+        _ => false,
+    }
+}
+
+/// A Dutch BSN: 9 digits passing the eleven test.
+fn is_bsn(value: &str) -> bool {
+    let digits: Vec<i64> = value.chars().filter_map(|c| c.to_digit(10)).map(i64::from).collect();
+    if value.len() != 9 || digits.len() != 9 {
+        return false;
+    }
+    // Weights 9 down to 2, and -1 for the last digit
+    let sum: i64 = digits[..8].iter().zip((2..=9).rev()).map(|(d, w)| d * w).sum::<i64>() - digits[8];
+    sum % 11 == 0 && sum != 0
+}
+
+/// Whether `digits` pass the Luhn check: every second digit from the
+/// right doubled.
+pub(crate) fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits.iter().rev().enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(country: &str, valid: &[&str], invalid: &[&str]) {
+        let is_valid = national_id_check(country).unwrap();
+        for value in valid {
+            assert!(is_valid(value), "{} {} should pass", country, value);
+        }
+        for value in invalid {
+            assert!(!is_valid(value), "{} {} should fail", country, value);
+        }
+    }
+
+    #[test]
+    fn us_social_security_numbers() {
+        check("US", &["123-45-6789", "123456789", "899-99-9999"], &[
+            "000-12-3456", "666-12-3456", "900-12-3456", "123-00-4567", "123-45-0000",
+            "123-456-789", "12345678", "12a-45-6789",
+        ]);
+    }
+
+    #[test]
+    fn uk_national_insurance_numbers() {
+        // QQ 12 34 56 C is the usual specimen, but Q is never a first letter
+        check("GBR", &["AB123456C", "ab 12 34 56 c", "JK123456D"], &[
+            "QQ123456C", "DA123456A", "BG123456A", "AB123456E", "AB12345C", "AO123456A",
+        ]);
+    }
+
+    #[test]
+    fn canadian_social_insurance_numbers() {
+        // 046 454 286 passes Luhn but is a sample number: 0 is never issued
+        check("CA", &["130 692 544", "130-692-544", "130692544"], &[
+            "130 692 545", "130-692 544", "046 454 286", "846 454 283", "13069254",
+        ]);
+    }
+
+    #[test]
+    fn spanish_dni_and_nie_letters() {
+        check("ES", &["12345678Z", "12345678z", "X1234567L", "Y1234567X", "Z1234567R"], &[
+            "12345678A", "X1234567A", "1234567Z", "123456789Z", "A1234567L",
+        ]);
+    }
+
+    #[test]
+    fn dutch_bsn_eleven_test() {
+        check("NL", &["111222333", "123456782"], &["111222334", "000000000", "12345678", "12345678a"]);
+    }
+
+    #[test]
+    fn countries_without_a_check() {
+        assert!(national_id_check("FR").is_none());
+        assert!(national_id_check("nowhere").is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use crate::engine::parse_number;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::Dataset;
+
+// --- Outliers ---
+//
+// Values far from the rest of a numeric column, e.g. a 1,000,000 kg
+// shipment among ones of 10-500 kg, which min/max rules tuned for normal
+// values let through. Empty and non-numeric cells are left out of the
+// statistics and never reported; the `number` rule is there for those.
+// `detect_outliers` reports on one column; the `anomaly` rule applies the
+// same bounds during validation.
+
+/// How `detect_outliers` and the `anomaly` rule decide what's far.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutlierMethod {
+    /// Beyond `threshold` interquartile ranges below the first quartile or
+    /// above the third (Tukey's fences). The outliers themselves barely move
+    /// the quartiles.
+    Iqr,
+    /// More than `threshold` standard deviations from the mean. Extreme
+    /// values inflate the deviation, so in small columns they can hide
+    /// each other.
+    Zscore,
+}
+
+impl OutlierMethod {
+    pub fn parse(method: &str) -> Result<OutlierMethod, ValidatorError> {
+        match method {
+            "iqr" => Ok(OutlierMethod::Iqr),
+            "zscore" => Ok(OutlierMethod::Zscore),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Outlier Method: {}", other))),
+        }
+    }
+
+    pub fn default_threshold(self) -> f64 {
+        match self {
+            OutlierMethod::Iqr => 1.5,
+            OutlierMethod::Zscore => 3.0,
+        }
+    }
+
+    /// The values below the first or above the second of which are
+    /// outliers among `numbers`, or `None` if there are none.
+    pub(crate) fn bounds(self, numbers: &[f64], threshold: f64) -> Option<(f64, f64)> {
+        if numbers.is_empty() {
+            return None;
+        }
+        Some(match self {
+            OutlierMethod::Iqr => {
+                let mut sorted = numbers.to_vec();
+                sorted.sort_unstable_by(f64::total_cmp);
+                let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+                (q1 - threshold * (q3 - q1), q3 + threshold * (q3 - q1))
+            }
+            OutlierMethod::Zscore => {
+                let n = numbers.len() as f64;
+                let mean = numbers.iter().sum::<f64>() / n;
+                let sd = (numbers.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+                (mean - threshold * sd, mean + threshold * sd)
+            }
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct OutlierReport<'a> {
+    pub column: &'a str,
+    pub method: OutlierMethod,
+    pub threshold: f64,
+    /// Numeric cells the statistics come from.
+    pub numeric_cells: usize,
+    /// Values below `lower` or above `upper` are outliers. Both are
+    /// missing when the column has no numbers.
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+    /// In row order.
+    pub outliers: Vec<Outlier<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct Outlier<'a> {
+    pub row: usize,
+    pub row_number: usize,
+    pub value: &'a str,
+}
+
+/// The `p` quantile of `sorted`, interpolating between neighbours.
+fn quantile(sorted: &[f64], p: f64) -> f64 {
+    let pos = (sorted.len() - 1) as f64 * p;
+    let (below, above) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[below] + (sorted[above] - sorted[below]) * (pos - below as f64)
+}
+
+impl Dataset {
+    /// The outliers of column `col`, `threshold` defaulting to the method's.
+    pub(crate) fn outliers(&self, col: usize, method: OutlierMethod, threshold: Option<f64>, progress: &Progress) -> Result<OutlierReport<'_>, ValidatorError> {
+        let threshold = threshold.unwrap_or(method.default_threshold());
+        if !(threshold.is_finite() && threshold > 0.0) {
+            return Err(ValidatorError::new(ErrorKind::Options, format!("Invalid Threshold: {} (must be positive)", threshold)));
+        }
+        let total = self.records.len();
+        let mut numbers: Vec<(usize, f64)> = Vec::new();
+        for (idx, value) in self.records.column(col).enumerate() {
+            progress.row(idx, total)?;
+            if let Ok(number) = parse_number(value.trim()) {
+                if number.is_finite() {
+                    numbers.push((idx, number));
+                }
+            }
+        }
+
+        let mut report = OutlierReport {
+            column: &self.headers[col],
+            method,
+            threshold,
+            numeric_cells: numbers.len(),
+            lower: None,
+            upper: None,
+            outliers: Vec::new(),
+        };
+        let values: Vec<f64> = numbers.iter().map(|&(_, n)| n).collect();
+        let Some((lower, upper)) = method.bounds(&values, threshold) else {
+            return Ok(report);
+        };
+        report.lower = Some(lower);
+        report.upper = Some(upper);
+        report.outliers = numbers.into_iter()
+            .filter(|&(_, x)| x < lower || x > upper)
+            .map(|(row, _)| Outlier { row, row_number: self.row_number(row), value: self.records.get(row, col) })
+            .collect();
+        Ok(report)
+    }
+}
@@ -0,0 +1,466 @@
+use crate::arrow::{ArrowType, ColumnBuffers};
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::Dataset;
+
+// --- Parquet Output ---
+//
+// The valid rows as a Parquet file, for engines that load files rather than
+// streams (DuckDB's `read_parquet`, Spark, pandas). Columns get the types of
+// the Arrow export: DOUBLE, INT32 annotated DATE, or BYTE_ARRAY annotated
+// STRING, all OPTIONAL. Every `ARROW_BATCH_ROWS` rows form a row group with
+// one uncompressed, PLAIN-encoded data page per column.
+
+const MAGIC: &[u8; 4] = b"PAR1";
+
+// `Type`
+const TYPE_INT32: i32 = 1;
+const TYPE_DOUBLE: i32 = 5;
+const TYPE_BYTE_ARRAY: i32 = 6;
+
+// `ConvertedType`
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_DATE: i32 = 6;
+
+// `LogicalType` union tags
+const LOGICAL_STRING: i16 = 1;
+const LOGICAL_DATE: i16 = 6;
+
+// `FieldRepetitionType::OPTIONAL`
+const OPTIONAL: i32 = 1;
+
+// `Encoding`
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+
+// `PageType::DATA_PAGE`
+const DATA_PAGE: i32 = 0;
+
+// `CompressionCodec::UNCOMPRESSED`
+const UNCOMPRESSED: i32 = 0;
+
+impl ArrowType {
+    /// The `SchemaElement` of a column called `name`.
+    fn parquet_schema(self, name: &str) -> Struct {
+        let annotation = match self {
+            ArrowType::Utf8 => Some((CONVERTED_UTF8, LOGICAL_STRING)),
+            ArrowType::Float64 => None,
+            ArrowType::Date32 => Some((CONVERTED_DATE, LOGICAL_DATE)),
+        };
+        let mut element = Struct::default()
+            .field(1, Value::I32(self.physical_type()))
+            .field(3, Value::I32(OPTIONAL))
+            .field(4, Value::Binary(name.as_bytes().to_vec()));
+        if let Some((converted, logical)) = annotation {
+            // The legacy annotation for older readers, then the `LogicalType`
+            // union: a struct with one member set, here an empty struct
+            element = element
+                .field(6, Value::I32(converted))
+                .field(10, Value::Struct(Struct::default().field(logical, Value::Struct(Struct::default()))));
+        }
+        element
+    }
+
+    fn physical_type(self) -> i32 {
+        match self {
+            ArrowType::Utf8 => TYPE_BYTE_ARRAY,
+            ArrowType::Float64 => TYPE_DOUBLE,
+            ArrowType::Date32 => TYPE_INT32,
+        }
+    }
+}
+
+impl ColumnBuffers {
+    /// The non-null values, PLAIN-encoded: length-prefixed text, `f64`s or
+    /// `i32` days.
+    fn plain_values(&self, rows: usize) -> Vec<u8> {
+        let mut plain = Vec::with_capacity(self.values.len() + 4 * rows);
+        for idx in (0..rows).filter(|&idx| self.is_valid(idx)) {
+            match self.kind {
+                ArrowType::Utf8 => {
+                    let offset = |i: usize| i32::from_le_bytes(self.offsets[4 * i..4 * i + 4].try_into().unwrap()) as usize;
+                    let text = &self.values[offset(idx)..offset(idx + 1)];
+                    plain.extend_from_slice(&(text.len() as u32).to_le_bytes());
+                    plain.extend_from_slice(text);
+                }
+                ArrowType::Float64 => plain.extend_from_slice(&self.values[8 * idx..8 * idx + 8]),
+                ArrowType::Date32 => plain.extend_from_slice(&self.values[4 * idx..4 * idx + 4]),
+            }
+        }
+        plain
+    }
+
+    /// The definition levels (1 for a value, 0 for a null) in the RLE /
+    /// bit-packed hybrid encoding, behind their 4-byte length. At bit width
+    /// 1 a single bit-packed run is exactly the validity bitmap.
+    fn definition_levels(&self) -> Vec<u8> {
+        let mut run = Vec::with_capacity(self.validity.len() + 5);
+        put_varint(&mut run, ((self.validity.len() as u64) << 1) | 1);
+        run.extend_from_slice(&self.validity);
+        let mut levels = (run.len() as u32).to_le_bytes().to_vec();
+        levels.extend_from_slice(&run);
+        levels
+    }
+}
+
+impl Dataset {
+    /// The valid rows as a Parquet file.
+    pub(crate) fn write_parquet(&self, progress: &Progress) -> Result<Vec<u8>, ValidatorError> {
+        let types: Vec<ArrowType> = (0..self.headers.len()).map(|col| self.arrow_type(col)).collect();
+        let mut out = MAGIC.to_vec();
+        let mut row_groups = Vec::new();
+        let mut num_rows = 0;
+        self.valid_batches(progress, |rows| {
+            row_groups.push(self.write_row_group(&mut out, &types, rows)?);
+            num_rows += rows.len() as i64;
+            Ok(())
+        })?;
+
+        let root = Struct::default()
+            .field(4, Value::Binary(b"schema".to_vec()))
+            .field(5, Value::I32(self.headers.len() as i32));
+        let columns = self.headers.iter().zip(&types)
+            .map(|(header, kind)| kind.parquet_schema(header));
+        let metadata = Struct::default()
+            .field(1, Value::I32(1))
+            .field(2, Value::Structs(std::iter::once(root).chain(columns).collect()))
+            .field(3, Value::I64(num_rows))
+            .field(4, Value::Structs(row_groups))
+            .field(6, Value::Binary(concat!("rust-csv-validator ", env!("CARGO_PKG_VERSION")).as_bytes().to_vec()));
+        let start = out.len();
+        metadata.write(&mut out);
+        let footer = (out.len() - start) as u32;
+        out.extend_from_slice(&footer.to_le_bytes());
+        out.extend_from_slice(MAGIC);
+        Ok(out)
+    }
+
+    /// Writes one data page per column of `rows` and returns the `RowGroup`
+    /// describing them.
+    fn write_row_group(&self, out: &mut Vec<u8>, types: &[ArrowType], rows: &[usize]) -> Result<Struct, ValidatorError> {
+        let too_large = || ValidatorError::new(ErrorKind::Export, "Parquet Export: a page exceeds 2 GiB");
+        let mut chunks = Vec::with_capacity(types.len());
+        let mut group_size = 0;
+        for (col, &kind) in types.iter().enumerate() {
+            let column = self.column_buffers(col, kind, rows)?;
+            let mut page = column.definition_levels();
+            page.extend_from_slice(&column.plain_values(rows.len()));
+            let page_size = i32::try_from(page.len()).map_err(|_| too_large())?;
+
+            let data_page_header = Struct::default()
+                .field(1, Value::I32(rows.len() as i32))
+                .field(2, Value::I32(ENCODING_PLAIN))
+                .field(3, Value::I32(ENCODING_RLE))
+                .field(4, Value::I32(ENCODING_RLE));
+            let page_header = Struct::default()
+                .field(1, Value::I32(DATA_PAGE))
+                .field(2, Value::I32(page_size))
+                .field(3, Value::I32(page_size))
+                .field(5, Value::Struct(data_page_header));
+            let offset = out.len() as i64;
+            page_header.write(out);
+            out.extend_from_slice(&page);
+            let chunk_size = out.len() as i64 - offset;
+            group_size += chunk_size;
+
+            let metadata = Struct::default()
+                .field(1, Value::I32(kind.physical_type()))
+                .field(2, Value::I32s(vec![ENCODING_PLAIN, ENCODING_RLE]))
+                .field(3, Value::Binaries(vec![self.headers[col].as_bytes().to_vec()]))
+                .field(4, Value::I32(UNCOMPRESSED))
+                .field(5, Value::I64(rows.len() as i64))
+                .field(6, Value::I64(chunk_size))
+                .field(7, Value::I64(chunk_size))
+                .field(9, Value::I64(offset));
+            chunks.push(Struct::default().field(2, Value::I64(offset)).field(3, Value::Struct(metadata)));
+        }
+        Ok(Struct::default()
+            .field(1, Value::Structs(chunks))
+            .field(2, Value::I64(group_size))
+            .field(3, Value::I64(rows.len() as i64)))
+    }
+}
+
+// --- Thrift Compact Protocol ---
+//
+// Just enough of it for Parquet's `FileMetaData` and `PageHeader`: structs
+// of integers, binaries, structs and lists of those. Fields are written in
+// the order they're added, which should be by id to keep their headers short.
+
+// Compact type ids
+const COMPACT_I32: u8 = 5;
+const COMPACT_I64: u8 = 6;
+const COMPACT_BINARY: u8 = 8;
+const COMPACT_LIST: u8 = 9;
+const COMPACT_STRUCT: u8 = 12;
+
+enum Value {
+    I32(i32),
+    I64(i64),
+    Binary(Vec<u8>),
+    Struct(Struct),
+    I32s(Vec<i32>),
+    Binaries(Vec<Vec<u8>>),
+    Structs(Vec<Struct>),
+}
+
+impl Value {
+    fn compact_type(&self) -> u8 {
+        match self {
+            Value::I32(_) => COMPACT_I32,
+            Value::I64(_) => COMPACT_I64,
+            Value::Binary(_) => COMPACT_BINARY,
+            Value::Struct(_) => COMPACT_STRUCT,
+            Value::I32s(_) | Value::Binaries(_) | Value::Structs(_) => COMPACT_LIST,
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::I32(v) => put_varint(out, zigzag(i64::from(*v))),
+            Value::I64(v) => put_varint(out, zigzag(*v)),
+            Value::Binary(bytes) => {
+                put_varint(out, bytes.len() as u64);
+                out.extend_from_slice(bytes);
+            }
+            Value::Struct(fields) => fields.write(out),
+            Value::I32s(items) => {
+                list_header(out, COMPACT_I32, items.len());
+                for &item in items {
+                    Value::I32(item).write(out);
+                }
+            }
+            Value::Binaries(items) => {
+                list_header(out, COMPACT_BINARY, items.len());
+                for item in items {
+                    put_varint(out, item.len() as u64);
+                    out.extend_from_slice(item);
+                }
+            }
+            Value::Structs(items) => {
+                list_header(out, COMPACT_STRUCT, items.len());
+                for item in items {
+                    item.write(out);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Struct {
+    // (field id, value)
+    fields: Vec<(i16, Value)>,
+}
+
+impl Struct {
+    fn field(mut self, id: i16, value: Value) -> Struct {
+        self.fields.push((id, value));
+        self
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        let mut last = 0;
+        for (id, value) in &self.fields {
+            match id - last {
+                delta @ 1..=15 => out.push(((delta as u8) << 4) | value.compact_type()),
+                _ => {
+                    out.push(value.compact_type());
+                    put_varint(out, zigzag(i64::from(*id)));
+                }
+            }
+            value.write(out);
+            last = *id;
+        }
+        // Stop field
+        out.push(0);
+    }
+}
+
+fn list_header(out: &mut Vec<u8>, element_type: u8, len: usize) {
+    if len < 15 {
+        out.push(((len as u8) << 4) | element_type);
+    } else {
+        out.push(0xf0 | element_type);
+        put_varint(out, len as u64);
+    }
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+/// Unsigned LEB128, as both Thrift and the RLE hybrid encoding use.
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::Validator;
+
+    #[derive(Debug, PartialEq)]
+    enum Thrift {
+        Int(i64),
+        Binary(Vec<u8>),
+        List(Vec<Thrift>),
+        Struct(BTreeMap<i16, Thrift>),
+    }
+
+    impl Thrift {
+        fn int(&self) -> i64 {
+            match self {
+                Thrift::Int(v) => *v,
+                other => panic!("not an integer: {:?}", other),
+            }
+        }
+
+        fn list(&self) -> &[Thrift] {
+            match self {
+                Thrift::List(items) => items,
+                other => panic!("not a list: {:?}", other),
+            }
+        }
+
+        fn text(&self) -> &str {
+            match self {
+                Thrift::Binary(bytes) => std::str::from_utf8(bytes).unwrap(),
+                other => panic!("not a binary: {:?}", other),
+            }
+        }
+    }
+
+    impl std::ops::Index<i16> for Thrift {
+        type Output = Thrift;
+
+        fn index(&self, id: i16) -> &Thrift {
+            match self {
+                Thrift::Struct(fields) => &fields[&id],
+                other => panic!("not a struct: {:?}", other),
+            }
+        }
+    }
+
+    /// Reads the compact-protocol types the writer uses.
+    struct Reader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+
+    impl Reader<'_> {
+        fn byte(&mut self) -> u8 {
+            self.pos += 1;
+            self.buf[self.pos - 1]
+        }
+
+        fn varint(&mut self) -> u64 {
+            let mut value = 0;
+            for shift in (0..).step_by(7) {
+                let byte = self.byte();
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            value
+        }
+
+        fn zigzag(&mut self) -> i64 {
+            let v = self.varint();
+            (v >> 1) as i64 ^ -((v & 1) as i64)
+        }
+
+        fn value(&mut self, kind: u8) -> Thrift {
+            match kind {
+                5 | 6 => Thrift::Int(self.zigzag()),
+                8 => {
+                    let len = self.varint() as usize;
+                    self.pos += len;
+                    Thrift::Binary(self.buf[self.pos - len..self.pos].to_vec())
+                }
+                9 => {
+                    let header = self.byte();
+                    let len = match header >> 4 {
+                        15 => self.varint() as usize,
+                        len => len as usize,
+                    };
+                    Thrift::List((0..len).map(|_| self.value(header & 0x0f)).collect())
+                }
+                12 => self.structure(),
+                other => panic!("unexpected type {}", other),
+            }
+        }
+
+        fn structure(&mut self) -> Thrift {
+            let mut fields = BTreeMap::new();
+            let mut last = 0;
+            loop {
+                let header = self.byte();
+                if header == 0 {
+                    return Thrift::Struct(fields);
+                }
+                let id = match header >> 4 {
+                    0 => self.zigzag() as i16,
+                    delta => last + delta as i16,
+                };
+                let value = self.value(header & 0x0f);
+                assert!(fields.insert(id, value).is_none());
+                last = id;
+            }
+        }
+    }
+
+    #[test]
+    fn file_decodes_to_the_schema_and_valid_rows() {
+        let csv = "amount,when,note\n1.5,2024-02-03,kept\nx,2024-02-04,dropped\nNA,2024-02-05,\n-2,1969-12-31,ünï\n";
+        let rules = r#"[{"column": "amount", "rules": [{"type": "number", "allow_empty": true}]}, {"column": "when", "rules": [{"type": "date"}]}]"#;
+        let options = r#"{"validation": {"null_values": ["NA"]}}"#;
+        let bytes = Validator::from_json(csv, rules, Some(options)).unwrap().export_parquet().unwrap();
+
+        assert_eq!((&bytes[..4], &bytes[bytes.len() - 4..]), (&b"PAR1"[..], &b"PAR1"[..]));
+        let footer = u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap()) as usize;
+        let mut reader = Reader { buf: &bytes, pos: bytes.len() - 8 - footer };
+        let metadata = reader.structure();
+        assert_eq!(reader.pos, bytes.len() - 8);
+        assert_eq!(metadata[3].int(), 3);
+
+        let schema = metadata[2].list();
+        assert_eq!((schema[0][4].text(), schema[0][5].int()), ("schema", 3));
+        let columns: Vec<(&str, i64, i64)> = schema[1..].iter().map(|e| (e[4].text(), e[1].int(), e[3].int())).collect();
+        assert_eq!(columns, [("amount", 5, 1), ("when", 1, 1), ("note", 6, 1)]);
+        assert_eq!(schema[2][6].int(), 6);
+        assert_eq!(schema[3][10][1], Thrift::Struct(BTreeMap::new()));
+
+        let row_groups = metadata[4].list();
+        assert_eq!(row_groups.len(), 1);
+        assert_eq!(row_groups[0][3].int(), 3);
+        let pages: Vec<(Vec<u8>, Vec<u8>)> = row_groups[0][1].list().iter()
+            .map(|chunk| {
+                let meta = &chunk[3];
+                assert_eq!((meta[4].int(), meta[5].int()), (0, 3));
+                let mut reader = Reader { buf: &bytes, pos: meta[9].int() as usize };
+                let header = reader.structure();
+                let size = header[2].int() as usize;
+                assert_eq!((header[1].int(), header[3].int()), (0, size as i64));
+                assert_eq!((header[5][1].int(), header[5][2].int(), header[5][3].int()), (3, 0, 3));
+                assert_eq!(reader.pos + size - meta[9].int() as usize, meta[7].int() as usize);
+
+                // Definition levels: their length, then one bit-packed run
+                // of a single group of 8
+                let page = &bytes[reader.pos..reader.pos + size];
+                assert_eq!(page[..5], [2, 0, 0, 0, 0b11]);
+                (vec![page[5]], page[6..].to_vec())
+            })
+            .collect();
+
+        assert_eq!(pages[0], (vec![0b101], [1.5f64.to_le_bytes(), (-2f64).to_le_bytes()].concat()));
+        assert_eq!(pages[1], (vec![0b111], [19756i32, 19758, -1].iter().flat_map(|d| d.to_le_bytes()).collect()));
+        assert_eq!(pages[2], (vec![0b101], [&4u32.to_le_bytes()[..], b"kept", &("ünï".len() as u32).to_le_bytes(), "ünï".as_bytes()].concat()));
+    }
+}
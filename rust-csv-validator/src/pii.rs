@@ -0,0 +1,209 @@
+use serde::Serialize;
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::national_id::{is_nino, is_ssn, luhn};
+use crate::progress::{unix_ms, Cancelled, Progress};
+use crate::report::iso8601;
+use crate::rules::email_regex;
+use crate::Dataset;
+
+// --- PII Detection ---
+//
+// A heuristic scan of every column, with or without rules, for values that
+// look like personal data. Each non-empty cell counts towards at most one
+// kind, tried from the most specific pattern to the least.
+
+/// What a cell looks like.
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    /// 13-19 digits (spaces and dashes allowed) passing the Luhn check.
+    CreditCard,
+    /// A US Social Security number (`123-45-6789`) or a UK National
+    /// Insurance number (`AB 12 34 56 C`).
+    NationalId,
+    Email,
+    /// 10-15 digits, optionally with a leading `+` and spaces, dots,
+    /// dashes or parentheses, e.g. `+44 20 7946 0958`.
+    Phone,
+}
+
+const KINDS: [PiiKind; 4] = [PiiKind::CreditCard, PiiKind::NationalId, PiiKind::Email, PiiKind::Phone];
+
+/// One kind of PII found in one column.
+#[derive(Serialize)]
+pub struct PiiFinding<'a> {
+    pub column: &'a str,
+    pub kind: PiiKind,
+    /// Cells of this kind.
+    pub matches: usize,
+    /// Non-empty cells in the column.
+    pub cells: usize,
+    /// `matches / cells`.
+    pub fraction: f64,
+}
+
+impl PiiKind {
+    fn matches(self, value: &str) -> bool {
+        match self {
+            PiiKind::CreditCard => is_card_number(value),
+            PiiKind::NationalId => is_dashed_ssn(value) || is_nino(value),
+            PiiKind::Email => email_regex().is_match(value),
+            PiiKind::Phone => is_phone_number(value),
+        }
+    }
+}
+
+fn is_card_number(value: &str) -> bool {
+    if !value.chars().all(|c| c.is_ascii_digit() || c == ' ' || c == '-') {
+        return false;
+    }
+    let digits: Vec<u32> = value.chars().filter_map(|c| c.to_digit(10)).collect();
+    (13..=19).contains(&digits.len()) && luhn(&digits)
+}
+
+fn is_phone_number(value: &str) -> bool {
+    let body = value.strip_prefix('+').unwrap_or(value);
+    if !body.chars().all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '.' | '(' | ')')) {
+        return false;
+    }
+    let digits = body.chars().filter(char::is_ascii_digit).count();
+    (10..=15).contains(&digits)
+}
+
+/// Only the dashed form: nine bare digits are as likely to be anything else.
+fn is_dashed_ssn(value: &str) -> bool {
+    value.len() == 11 && is_ssn(value)
+}
+
+// Per column: non-empty cells, and matches per entry of `KINDS`
+type ColumnCounts = (usize, [usize; KINDS.len()]);
+
+impl Dataset {
+    fn pii_counts(&self, progress: &Progress) -> Result<Vec<ColumnCounts>, Cancelled> {
+        let total = self.records.len() * self.headers.len();
+        let mut columns = Vec::with_capacity(self.headers.len());
+        for col in 0..self.headers.len() {
+            let mut counts = [0; KINDS.len()];
+            let mut cells = 0;
+            for (idx, value) in self.records.column(col).enumerate() {
+                progress.row(col * self.records.len() + idx, total)?;
+                let value = value.trim();
+                if value.is_empty() {
+                    continue;
+                }
+                cells += 1;
+                if let Some(kind) = KINDS.iter().position(|kind| kind.matches(value)) {
+                    counts[kind] += 1;
+                }
+            }
+            columns.push((cells, counts));
+        }
+        Ok(columns)
+    }
+
+    /// Every (column, kind) pair with at least one match, in column order.
+    pub(crate) fn detect_pii(&self, progress: &Progress) -> Result<Vec<PiiFinding<'_>>, Cancelled> {
+        let mut findings = Vec::new();
+        for (header, (cells, counts)) in self.headers.iter().zip(self.pii_counts(progress)?) {
+            for (kind, &matches) in KINDS.iter().zip(&counts) {
+                if matches > 0 {
+                    findings.push(PiiFinding { column: header, kind: *kind, matches, cells, fraction: matches as f64 / cells as f64 });
+                }
+            }
+        }
+        Ok(findings)
+    }
+}
+
+// --- Privacy Report ---
+//
+// Versioned JSON for the processing records kept under GDPR: which columns
+// hold personal data and what was done to them. Like the audit report, bump
+// `PRIVACY_REPORT_VERSION` whenever a field changes meaning or is removed.
+// Cell values never appear in it.
+
+pub const PRIVACY_REPORT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct PrivacyReport<'a> {
+    report_version: u32,
+    /// ISO 8601 UTC time the report was generated.
+    generated_at: String,
+    row_count: usize,
+    column_count: usize,
+    /// Columns with personal data detected or a mask applied, in column order.
+    personal_data_columns: Vec<PrivacyColumn<'a>>,
+}
+
+#[derive(Serialize)]
+struct PrivacyColumn<'a> {
+    column: &'a str,
+    /// Non-empty cells.
+    cells: usize,
+    /// What the values look like now. A masked column can still match,
+    /// e.g. `partial` keeps an email's domain.
+    detected: Vec<Detection>,
+    /// The masks applied, in order; empty if the values are as loaded.
+    masking: Vec<MaskEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct Detection {
+    kind: PiiKind,
+    matches: usize,
+    fraction: f64,
+}
+
+#[derive(Serialize)]
+struct MaskEntry<'a> {
+    strategy: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    algorithm: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    salted: bool,
+    cells: usize,
+}
+
+impl Dataset {
+    pub(crate) fn privacy_report(&self, progress: &Progress) -> Result<String, ValidatorError> {
+        let mut columns = Vec::new();
+        for (header, (cells, counts)) in self.headers.iter().zip(self.pii_counts(progress)?) {
+            let detected: Vec<Detection> = KINDS.iter().zip(&counts)
+                .filter(|(_, &matches)| matches > 0)
+                .map(|(&kind, &matches)| Detection { kind, matches, fraction: matches as f64 / cells as f64 })
+                .collect();
+            let masking: Vec<MaskEntry> = self.masks.iter()
+                .filter(|mask| &mask.column == header)
+                .map(|mask| MaskEntry { strategy: &mask.strategy, algorithm: mask.algorithm.as_deref(), salted: mask.salted, cells: mask.cells })
+                .collect();
+            if !detected.is_empty() || !masking.is_empty() {
+                columns.push(PrivacyColumn { column: header, cells, detected, masking });
+            }
+        }
+        let report = PrivacyReport {
+            report_version: PRIVACY_REPORT_VERSION,
+            generated_at: iso8601(unix_ms()),
+            row_count: self.records.len(),
+            column_count: self.headers.len(),
+            personal_data_columns: columns,
+        };
+        serde_json::to_string_pretty(&report).map_err(|e| ValidatorError::new(ErrorKind::Export, e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn national_ids_use_the_nationalid_checks() {
+        let id = |value| PiiKind::NationalId.matches(value);
+        assert!(id("123-45-6789"));
+        assert!(id("AB 12 34 56 C"));
+        for never_issued in ["000-12-3456", "666-12-3456", "900-12-3456", "123-00-4567", "123-45-0000"] {
+            assert!(!id(never_issued), "{}", never_issued);
+        }
+        assert!(!id("123456789"));
+    }
+}
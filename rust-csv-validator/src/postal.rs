@@ -0,0 +1,93 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::countries::alpha2;
+
+// --- Postal Code Formats ---
+//
+// The shape of a postal code in each country that uses them, compiled in
+// for the `postalcode` rule. Patterns describe the format, not the codes in
+// use, so a well-formed code that was never assigned still passes. Letters
+// are upper case here; the rule matches either case.
+
+/// ISO 3166-1 alpha-2 code and unanchored pattern, sorted by code.
+const POSTAL_CODES: [(&str, &str); 58] = [
+    ("AR", r"[A-HJ-NP-Z]?\d{4}(?:[A-Z]{3})?"),
+    ("AT", r"\d{4}"),
+    ("AU", r"\d{4}"),
+    ("BE", r"\d{4}"),
+    ("BG", r"\d{4}"),
+    ("BR", r"\d{5}-?\d{3}"),
+    ("CA", r"[ABCEGHJ-NPRSTVXY]\d[ABCEGHJ-NPRSTV-Z] ?\d[ABCEGHJ-NPRSTV-Z]\d"),
+    ("CH", r"\d{4}"),
+    ("CL", r"\d{7}|\d{3}-\d{4}"),
+    ("CN", r"\d{6}"),
+    ("CO", r"\d{6}"),
+    ("CZ", r"\d{3} ?\d{2}"),
+    ("DE", r"\d{5}"),
+    ("DK", r"\d{4}"),
+    ("EE", r"\d{5}"),
+    ("EG", r"\d{5}"),
+    ("ES", r"(?:0[1-9]|[1-4]\d|5[0-2])\d{3}"),
+    ("FI", r"\d{5}"),
+    ("FR", r"\d{2} ?\d{3}"),
+    ("GB", r"GIR ?0AA|[A-PR-UWYZ](?:\d{1,2}|[A-HK-Y]\d{1,2}|\d[A-HJKPS-UW]|[A-HK-Y]\d[ABEHMNPRV-Y]) ?\d[ABD-HJLNP-UW-Z]{2}"),
+    ("GR", r"\d{3} ?\d{2}"),
+    ("HR", r"\d{5}"),
+    ("HU", r"\d{4}"),
+    ("ID", r"\d{5}"),
+    ("IE", r"(?:[AC-FHKNPRTV-Y]\d{2}|D6W) ?[0-9AC-FHKNPRTV-Y]{4}"),
+    ("IL", r"\d{5}(?:\d{2})?"),
+    ("IN", r"[1-9]\d{2} ?\d{3}"),
+    ("IS", r"\d{3}"),
+    ("IT", r"\d{5}"),
+    ("JP", r"\d{3}-?\d{4}"),
+    ("KE", r"\d{5}"),
+    ("KR", r"\d{5}"),
+    ("LT", r"(?:LT-)?\d{5}"),
+    ("LU", r"(?:L-)?\d{4}"),
+    ("LV", r"(?:LV-)?\d{4}"),
+    ("MA", r"\d{5}"),
+    ("MX", r"\d{5}"),
+    ("MY", r"\d{5}"),
+    ("NG", r"\d{6}"),
+    ("NL", r"[1-9]\d{3} ?(?:[A-RT-Z][A-Z]|S[BCE-RT-Z])"),
+    ("NO", r"\d{4}"),
+    ("NZ", r"\d{4}"),
+    ("PE", r"\d{5}"),
+    ("PH", r"\d{4}"),
+    ("PK", r"\d{5}"),
+    ("PL", r"\d{2}-\d{3}"),
+    ("PT", r"\d{4}-\d{3}"),
+    ("RO", r"\d{6}"),
+    ("RU", r"\d{6}"),
+    ("SA", r"\d{5}(?:-\d{4})?"),
+    ("SE", r"\d{3} ?\d{2}"),
+    ("SG", r"\d{6}"),
+    ("SI", r"(?:SI-)?\d{4}"),
+    ("SK", r"\d{3} ?\d{2}"),
+    ("TH", r"\d{5}"),
+    ("TR", r"\d{5}"),
+    ("US", r"\d{5}(?:-\d{4})?"),
+    ("ZA", r"\d{4}"),
+];
+
+/// The pattern for `country`, an ISO 3166-1 alpha-2 or alpha-3 code in
+/// either case.
+pub(crate) fn postal_pattern(country: &str) -> Option<&'static str> {
+    postal_index(country).map(|i| POSTAL_CODES[i].1)
+}
+
+/// `postal_pattern`, compiled to match a whole value in either case.
+pub(crate) fn postal_regex(country: &str) -> Option<&'static Regex> {
+    static POSTAL_REGEXES: OnceLock<Vec<Regex>> = OnceLock::new();
+    let regexes = POSTAL_REGEXES.get_or_init(|| {
+        POSTAL_CODES.iter().map(|(_, pattern)| Regex::new(&format!("(?i)^(?:{})$", pattern)).unwrap()).collect()
+    });
+    postal_index(country).map(|i| &regexes[i])
+}
+
+fn postal_index(country: &str) -> Option<usize> {
+    let alpha2 = alpha2(country)?;
+    POSTAL_CODES.binary_search_by(|&(code, _)| code.cmp(alpha2)).ok()
+}
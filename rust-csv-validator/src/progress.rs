@@ -0,0 +1,198 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// --- Progress Reporting & Cancellation ---
+
+// Rows processed between two progress callbacks
+const PROGRESS_INTERVAL_ROWS: usize = 50_000;
+// Rows processed between two checks of the cancel flag
+const CANCEL_CHECK_ROWS: usize = 1024;
+
+/// Shared flag that lets JS abort a long validation or export without
+/// throwing away the loaded data. Flip it from a progress callback, a UI
+/// event between async batches, or another worker.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CancellationToken {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+
+    /// Clears a previous cancel request.
+    pub fn reset(&self) {
+        self.flag.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Milliseconds from an arbitrary fixed point, for measuring durations.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    use wasm_bindgen::JsCast;
+    // `performance` exists on both window and worker globals
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+        .ok()
+        .and_then(|p| p.dyn_into::<web_sys::Performance>().ok())
+        .map_or_else(js_sys::Date::now, |p| p.now())
+}
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn now_ms() -> f64 {
+    static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    START.get_or_init(std::time::Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Wall-clock time as milliseconds since the Unix epoch.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn unix_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn unix_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_millis() as f64)
+}
+
+#[cfg(feature = "wasm")]
+/// Splits `0..total` into consecutive ranges of at most `size` rows.
+pub fn batches(total: usize, size: usize) -> impl Iterator<Item = std::ops::Range<usize>> {
+    (0..total).step_by(size).map(move |start| start..(start + size).min(total))
+}
+
+/// Resolves on a fresh macrotask (`setTimeout(0)`), giving the browser a
+/// chance to handle input and paint. Resolves immediately without a timer.
+#[cfg(feature = "wasm")]
+pub async fn yield_to_event_loop() {
+    use wasm_bindgen::JsCast;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let global = js_sys::global();
+        let set_timeout = js_sys::Reflect::get(&global, &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        let _ = match set_timeout {
+            Some(set_timeout) => set_timeout.call2(&global, &resolve, &JsValue::from_f64(0.0)),
+            None => resolve.call0(&JsValue::NULL),
+        };
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Returned by operations that stopped because of a cancel request.
+#[derive(Debug)]
+pub struct Cancelled;
+
+#[cfg(feature = "wasm")]
+impl From<Cancelled> for JsValue {
+    fn from(_: Cancelled) -> JsValue {
+        JsValue::from_str("Operation Cancelled")
+    }
+}
+
+/// A JS `on_progress(percent, phase)` function.
+#[cfg(feature = "wasm")]
+pub type Callback = js_sys::Function;
+
+/// Without the JS API there is no JS function to call back.
+#[cfg(not(feature = "wasm"))]
+pub type Callback = std::convert::Infallible;
+
+/// A Rust `on_progress(percent, phase)` closure, as `Validator` takes it.
+pub type ProgressFn = dyn Fn(f64, &str) + Send + Sync;
+
+#[derive(Clone, Copy)]
+enum Sink<'a> {
+    Js(&'a Callback),
+    Rust(&'a ProgressFn),
+}
+
+/// Forwards `(percent, phase)` updates to an optional `on_progress`
+/// callback while a long operation runs, and stops it between row batches
+/// once cancelled. Errors thrown by a JS callback are ignored so a broken
+/// progress bar can't abort validation.
+pub struct Progress<'a> {
+    sink: Option<Sink<'a>>,
+    phase: &'static str,
+    cancel: Option<&'a AtomicBool>,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(callback: Option<&'a Callback>, phase: &'static str) -> Progress<'a> {
+        Progress { sink: callback.map(Sink::Js), phase, cancel: None }
+    }
+
+    /// Like `new`, reporting to a Rust closure.
+    pub fn with_fn(callback: Option<&'a ProgressFn>, phase: &'static str) -> Progress<'a> {
+        Progress { sink: callback.map(Sink::Rust), phase, cancel: None }
+    }
+
+    /// Makes the operation stop early once `token` is cancelled.
+    pub fn cancellable(mut self, token: &'a CancellationToken) -> Progress<'a> {
+        self.cancel = Some(&token.flag);
+        self
+    }
+
+    /// A progress that never calls back into JS, for worker threads.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    pub fn detached(token: &'a CancellationToken) -> Progress<'a> {
+        Progress { sink: None, phase: "", cancel: Some(&token.flag) }
+    }
+
+    /// Called for every row `idx` of `total`; reports every few thousand rows.
+    pub fn row(&self, idx: usize, total: usize) -> Result<(), Cancelled> {
+        self.step(idx, || idx as f64 * 100.0 / total.max(1) as f64)
+    }
+
+    /// Like `row`, for callers that compute the percentage some other way.
+    pub fn step(&self, idx: usize, percent: impl FnOnce() -> f64) -> Result<(), Cancelled> {
+        if self.sink.is_some() && idx > 0 && idx.is_multiple_of(PROGRESS_INTERVAL_ROWS) {
+            self.report(percent());
+        }
+        if idx.is_multiple_of(CANCEL_CHECK_ROWS) {
+            self.check()?;
+        }
+        Ok(())
+    }
+
+    /// Fails if a cancel has been requested.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        match self.cancel {
+            Some(flag) if flag.load(Ordering::Relaxed) => Err(Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn finish(&self) {
+        self.report(100.0);
+    }
+
+    fn report(&self, percent: f64) {
+        let percent = percent.min(100.0);
+        match self.sink {
+            Some(Sink::Rust(callback)) => callback(percent, self.phase),
+            #[cfg(feature = "wasm")]
+            Some(Sink::Js(callback)) => {
+                let _ = callback.call2(&JsValue::NULL, &JsValue::from_f64(percent), &JsValue::from_str(self.phase));
+            }
+            #[cfg(not(feature = "wasm"))]
+            Some(Sink::Js(never)) => match *never {},
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::engine::parse_number;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::rules::depends_on;
+use crate::Dataset;
+
+// --- Row Queries ---
+
+/// A row filter, passed as JSON to `find_rows`, e.g.
+/// `{"column": "status", "op": "equals", "value": "pending"}`.
+#[derive(Deserialize)]
+pub struct RowQuery {
+    /// Column to test; without one a row matches if any cell does.
+    pub column: Option<String>,
+    #[serde(flatten)]
+    pub predicate: Predicate,
+    /// Compare text case-insensitively (`equals`, `not_equals`, `contains`, `regex`).
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Predicate {
+    Equals { value: String },
+    NotEquals { value: String },
+    Contains { value: String },
+    Regex { pattern: String },
+    Empty,
+    NotEmpty,
+    // Numeric comparisons; cells that aren't numbers never match
+    Gt { value: f64 },
+    Gte { value: f64 },
+    Lt { value: f64 },
+    Lte { value: f64 },
+}
+
+/// A `Predicate` ready to run against cells.
+enum Matcher {
+    Equals(String),
+    NotEquals(String),
+    Contains(String),
+    Regex(Regex),
+    Empty,
+    NotEmpty,
+    Compare(fn(f64, f64) -> bool, f64),
+}
+
+impl Matcher {
+    fn compile(predicate: &Predicate, ignore_case: bool) -> Result<Matcher, String> {
+        let fold = |text: &str| if ignore_case { text.to_lowercase() } else { text.to_string() };
+        Ok(match predicate {
+            Predicate::Equals { value } => Matcher::Equals(fold(value)),
+            Predicate::NotEquals { value } => Matcher::NotEquals(fold(value)),
+            Predicate::Contains { value } => Matcher::Contains(fold(value)),
+            Predicate::Regex { pattern } => Matcher::Regex(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(ignore_case)
+                    .build()
+                    .map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?,
+            ),
+            Predicate::Empty => Matcher::Empty,
+            Predicate::NotEmpty => Matcher::NotEmpty,
+            Predicate::Gt { value } => Matcher::Compare(|a, b| a > b, *value),
+            Predicate::Gte { value } => Matcher::Compare(|a, b| a >= b, *value),
+            Predicate::Lt { value } => Matcher::Compare(|a, b| a < b, *value),
+            Predicate::Lte { value } => Matcher::Compare(|a, b| a <= b, *value),
+        })
+    }
+
+    fn matches(&self, cell: &str, ignore_case: bool) -> bool {
+        let folded = |cell: &str| if ignore_case { cell.to_lowercase() } else { cell.to_string() };
+        match self {
+            Matcher::Equals(value) if !ignore_case => cell == value,
+            Matcher::Equals(value) => folded(cell) == *value,
+            Matcher::NotEquals(value) if !ignore_case => cell != value,
+            Matcher::NotEquals(value) => folded(cell) != *value,
+            Matcher::Contains(value) if !ignore_case => cell.contains(value.as_str()),
+            Matcher::Contains(value) => folded(cell).contains(value.as_str()),
+            Matcher::Regex(re) => re.is_match(cell),
+            Matcher::Empty => cell.trim().is_empty(),
+            Matcher::NotEmpty => !cell.trim().is_empty(),
+            Matcher::Compare(cmp, value) => parse_number(cell.trim()).is_ok_and(|num| cmp(num, *value)),
+        }
+    }
+}
+
+/// A distinct value and how many rows hold it, from `get_top_values`.
+#[derive(Serialize)]
+pub struct ValueCount<'a> {
+    pub value: &'a str,
+    pub count: usize,
+}
+
+/// Rows sharing a key, from `find_duplicates`.
+#[derive(Serialize)]
+pub struct DuplicateGroup<'a> {
+    /// The key columns' values, in the order the columns were given.
+    pub key: Vec<&'a str>,
+    pub count: usize,
+    /// Current indices, in order.
+    pub rows: Vec<u32>,
+    /// Original row numbers, as error exports show them.
+    pub row_numbers: Vec<usize>,
+}
+
+/// Rows that agree on the key columns but not on `column`, from
+/// `find_dependency_conflicts`.
+#[derive(Serialize)]
+pub struct DependencyConflict<'a> {
+    pub column: &'a str,
+    pub key: Vec<&'a str>,
+    /// Each value of `column` among the rows, in order of first appearance.
+    pub values: Vec<ConflictingValue<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct ConflictingValue<'a> {
+    pub value: &'a str,
+    pub rows: Vec<u32>,
+    pub row_numbers: Vec<usize>,
+}
+
+// A key's values and the rows holding them
+type KeyGroup<'a> = (Vec<&'a str>, Vec<u32>);
+
+impl Dataset {
+    /// The `n` most common values in column `col`, most frequent first; ties
+    /// are ordered by value. Empty cells count as the value `""`.
+    pub(crate) fn top_values(&self, col: usize, n: usize, progress: &Progress) -> Result<Vec<ValueCount<'_>>, ValidatorError> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let total = self.records.len();
+        for (idx, value) in self.records.column(col).enumerate() {
+            *counts.entry(value).or_insert(0) += 1;
+            progress.row(idx, total)?;
+        }
+        let mut values: Vec<ValueCount> = counts.into_iter().map(|(value, count)| ValueCount { value, count }).collect();
+        values.sort_unstable_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(b.value)));
+        values.truncate(n);
+        Ok(values)
+    }
+
+    /// Indices of the rows matching `query`, in order.
+    pub(crate) fn find_rows(&self, query: &RowQuery, progress: &Progress) -> Result<Vec<u32>, ValidatorError> {
+        let matcher = Matcher::compile(&query.predicate, query.ignore_case)
+            .map_err(|e| ValidatorError::new(ErrorKind::Options, format!("Invalid Query: {}", e)))?;
+        let column = query.column.as_deref().map(|name| self.column_index(name)).transpose()?;
+
+        let total = self.records.len();
+        let mut matches = Vec::new();
+        for idx in 0..total {
+            let row = self.records.row(idx);
+            let hit = match column {
+                Some(col) => matcher.matches(row.get(col), query.ignore_case),
+                None => row.iter().any(|cell| matcher.matches(cell, query.ignore_case)),
+            };
+            if hit {
+                matches.push(idx as u32);
+            }
+            progress.row(idx, total)?;
+        }
+        Ok(matches)
+    }
+    /// Groups of two or more rows with equal values in columns `cols`
+    /// (every column when empty), ordered by their first row.
+    pub(crate) fn find_duplicates(&self, cols: &[usize], progress: &Progress) -> Result<Vec<DuplicateGroup<'_>>, ValidatorError> {
+        let every: Vec<usize>;
+        let cols = if cols.is_empty() {
+            every = (0..self.headers.len()).collect();
+            &every
+        } else {
+            cols
+        };
+        Ok(self.key_groups(cols, progress)?.into_iter()
+            .filter(|(_, rows)| rows.len() > 1)
+            .map(|(key, rows)| DuplicateGroup {
+                key,
+                count: rows.len(),
+                row_numbers: rows.iter().map(|&row| self.row_number(row as usize)).collect(),
+                rows,
+            })
+            .collect())
+    }
+
+    /// Checks that the columns `key_cols` determine each of `dependent_cols`:
+    /// the rows the `dependson` rule fails, grouped by key and value. Returns
+    /// one conflict per key and dependent column, ordered by the key's first
+    /// row. Rows with a blank key cell and blank cells aren't compared.
+    pub(crate) fn find_dependency_conflicts(&self, key_cols: &[usize], dependent_cols: &[usize], progress: &Progress) -> Result<Vec<DependencyConflict<'_>>, ValidatorError> {
+        let total = self.records.len();
+        let mut conflicts: Vec<DependencyConflict> = Vec::new();
+        for (done, &col) in dependent_cols.iter().enumerate() {
+            let rule = self.rule_set.fit_rule(&depends_on(key_cols.to_vec()), col, &self.records);
+            let mut index: HashMap<Vec<&str>, usize> = HashMap::new();
+            for idx in 0..total {
+                let row = self.records.row(idx);
+                if self.rule_set.check(&rule, row.get(col), &row).is_some() {
+                    let key: Vec<&str> = key_cols.iter().map(|&key| row.get(key)).collect();
+                    let conflict = *index.entry(key.clone()).or_insert_with(|| {
+                        conflicts.push(DependencyConflict { column: &self.headers[col], key, values: Vec::new() });
+                        conflicts.len() - 1
+                    });
+                    let (value, row_number) = (row.get(col), self.row_number(idx));
+                    let values = &mut conflicts[conflict].values;
+                    match values.iter_mut().find(|v| v.value == value) {
+                        Some(entry) => {
+                            entry.rows.push(idx as u32);
+                            entry.row_numbers.push(row_number);
+                        }
+                        None => values.push(ConflictingValue { value, rows: vec![idx as u32], row_numbers: vec![row_number] }),
+                    }
+                }
+                progress.row(done * total + idx, dependent_cols.len() * total)?;
+            }
+        }
+        // Stable, so a key's conflicts stay in the order of `dependent_cols`
+        conflicts.sort_by_key(|conflict| conflict.values[0].rows[0]);
+        Ok(conflicts)
+    }
+
+    /// Every distinct combination of values in columns `cols` with the rows
+    /// holding it, in order of first appearance.
+    fn key_groups(&self, cols: &[usize], progress: &Progress) -> Result<Vec<KeyGroup<'_>>, ValidatorError> {
+        let total = self.records.len();
+        let mut groups: Vec<KeyGroup> = Vec::new();
+        let mut index: HashMap<Vec<&str>, usize> = HashMap::new();
+        for idx in 0..total {
+            let row = self.records.row(idx);
+            let key: Vec<&str> = cols.iter().map(|&col| row.get(col)).collect();
+            let group = *index.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].1.push(idx as u32);
+            progress.row(idx, total)?;
+        }
+        Ok(groups)
+    }
+}
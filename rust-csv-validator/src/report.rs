@@ -0,0 +1,403 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::engine::error_code;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::export::ExportOptions;
+use crate::progress::{now_ms, unix_ms, Cancelled, Progress};
+use crate::sha256::sha256_hex;
+use crate::{ColumnRule, Dataset, ErrorSummary, RuleTiming, ValidationOptions};
+
+// --- HTML Report ---
+
+/// Whole-file results behind the HTML report.
+pub(crate) struct ReportData {
+    pub(crate) summary: ErrorSummary,
+    pub(crate) invalid_rows: usize,
+}
+
+/// Per-column error counts embedded in the report for charting.
+#[derive(Serialize)]
+struct ChartData<'a> {
+    row_count: usize,
+    column_count: usize,
+    invalid_rows: usize,
+    total_errors: usize,
+    columns: Vec<ChartColumn<'a>>,
+}
+
+#[derive(Serialize)]
+struct ChartColumn<'a> {
+    column: &'a str,
+    errors: BTreeMap<&'a str, usize>,
+}
+
+impl Dataset {
+    pub(crate) fn report_data(&self, progress: &Progress) -> Result<ReportData, Cancelled> {
+        let summary = self.summarize(0..self.records.len(), &ValidationOptions::default(), progress)?;
+        Ok(ReportData { invalid_rows: summary.invalid_rows, summary })
+    }
+
+    /// A standalone HTML page (no external assets) with the overall counts,
+    /// a per-column breakdown with example values, and the counts again as
+    /// JSON in `<script id="report-data">` for anything that wants to chart them.
+        pub(crate) fn html_report(&self, progress: &Progress) -> Result<String, Cancelled> {
+        let ReportData { summary, invalid_rows } = self.report_data(progress)?;
+        let rows = self.records.len();
+
+        let chart = ChartData {
+            row_count: rows,
+            column_count: self.headers.len(),
+            invalid_rows,
+            total_errors: summary.total_errors,
+            columns: self.headers.iter()
+                .map(|h| ChartColumn {
+                    column: h,
+                    errors: summary.stats.get(h)
+                        .map(|counts| counts.iter().map(|(t, &c)| (t.as_str(), c)).collect())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+        };
+        // `</` can't appear inside a script element
+        let chart_json = serde_json::to_string(&chart).unwrap_or_default().replace("</", "<\\/");
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>CSV Validation Report</title>\n");
+        html.push_str(REPORT_STYLE);
+        html.push_str("</head>\n<body>\n<h1>CSV Validation Report</h1>\n");
+
+        let valid_pct = if rows == 0 { 100.0 } else { (rows - invalid_rows) as f64 * 100.0 / rows as f64 };
+        let _ = write!(
+            html,
+            "<table class=\"totals\">\n<tr><th>Rows</th><td>{}</td></tr>\n<tr><th>Columns</th><td>{}</td></tr>\n\
+             <tr><th>Valid rows</th><td>{} ({:.1}%)</td></tr>\n<tr><th>Invalid rows</th><td>{}</td></tr>\n\
+             <tr><th>Total errors</th><td>{}</td></tr>\n</table>\n",
+            rows, self.headers.len(), rows - invalid_rows, valid_pct, invalid_rows, summary.total_errors,
+        );
+
+        html.push_str("<h2>Columns</h2>\n<table>\n<tr><th>Column</th><th>Rules</th><th>Errors</th><th>Error type</th><th>Count</th><th>Example</th></tr>\n");
+        for (col, header) in self.headers.iter().enumerate() {
+            let rules: Vec<&str> = self.rule_set.rules_for(col).iter().map(|r| r.name()).collect();
+            let counts: BTreeMap<&String, &usize> = summary.stats.get(header).map(|c| c.iter().collect()).unwrap_or_default();
+            let column_errors: usize = counts.values().copied().sum();
+            let span = counts.len().max(1);
+
+            let _ = write!(
+                html,
+                "<tr><td rowspan=\"{span}\">{}</td><td rowspan=\"{span}\">{}</td><td rowspan=\"{span}\" class=\"{}\">{}</td>",
+                escape_html(header),
+                if rules.is_empty() { "&mdash;".to_string() } else { escape_html(&rules.join(", ")) },
+                if column_errors > 0 { "bad" } else { "ok" },
+                column_errors,
+            );
+            if counts.is_empty() {
+                html.push_str("<td></td><td></td><td></td></tr>\n");
+            }
+            for (i, (error_type, count)) in counts.iter().enumerate() {
+                if i > 0 {
+                    html.push_str("<tr>");
+                }
+                let example = summary.examples.get(header).and_then(|e| e.get(*error_type)).map_or("", |v| v.as_str());
+                let _ = writeln!(html, "<td>{}</td><td>{}</td><td><code>{}</code></td></tr>", escape_html(error_type), count, escape_html(example));
+            }
+        }
+        html.push_str("</table>\n");
+
+        let _ = write!(html, "<script type=\"application/json\" id=\"report-data\">{}</script>\n</body>\n</html>\n", chart_json);
+        Ok(html)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+const REPORT_STYLE: &str = "<style>\n\
+body { font-family: -apple-system, 'Segoe UI', Roboto, sans-serif; margin: 2rem; color: #1f2937; }\n\
+table { border-collapse: collapse; margin-bottom: 2rem; }\n\
+th, td { border: 1px solid #d1d5db; padding: 0.35rem 0.75rem; text-align: left; vertical-align: top; }\n\
+th { background: #f3f4f6; }\n\
+.totals th { width: 10rem; }\n\
+.bad { color: #b91c1c; font-weight: 600; }\n\
+.ok { color: #15803d; }\n\
+code { white-space: pre-wrap; }\n\
+</style>\n";
+
+// --- Audit Report ---
+//
+// Stable, versioned JSON for archiving next to each import. Bump
+// `REPORT_VERSION` whenever a field changes meaning or is removed; adding
+// fields doesn't need a bump.
+
+pub const REPORT_VERSION: u32 = 1;
+
+// Violations kept per column as samples
+const REPORT_SAMPLES: usize = 5;
+
+#[derive(Serialize)]
+struct AuditReport<'a> {
+    report_version: u32,
+    /// ISO 8601 UTC time the report was generated.
+    generated_at: String,
+    #[serde(flatten)]
+    audit: DatasetAudit<'a>,
+}
+
+/// The per-dataset part of a report, shared with the session report.
+#[derive(Serialize)]
+pub(crate) struct DatasetAudit<'a> {
+    file: FileInfo<'a>,
+    rules: RulesInfo<'a>,
+    summary: ReportSummary,
+    columns: Vec<ColumnReport<'a>>,
+    timings: ReportTimings,
+}
+
+#[derive(Serialize)]
+struct FileInfo<'a> {
+    row_count: usize,
+    column_count: usize,
+    headers: &'a [String],
+    /// SHA-256 of the data as `to_csv()` exports it with default options.
+    data_sha256: String,
+}
+
+#[derive(Serialize)]
+struct RulesInfo<'a> {
+    /// SHA-256 of `definitions` serialized as compact JSON.
+    sha256: String,
+    rule_count: usize,
+    definitions: &'a [ColumnRule],
+}
+
+#[derive(Serialize)]
+struct ReportSummary {
+    valid_rows: usize,
+    invalid_rows: usize,
+    total_errors: usize,
+}
+
+#[derive(Serialize)]
+struct ColumnReport<'a> {
+    column: &'a str,
+    rules: Vec<&'static str>,
+    errors: usize,
+    /// error code -> count
+    error_counts: BTreeMap<&'static str, usize>,
+    /// The first few violations in row order.
+    samples: Vec<ViolationSample<'a>>,
+}
+
+#[derive(Serialize)]
+struct ViolationSample<'a> {
+    row_number: usize,
+    rule: &'static str,
+    error_code: &'static str,
+    message: &'static str,
+    value: &'a str,
+}
+
+#[derive(Serialize)]
+struct ReportTimings {
+    validation_ms: f64,
+    rules: Vec<RuleTiming>,
+}
+
+impl DatasetAudit<'_> {
+    pub(crate) fn passed(&self) -> bool {
+        self.summary.total_errors == 0
+    }
+}
+
+impl Dataset {
+    pub(crate) fn audit_report(&self, rules: &[ColumnRule], progress: &Progress) -> Result<String, ValidatorError> {
+        let report = AuditReport {
+            report_version: REPORT_VERSION,
+            generated_at: iso8601(unix_ms()),
+            audit: self.audit(rules, progress)?,
+        };
+        serde_json::to_string_pretty(&report).map_err(|e| ValidatorError::new(ErrorKind::Export, e.to_string()))
+    }
+
+    pub(crate) fn audit<'a>(&'a self, rules: &'a [ColumnRule], progress: &Progress) -> Result<DatasetAudit<'a>, ValidatorError> {
+        let started = now_ms();
+        let mut invalid = vec![false; self.records.len()];
+        let mut columns = Vec::with_capacity(self.headers.len());
+        let mut timings = Vec::new();
+        let mut total_errors = 0;
+
+        for (col, header) in self.headers.iter().enumerate() {
+            let mut report = ColumnReport {
+                column: header,
+                rules: self.rule_set.rules_for(col).iter().map(|r| r.name()).collect(),
+                errors: 0,
+                error_counts: BTreeMap::new(),
+                samples: Vec::new(),
+            };
+            // With short-circuiting, cells an earlier rule already failed
+            let mut failed = vec![false; if self.rule_set.short_circuit() { self.records.len() } else { 0 }];
+            for (rule_index, rule) in self.rule_set.rules_for(col).iter().enumerate() {
+                if !self.rule_set.is_enabled(col, rule_index) {
+                    continue;
+                }
+                progress.check()?;
+                let rule_started = now_ms();
+                let mut errors = 0;
+                let mut samples = 0;
+                for (row, value) in self.records.column(col).enumerate() {
+                    if failed.get(row) == Some(&true) {
+                        continue;
+                    }
+                    let Some(error_type) = self.rule_set.check(rule, value, &self.records.row(row)) else { continue };
+                    if let Some(slot) = failed.get_mut(row) {
+                        *slot = true;
+                    }
+                    errors += 1;
+                    invalid[row] = true;
+                    let code = error_code(error_type);
+                    *report.error_counts.entry(code).or_insert(0) += 1;
+                    if samples < REPORT_SAMPLES {
+                        samples += 1;
+                        report.samples.push(ViolationSample { row_number: self.row_number(row), rule: rule.name(), error_code: code, message: error_type, value });
+                    }
+                }
+                timings.push(RuleTiming { column: header.clone(), rule_index, rule: rule.name(), millis: now_ms() - rule_started, errors });
+                report.errors += errors;
+            }
+            // Samples were gathered rule by rule; keep the earliest rows overall
+            report.samples.sort_by_key(|s| s.row_number);
+            report.samples.truncate(REPORT_SAMPLES);
+            total_errors += report.errors;
+            columns.push(report);
+        }
+        let validation_ms = now_ms() - started;
+
+        let invalid_rows = invalid.iter().filter(|&&bad| bad).count();
+        let rules_json = serde_json::to_string(rules).map_err(|e| ValidatorError::new(ErrorKind::Export, e.to_string()))?;
+        let data_csv = self.write_all(&ExportOptions::default(), progress)?;
+
+        Ok(DatasetAudit {
+            file: FileInfo {
+                row_count: self.records.len(),
+                column_count: self.headers.len(),
+                headers: &self.headers,
+                data_sha256: sha256_hex(&data_csv),
+            },
+            rules: RulesInfo {
+                sha256: sha256_hex(rules_json.as_bytes()),
+                rule_count: rules.iter().map(|r| r.rules.len()).sum(),
+                definitions: rules,
+            },
+            summary: ReportSummary { valid_rows: self.records.len() - invalid_rows, invalid_rows, total_errors },
+            columns,
+            timings: ReportTimings { validation_ms, rules: timings },
+        })
+    }
+}
+
+// --- Session Report ---
+
+/// Combined report over the datasets of a `ValidationSession`.
+#[derive(Serialize)]
+pub(crate) struct SessionReport<'a> {
+    report_version: u32,
+    generated_at: String,
+    /// `true` only when every dataset is free of errors.
+    passed: bool,
+    summary: SessionSummary,
+    datasets: Vec<SessionDataset<'a>>,
+}
+
+#[derive(Serialize)]
+struct SessionSummary {
+    dataset_count: usize,
+    failed_datasets: Vec<String>,
+    row_count: usize,
+    valid_rows: usize,
+    invalid_rows: usize,
+    total_errors: usize,
+}
+
+#[derive(Serialize)]
+struct SessionDataset<'a> {
+    name: &'a str,
+    passed: bool,
+    #[serde(flatten)]
+    audit: DatasetAudit<'a>,
+}
+
+impl<'a> SessionReport<'a> {
+    pub(crate) fn new(audits: Vec<(&'a str, DatasetAudit<'a>)>) -> SessionReport<'a> {
+        let mut summary = SessionSummary {
+            dataset_count: audits.len(),
+            failed_datasets: Vec::new(),
+            row_count: 0,
+            valid_rows: 0,
+            invalid_rows: 0,
+            total_errors: 0,
+        };
+        let datasets: Vec<SessionDataset> = audits.into_iter()
+            .map(|(name, audit)| {
+                summary.row_count += audit.file.row_count;
+                summary.valid_rows += audit.summary.valid_rows;
+                summary.invalid_rows += audit.summary.invalid_rows;
+                summary.total_errors += audit.summary.total_errors;
+                if !audit.passed() {
+                    summary.failed_datasets.push(name.to_string());
+                }
+                SessionDataset { name, passed: audit.passed(), audit }
+            })
+            .collect();
+
+        SessionReport {
+            report_version: REPORT_VERSION,
+            generated_at: iso8601(unix_ms()),
+            passed: summary.failed_datasets.is_empty(),
+            summary,
+            datasets,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, ValidatorError> {
+        serde_json::to_string_pretty(self).map_err(|e| ValidatorError::new(ErrorKind::Export, e.to_string()))
+    }
+}
+
+/// The (year, month, day) `days` after 1970-01-01, after Howard Hinnant's
+/// date algorithms.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month as u32, day as u32)
+}
+
+/// Inverse of `civil_from_days`.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Milliseconds since the Unix epoch as e.g. "2024-03-01T12:00:00.000Z".
+pub(crate) fn iso8601(unix_ms: f64) -> String {
+    let ms = unix_ms.max(0.0) as u64;
+    let (days, day_ms) = ((ms / 86_400_000) as i64, ms % 86_400_000);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day,
+        day_ms / 3_600_000, day_ms / 60_000 % 60, day_ms / 1000 % 60, day_ms % 1000,
+    )
+}
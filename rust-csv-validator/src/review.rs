@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::Dataset;
+
+// --- Review Annotations ---
+//
+// Reviewers mark rows or single cells with a status and an optional note.
+// Annotations are keyed by the row's original index so they stay attached to
+// it through `sort_by`, and are saved with the processor state.
+
+// Columns added by `ExportOptions::include_review`
+pub const REVIEW_HEADERS: [&str; 2] = ["Review_Status", "Review_Note"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    Reviewed,
+    /// Accepted as-is despite any errors.
+    Accepted,
+    /// Needs follow-up.
+    FollowUp,
+}
+
+impl ReviewStatus {
+    pub fn parse(status: &str) -> Result<ReviewStatus, ValidatorError> {
+        match status {
+            "reviewed" => Ok(ReviewStatus::Reviewed),
+            "accepted" => Ok(ReviewStatus::Accepted),
+            "follow_up" => Ok(ReviewStatus::FollowUp),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Review Status: {}", other))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ReviewStatus::Reviewed => "reviewed",
+            ReviewStatus::Accepted => "accepted",
+            ReviewStatus::FollowUp => "follow_up",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Annotation {
+    /// The annotated cell's column; `None` for the whole row.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    pub status: ReviewStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// An annotation as `get_annotations` reports it.
+#[derive(Serialize)]
+pub struct AnnotationEntry<'a> {
+    /// Current index of the row.
+    row: usize,
+    /// 1-based row number as loaded, as used by the error exports.
+    row_number: usize,
+    #[serde(flatten)]
+    annotation: &'a Annotation,
+}
+
+impl Dataset {
+    /// Sets the annotation of row `idx`, or of one of its cells when `column`
+    /// is given, replacing any earlier one.
+    pub(crate) fn annotate(&mut self, idx: usize, column: Option<String>, status: ReviewStatus, note: Option<String>) -> Result<(), ValidatorError> {
+        self.check_row(idx)?;
+        if let Some(name) = &column {
+            self.column_index(name)?;
+        }
+        let annotations = self.annotations.entry(self.origin(idx)).or_default();
+        let note = note.filter(|n| !n.is_empty());
+        match annotations.iter_mut().find(|a| a.column == column) {
+            Some(existing) => {
+                existing.status = status;
+                existing.note = note;
+            }
+            // Keep the row annotation ahead of the cell ones
+            None if column.is_none() => annotations.insert(0, Annotation { column, status, note }),
+            None => annotations.push(Annotation { column, status, note }),
+        }
+        Ok(())
+    }
+
+    /// Removes the row (or cell) annotation; `false` if there was none.
+    pub(crate) fn clear_annotation(&mut self, idx: usize, column: Option<&str>) -> Result<bool, ValidatorError> {
+        self.check_row(idx)?;
+        let origin = self.origin(idx);
+        let Some(annotations) = self.annotations.get_mut(&origin) else { return Ok(false) };
+        let before = annotations.len();
+        annotations.retain(|a| a.column.as_deref() != column);
+        let removed = annotations.len() != before;
+        if annotations.is_empty() {
+            self.annotations.remove(&origin);
+        }
+        Ok(removed)
+    }
+
+    /// Every annotation in current row order, each row's own one first.
+    pub(crate) fn annotation_entries(&self) -> Vec<AnnotationEntry<'_>> {
+        let mut entries = Vec::new();
+        if self.annotations.is_empty() {
+            return entries;
+        }
+        for idx in 0..self.records.len() {
+            for annotation in self.row_annotations(idx) {
+                entries.push(AnnotationEntry { row: idx, row_number: self.row_number(idx), annotation });
+            }
+        }
+        entries
+    }
+
+    pub(crate) fn row_annotations(&self, idx: usize) -> &[Annotation] {
+        self.annotations.get(&self.origin(idx)).map_or(&[], |a| a.as_slice())
+    }
+
+    /// `Review_Status` and `Review_Note` cells for row `idx`. A row status is
+    /// written as is; cell ones as "column: status", joined with "; ".
+    pub(crate) fn review_cells(&self, idx: usize) -> [String; 2] {
+        let mut statuses = Vec::new();
+        let mut notes = Vec::new();
+        for a in self.row_annotations(idx) {
+            match &a.column {
+                None => statuses.push(a.status.name().to_string()),
+                Some(column) => statuses.push(format!("{}: {}", column, a.status.name())),
+            }
+            match (&a.column, &a.note) {
+                (None, Some(note)) => notes.push(note.clone()),
+                (Some(column), Some(note)) => notes.push(format!("{}: {}", column, note)),
+                (_, None) => {}
+            }
+        }
+        [statuses.join("; "), notes.join("; ")]
+    }
+
+    fn check_row(&self, idx: usize) -> Result<(), ValidatorError> {
+        if idx >= self.records.len() {
+            return Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Row: {}", idx)));
+        }
+        Ok(())
+    }
+}
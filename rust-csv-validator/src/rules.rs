@@ -0,0 +1,1584 @@
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, Engine, GeneralPurpose, GeneralPurposeConfig};
+use regex::Regex;
+use serde::Deserialize;
+use semver::Version;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, OnceLock};
+
+use crate::engine::{parse_number, CompiledRule, Record, Rule, RuleRegistry};
+use crate::countries::ISO_3166;
+use crate::languages::is_language_tag;
+use crate::national_id::national_id_check;
+use crate::outliers::OutlierMethod;
+use crate::postal::postal_regex;
+use crate::timezones::TIME_ZONES;
+use crate::progress::unix_ms;
+use crate::report::{civil_from_days, days_from_civil};
+use crate::{CoordinateKind, DateConstraint, DurationFormat, MacFormat, PathStyle};
+
+// --- Built-in Rules ---
+//
+// The rule types every registry starts with. Each factory reads its
+// parameters from the rule's JSON object, ignoring the `type` key.
+
+pub(crate) fn register_builtins(registry: &mut RuleRegistry) {
+    registry.register("notempty", |_| Ok(Arc::new(NotEmpty)));
+    registry.register("number", |params| {
+        let NumberParams { min, max, allow_empty } = from_params(params)?;
+        Ok(Arc::new(Number { min, max, allow_empty }))
+    });
+    registry.register("email", |params| {
+        let EmailParams { allow_empty } = from_params(params)?;
+        Ok(Arc::new(Email { allow_empty }))
+    });
+    registry.register("regex", |params| {
+        let RegexParams { pattern, allow_empty } = from_params(params)?;
+        let regex = Regex::new(&pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+        Ok(Arc::new(Pattern { regex, allow_empty }))
+    });
+    registry.register("oneof", |params| {
+        let OneOfParams { options } = from_params(params)?;
+        Ok(Arc::new(OneOf(options)))
+    });
+    registry.register("coordinate", |params| {
+        let CoordinateParams { kind, allow_empty } = from_params(params)?;
+        Ok(Arc::new(Coordinate { kind, allow_empty }))
+    });
+    registry.register("countrycode", |params| {
+        let CountryCodeParams { alpha, allow_empty } = from_params(params)?;
+        let mut codes: Vec<&str> = match alpha {
+            2 => ISO_3166.iter().map(|&(code, _)| code).collect(),
+            3 => ISO_3166.iter().map(|&(_, code)| code).collect(),
+            other => return Err(format!("alpha must be 2 or 3, not {}", other)),
+        };
+        codes.sort_unstable();
+        Ok(Arc::new(CountryCode { codes, allow_empty }))
+    });
+    registry.register("languagecode", |params| {
+        let LanguageCodeParams { allow_empty } = from_params(params)?;
+        Ok(Arc::new(LanguageCode { allow_empty }))
+    });
+    registry.register("timezone", |params| {
+        let TimezoneParams { allow_empty } = from_params(params)?;
+        Ok(Arc::new(Timezone { allow_empty }))
+    });
+    registry.register("date", |params| {
+        let DateParams { must_be, min, max, now, allow_empty } = from_params(params)?;
+        let now = match now {
+            Some(date) => parse_date(&date).ok_or_else(|| format!("Invalid now '{}': expected YYYY-MM-DD", date))?,
+            None => today(),
+        };
+        let bound = |name: &str, bound: Option<String>| -> Result<Option<i64>, String> {
+            bound.map(|bound| {
+                date_bound(&bound, now)
+                    .map(day_number)
+                    .ok_or_else(|| format!("Invalid {} '{}': expected YYYY-MM-DD or an offset like -90d", name, bound))
+            }).transpose()
+        };
+        let (min, max) = (bound("min", min)?, bound("max", max)?);
+        Ok(Arc::new(DateRule { must_be, min, max, now: day_number(now), allow_empty }))
+    });
+    registry.register("age", |params| {
+        let AgeParams { min, max, reference_date, allow_empty } = from_params(params)?;
+        let reference = match reference_date {
+            Some(date) => parse_date(&date).ok_or_else(|| format!("Invalid reference_date '{}': expected YYYY-MM-DD", date))?,
+            None => today(),
+        };
+        Ok(Arc::new(Age { min, max, reference, allow_empty }))
+    });
+    registry.register("dependson", |params| {
+        let DependsOnParams { key_columns } = from_params(params)?;
+        if key_columns.is_empty() {
+            return Err("key_columns needs at least one column".to_string());
+        }
+        Ok(Arc::new(DependsOn { key_columns, keys: Vec::new(), seen: None }))
+    });
+    registry.register("anomaly", |params| {
+        let AnomalyParams { method, threshold } = from_params(params)?;
+        let threshold = threshold.unwrap_or(method.default_threshold());
+        if !(threshold.is_finite() && threshold > 0.0) {
+            return Err(format!("threshold must be positive, not {}", threshold));
+        }
+        Ok(Arc::new(Anomaly { method, threshold, bounds: None }))
+    });
+    registry.register("nationalid", |params| {
+        let NationalIdParams { country, allow_empty } = from_params(params)?;
+        let check = national_id_check(&country).ok_or_else(|| format!("No national ID format for country '{}'", country))?;
+        Ok(Arc::new(NationalId { check, allow_empty }))
+    });
+    registry.register("postalcode", |params| {
+        let PostalCodeParams { country, country_column, allow_empty } = from_params(params)?;
+        if country.is_none() && country_column.is_none() {
+            return Err("Set country or country_column".to_string());
+        }
+        let fixed = match country {
+            Some(country) => Some(postal_regex(&country).ok_or_else(|| format!("No postal code format for country '{}'", country))?),
+            None => None,
+        };
+        Ok(Arc::new(PostalCode { fixed, country_column, country_col: None, allow_empty }))
+    });
+    registry.register("path", |params| {
+        let PathParams { style, must_be_absolute, allowed_extensions, allow_empty } = from_params(params)?;
+        let extensions = allowed_extensions.iter()
+            .map(|ext| ext.strip_prefix('.').unwrap_or(ext).to_lowercase())
+            .collect();
+        Ok(Arc::new(FilePath { style, must_be_absolute, extensions, allow_empty }))
+    });
+    registry.register("hostname", |params| {
+        let HostnameParams { require_tld, allow_empty } = from_params(params)?;
+        Ok(Arc::new(Hostname { require_tld, allow_empty }))
+    });
+    registry.register("base64", |params| {
+        let Base64Params { max_decoded_bytes, allow_empty } = from_params(params)?;
+        Ok(Arc::new(Base64 { max_decoded_bytes, allow_empty }))
+    });
+    registry.register("macaddress", |params| {
+        let MacAddressParams { formats, allow_empty } = from_params(params)?;
+        Ok(Arc::new(MacAddress { formats, allow_empty }))
+    });
+    registry.register("hexcolor", |params| {
+        let HexColorParams { allow_alpha, allow_shorthand, allow_empty } = from_params(params)?;
+        Ok(Arc::new(HexColor { allow_alpha, allow_shorthand, allow_empty }))
+    });
+    registry.register("semver", |params| {
+        let SemverParams { min, max, allow_empty } = from_params(params)?;
+        let bound = |name: &str, bound: Option<String>| -> Result<Option<Version>, String> {
+            bound.map(|bound| Version::parse(&bound).map_err(|_| format!("Invalid {} '{}': expected a semantic version", name, bound)))
+                .transpose()
+        };
+        let (min, max) = (bound("min", min)?, bound("max", max)?);
+        Ok(Arc::new(Semver { min, max, allow_empty }))
+    });
+    registry.register("duration", |params| {
+        let DurationParams { min, max, formats, allow_empty } = from_params(params)?;
+        let bound = |name: &str, bound: Option<String>| -> Result<Option<f64>, String> {
+            bound.map(|bound| parse_duration(&bound, &[]).ok_or_else(|| format!("Invalid {} '{}': expected a duration", name, bound)))
+                .transpose()
+        };
+        let (min, max) = (bound("min", min)?, bound("max", max)?);
+        Ok(Arc::new(Duration { min, max, formats, allow_empty }))
+    });
+    registry.register("percent", |params| {
+        let PercentParams { min, max, allow_symbol, fraction, allow_empty } = from_params(params)?;
+        Ok(Arc::new(Percent { min, max, allow_symbol, fraction, allow_empty }))
+    });
+}
+
+// Empty or whitespace only, as `notempty` sees it
+fn is_blank(value: &str) -> bool {
+    value.trim().is_empty()
+}
+
+fn from_params<T: for<'de> Deserialize<'de>>(params: &Value) -> Result<T, String> {
+    T::deserialize(params).map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+struct NumberParams {
+    #[serde(default)]
+    min: Option<f64>,
+    #[serde(default)]
+    max: Option<f64>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct EmailParams {
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct RegexParams {
+    pattern: String,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct OneOfParams {
+    options: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CoordinateParams {
+    kind: CoordinateKind,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct CountryCodeParams {
+    alpha: u8,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct LanguageCodeParams {
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct TimezoneParams {
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct DateParams {
+    #[serde(default)]
+    must_be: Option<DateConstraint>,
+    #[serde(default)]
+    min: Option<String>,
+    #[serde(default)]
+    max: Option<String>,
+    #[serde(default)]
+    now: Option<String>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct AgeParams {
+    #[serde(default)]
+    min: Option<u32>,
+    #[serde(default)]
+    max: Option<u32>,
+    #[serde(default)]
+    reference_date: Option<String>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct DependsOnParams {
+    key_columns: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AnomalyParams {
+    method: OutlierMethod,
+    #[serde(default)]
+    threshold: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct NationalIdParams {
+    country: String,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct PostalCodeParams {
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    country_column: Option<String>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    #[serde(default)]
+    style: PathStyle,
+    #[serde(default)]
+    must_be_absolute: bool,
+    #[serde(default)]
+    allowed_extensions: Vec<String>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct HostnameParams {
+    #[serde(default)]
+    require_tld: bool,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct Base64Params {
+    #[serde(default)]
+    max_decoded_bytes: Option<usize>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct MacAddressParams {
+    #[serde(default)]
+    formats: Vec<MacFormat>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct HexColorParams {
+    #[serde(default)]
+    allow_alpha: bool,
+    #[serde(default)]
+    allow_shorthand: bool,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct SemverParams {
+    #[serde(default)]
+    min: Option<String>,
+    #[serde(default)]
+    max: Option<String>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct DurationParams {
+    #[serde(default)]
+    min: Option<String>,
+    #[serde(default)]
+    max: Option<String>,
+    #[serde(default)]
+    formats: Vec<DurationFormat>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Deserialize)]
+struct PercentParams {
+    #[serde(default)]
+    min: Option<f64>,
+    #[serde(default)]
+    max: Option<f64>,
+    #[serde(default)]
+    allow_symbol: bool,
+    #[serde(default)]
+    fraction: bool,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+struct NotEmpty;
+
+impl Rule for NotEmpty {
+    fn name(&self) -> &'static str {
+        "notempty"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if is_blank(value) { Some("Required") } else { None }
+    }
+
+    fn checks_presence(&self) -> bool {
+        true
+    }
+}
+
+struct Number {
+    min: Option<f64>,
+    max: Option<f64>,
+    allow_empty: bool,
+}
+
+impl Rule for Number {
+    fn name(&self) -> &'static str {
+        "number"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        if value.is_empty() {
+            return Some("Not a Number");
+        }
+        match parse_number(value) {
+            Ok(num) => {
+                if self.min.is_some_and(|m| num < m) { Some("Min Value") }
+                else if self.max.is_some_and(|m| num > m) { Some("Max Value") }
+                else { None }
+            },
+            Err(_) => Some("Not a Number")
+        }
+    }
+}
+
+struct Email {
+    allow_empty: bool,
+}
+
+impl Rule for Email {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        if !email_regex().is_match(value) { Some("Invalid Email") } else { None }
+    }
+}
+
+pub(crate) fn email_regex() -> &'static Regex {
+    static EMAIL_REGEX: OnceLock<Regex> = OnceLock::new();
+    EMAIL_REGEX.get_or_init(|| Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap())
+}
+
+struct Pattern {
+    regex: Regex,
+    allow_empty: bool,
+}
+
+impl Rule for Pattern {
+    fn name(&self) -> &'static str {
+        "regex"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        if !self.regex.is_match(value) { Some("Pattern Mismatch") } else { None }
+    }
+}
+
+struct OneOf(Vec<String>);
+
+impl Rule for OneOf {
+    fn name(&self) -> &'static str {
+        "oneof"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if !self.0.iter().any(|o| o == value) { Some("Invalid Option") } else { None }
+    }
+}
+
+struct Percent {
+    min: Option<f64>,
+    max: Option<f64>,
+    allow_symbol: bool,
+    fraction: bool,
+    allow_empty: bool,
+}
+
+impl Rule for Percent {
+    fn name(&self) -> &'static str {
+        "percent"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let (number, symbol) = match value.strip_suffix('%') {
+            Some(number) if self.allow_symbol => (number.trim_end(), true),
+            Some(_) => return Some("Not a Percentage"),
+            None => (value, false),
+        };
+        let Ok(num) = parse_number(number) else { return Some("Not a Percentage") };
+        let percent = if self.fraction && !symbol { num * 100.0 } else { num };
+        if self.min.is_some_and(|m| percent < m) { Some("Min Value") }
+        else if self.max.is_some_and(|m| percent > m) { Some("Max Value") }
+        else { None }
+    }
+}
+
+struct Coordinate {
+    kind: CoordinateKind,
+    allow_empty: bool,
+}
+
+impl Rule for Coordinate {
+    fn name(&self) -> &'static str {
+        "coordinate"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        match self.kind {
+            CoordinateKind::Lat => check_degrees(value, 90.0),
+            CoordinateKind::Lng => check_degrees(value, 180.0),
+            CoordinateKind::Pair => match value.split_once(',') {
+                Some((lat, lng)) => check_degrees(lat.trim(), 90.0).or_else(|| check_degrees(lng.trim(), 180.0)),
+                None => Some("Invalid Coordinate"),
+            },
+        }
+    }
+}
+
+fn check_degrees(value: &str, limit: f64) -> Option<&'static str> {
+    match parse_number(value) {
+        Ok(degrees) if !degrees.is_finite() => Some("Invalid Coordinate"),
+        Ok(degrees) if degrees.abs() > limit => Some("Coordinate Out of Range"),
+        Ok(_) => None,
+        Err(_) => Some("Invalid Coordinate"),
+    }
+}
+
+struct CountryCode {
+    // Sorted for binary search
+    codes: Vec<&'static str>,
+    allow_empty: bool,
+}
+
+impl Rule for CountryCode {
+    fn name(&self) -> &'static str {
+        "countrycode"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        if self.codes.binary_search(&value).is_err() { Some("Invalid Country Code") } else { None }
+    }
+}
+
+struct LanguageCode {
+    allow_empty: bool,
+}
+
+impl Rule for LanguageCode {
+    fn name(&self) -> &'static str {
+        "languagecode"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        if !is_language_tag(value) { Some("Invalid Language Tag") } else { None }
+    }
+}
+
+struct Timezone {
+    allow_empty: bool,
+}
+
+impl Rule for Timezone {
+    fn name(&self) -> &'static str {
+        "timezone"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        if TIME_ZONES.binary_search(&value).is_err() { Some("Unknown Time Zone") } else { None }
+    }
+}
+
+struct DateRule {
+    must_be: Option<DateConstraint>,
+    // As `day_number`s, like `now`
+    min: Option<i64>,
+    max: Option<i64>,
+    now: i64,
+    allow_empty: bool,
+}
+
+impl Rule for DateRule {
+    fn name(&self) -> &'static str {
+        "date"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let Some(date) = parse_date(value) else { return Some("Invalid Date") };
+        let day = day_number(date);
+        if self.min.is_some_and(|min| day < min) {
+            return Some("Date Too Early");
+        }
+        if self.max.is_some_and(|max| day > max) {
+            return Some("Date Too Late");
+        }
+        match self.must_be {
+            Some(DateConstraint::Past) if day >= self.now => Some("Not in the Past"),
+            Some(DateConstraint::TodayOrPast) if day > self.now => Some("Not in the Past"),
+            Some(DateConstraint::Future) if day <= self.now => Some("Not in the Future"),
+            _ => None,
+        }
+    }
+}
+
+struct Age {
+    min: Option<u32>,
+    max: Option<u32>,
+    reference: Date,
+    allow_empty: bool,
+}
+
+impl Rule for Age {
+    fn name(&self) -> &'static str {
+        "age"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let Some(born) = parse_date(value) else { return Some("Invalid Date") };
+        let age = age_on(born, self.reference);
+        if self.min.is_some_and(|m| age < i64::from(m)) { Some("Min Value") }
+        else if self.max.is_some_and(|m| age > i64::from(m)) { Some("Max Value") }
+        else { None }
+    }
+}
+
+/// (year, month, day)
+pub(crate) type Date = (i64, u32, u32);
+
+/// A `YYYY-MM-DD` date that exists, ignoring anything after a following
+/// `T` or space (a time).
+pub(crate) fn parse_date(value: &str) -> Option<Date> {
+    parse_ymd(value.split(['T', ' ']).next()?, '-')
+}
+
+/// A date as `parse_date` reads it (with `-` or `/` between the fields) and
+/// the millisecond of the day of an optional `HH:MM[:SS[.fff]]` time after
+/// a `T` or space. A trailing `Z` is dropped; other offsets aren't applied.
+pub(crate) fn parse_timestamp(value: &str) -> Option<(Date, u32)> {
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let date = parse_ymd(date, '-').or_else(|| parse_ymd(date, '/'))?;
+    Some((date, time.map_or(Some(0), time_of_day)?))
+}
+
+fn time_of_day(time: &str) -> Option<u32> {
+    let time = time.strip_suffix('Z').unwrap_or(time);
+    let (hms, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut fields = hms.split(':');
+    let hours: u32 = fields.next()?.parse().ok()?;
+    let minutes: u32 = fields.next()?.parse().ok()?;
+    let seconds: u32 = fields.next().map_or(Some(0), |s| s.parse().ok())?;
+    if fields.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    let millis = match fraction {
+        "" => 0,
+        f if f.bytes().all(|b| b.is_ascii_digit()) => {
+            let digits = &f[..f.len().min(3)];
+            digits.parse::<u32>().ok()? * 10u32.pow(3 - digits.len() as u32)
+        }
+        _ => return None,
+    };
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis)
+}
+
+fn parse_ymd(date: &str, separator: char) -> Option<Date> {
+    let mut parts = date.splitn(3, separator);
+    let mut field = |len: usize| -> Option<u32> {
+        parts.next().filter(|p| p.len() == len && p.bytes().all(|b| b.is_ascii_digit()))?.parse().ok()
+    };
+    let (year, month, day) = (i64::from(field(4)?), field(2)?, field(2)?);
+    (1..=days_in_month(year, month)?).contains(&day).then_some((year, month, day))
+}
+
+fn days_in_month(year: i64, month: u32) -> Option<u32> {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+        4 | 6 | 9 | 11 => Some(30),
+        2 if leap => Some(29),
+        2 => Some(28),
+        _ => None,
+    }
+}
+
+/// A `YYYY-MM-DD` date, or `today`, or `now` moved by a signed count of
+/// days, weeks, months or years (`-90d`, `+2w`, `-6m`, `+1y`). Adding
+/// months keeps the day of the month where it exists and otherwise takes
+/// the month's last day, so a month after `2024-01-31` is `2024-02-29`.
+pub(crate) fn date_bound(bound: &str, now: Date) -> Option<Date> {
+    let bound = bound.trim();
+    if bound.eq_ignore_ascii_case("today") {
+        return Some(now);
+    }
+    if !bound.starts_with(['+', '-']) {
+        return parse_date(bound);
+    }
+    let (count, unit) = bound.split_at(bound.char_indices().last()?.0);
+    let count: i64 = count.parse().ok()?;
+    let months = match unit.to_ascii_lowercase().as_str() {
+        "d" => return offset_days(now, count),
+        "w" => return offset_days(now, count.checked_mul(7)?),
+        "m" => count,
+        "y" => count.checked_mul(12)?,
+        _ => return None,
+    };
+    let (year, month, day) = now;
+    let total = (year * 12 + i64::from(month) - 1).checked_add(months)?;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    if year.abs() > MAX_BOUND_YEAR {
+        return None;
+    }
+    Some((year, month, day.min(days_in_month(year, month)?)))
+}
+
+// Offsets reaching further are surely mistakes, and would overflow the day
+// arithmetic
+const MAX_BOUND_YEAR: i64 = 1_000_000;
+
+fn offset_days(now: Date, days: i64) -> Option<Date> {
+    let days = day_number(now).checked_add(days)?;
+    (days.abs() <= MAX_BOUND_YEAR * 366).then(|| civil_from_days(days))
+}
+
+/// Today's date in UTC.
+pub(crate) fn today() -> Date {
+    civil_from_days((unix_ms() / 86_400_000.0).floor() as i64)
+}
+
+/// Days since 1970-01-01, for comparing and offsetting dates.
+pub(crate) fn day_number((year, month, day): Date) -> i64 {
+    days_from_civil(year, month, day)
+}
+
+/// Whole years from `born` to `on`; negative if `born` is later.
+fn age_on(born: Date, on: Date) -> i64 {
+    on.0 - born.0 - i64::from((on.1, on.2) < (born.1, born.2))
+}
+
+struct Duration {
+    // In seconds
+    min: Option<f64>,
+    max: Option<f64>,
+    formats: Vec<DurationFormat>,
+    allow_empty: bool,
+}
+
+impl Rule for Duration {
+    fn name(&self) -> &'static str {
+        "duration"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        match parse_duration(value, &self.formats) {
+            None => Some("Invalid Duration"),
+            Some(seconds) if self.min.is_some_and(|min| seconds < min) => Some("Min Value"),
+            Some(seconds) if self.max.is_some_and(|max| seconds > max) => Some("Max Value"),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Seconds in a duration of one of `formats` (any when empty).
+pub(crate) fn parse_duration(value: &str, formats: &[DurationFormat]) -> Option<f64> {
+    let value = value.trim();
+    let allowed = |format| formats.is_empty() || formats.contains(&format);
+    [DurationFormat::Clock, DurationFormat::Units, DurationFormat::Iso8601]
+        .into_iter()
+        .filter(|&format| allowed(format))
+        .find_map(|format| match format {
+            DurationFormat::Clock => clock_seconds(value),
+            DurationFormat::Units => unit_seconds(value, &[('d', 86_400.0), ('h', 3_600.0), ('m', 60.0), ('s', 1.0)], false),
+            DurationFormat::Iso8601 => iso_duration_seconds(value),
+        })
+}
+
+fn clock_seconds(value: &str) -> Option<f64> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || !parts.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let hours: f64 = parts[0].parse().ok()?;
+    let mut seconds = hours * 3_600.0;
+    for (part, scale) in parts[1..].iter().zip([60.0, 1.0]) {
+        let amount: u32 = part.parse().ok()?;
+        if part.len() != 2 || amount >= 60 {
+            return None;
+        }
+        seconds += f64::from(amount) * scale;
+    }
+    Some(seconds)
+}
+
+/// Amounts each followed by one of `units` (matched case-insensitively),
+/// in the order given and each at most once. Spaces between amounts are
+/// allowed unless `compact`.
+fn unit_seconds(value: &str, units: &[(char, f64)], compact: bool) -> Option<f64> {
+    let mut rest = value;
+    let mut next_unit = 0;
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let amount: f64 = rest[..end].parse().ok()?;
+        let unit = rest[end..].chars().next()?.to_ascii_lowercase();
+        let index = next_unit + units[next_unit..].iter().position(|&(u, _)| u == unit)?;
+        seconds += amount * units[index].1;
+        next_unit = index + 1;
+        rest = &rest[end + 1..];
+        if !compact {
+            rest = rest.trim_start();
+        }
+    }
+    (next_unit > 0).then_some(seconds)
+}
+
+fn iso_duration_seconds(value: &str) -> Option<f64> {
+    let body = value.strip_prefix(['P', 'p'])?;
+    if let Some(weeks) = body.strip_suffix(['W', 'w']) {
+        return unit_seconds(&format!("{}w", weeks), &[('w', 604_800.0)], true);
+    }
+    let (days, time) = match body.find(['T', 't']) {
+        Some(t) => (&body[..t], Some(&body[t + 1..])),
+        None => (body, None),
+    };
+    let days = if days.is_empty() { 0.0 } else { unit_seconds(days, &[('d', 86_400.0)], true)? };
+    let time = match time {
+        // A `T` needs something after it
+        Some(time) => unit_seconds(time, &[('h', 3_600.0), ('m', 60.0), ('s', 1.0)], true)?,
+        None if body.is_empty() => return None,
+        None => 0.0,
+    };
+    Some(days + time)
+}
+
+struct Semver {
+    min: Option<Version>,
+    max: Option<Version>,
+    allow_empty: bool,
+}
+
+impl Rule for Semver {
+    fn name(&self) -> &'static str {
+        "semver"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        // Bounds compare by SemVer precedence, which ignores build metadata
+        match Version::parse(value) {
+            Err(_) => Some("Invalid Version"),
+            Ok(version) if self.min.as_ref().is_some_and(|min| version.cmp_precedence(min).is_lt()) => Some("Min Value"),
+            Ok(version) if self.max.as_ref().is_some_and(|max| version.cmp_precedence(max).is_gt()) => Some("Max Value"),
+            Ok(_) => None,
+        }
+    }
+}
+
+struct HexColor {
+    allow_alpha: bool,
+    allow_shorthand: bool,
+    allow_empty: bool,
+}
+
+impl Rule for HexColor {
+    fn name(&self) -> &'static str {
+        "hexcolor"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let valid = match value.strip_prefix('#') {
+            Some(digits) if digits.bytes().all(|b| b.is_ascii_hexdigit()) => match digits.len() {
+                6 => true,
+                8 => self.allow_alpha,
+                3 => self.allow_shorthand,
+                4 => self.allow_shorthand && self.allow_alpha,
+                _ => false,
+            },
+            _ => false,
+        };
+        (!valid).then_some("Invalid Hex Color")
+    }
+}
+
+struct MacAddress {
+    formats: Vec<MacFormat>,
+    allow_empty: bool,
+}
+
+impl Rule for MacAddress {
+    fn name(&self) -> &'static str {
+        "macaddress"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let format = match value.len() {
+            17 if value.as_bytes()[2] == b':' => MacFormat::Colon,
+            17 => MacFormat::Dash,
+            12 => MacFormat::Bare,
+            _ => return Some("Invalid MAC Address"),
+        };
+        let groups: Vec<&str> = match format {
+            MacFormat::Colon => value.split(':').collect(),
+            MacFormat::Dash => value.split('-').collect(),
+            // Byte length 12 but maybe not ASCII; `get` avoids a panic
+            MacFormat::Bare => (0..12).step_by(2).map(|i| value.get(i..i + 2).unwrap_or("")).collect(),
+        };
+        let valid = (self.formats.is_empty() || self.formats.contains(&format))
+            && groups.len() == 6
+            && groups.iter().all(|group| group.len() == 2 && group.bytes().all(|b| b.is_ascii_hexdigit()));
+        (!valid).then_some("Invalid MAC Address")
+    }
+}
+
+struct Base64 {
+    max_decoded_bytes: Option<usize>,
+    allow_empty: bool,
+}
+
+impl Rule for Base64 {
+    fn name(&self) -> &'static str {
+        "base64"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        match base64_decoded_len(value) {
+            None => Some("Invalid Base64"),
+            Some(len) if self.max_decoded_bytes.is_some_and(|max| len > max) => Some("Max Value"),
+            Some(_) => None,
+        }
+    }
+}
+
+/// The number of bytes `value` decodes to. Bits left over after the last
+/// byte must be zero, as strict decoders require, and padding, when there
+/// is any, must be complete.
+fn base64_decoded_len(value: &str) -> Option<usize> {
+    const ENGINE: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent));
+    if value.is_empty() || (value.ends_with('=') && !value.len().is_multiple_of(4)) {
+        return None;
+    }
+    ENGINE.decode(value).ok().map(|bytes| bytes.len())
+}
+
+struct Hostname {
+    require_tld: bool,
+    allow_empty: bool,
+}
+
+impl Rule for Hostname {
+    fn name(&self) -> &'static str {
+        "hostname"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let name = value.strip_suffix('.').unwrap_or(value);
+        let label = |label: &str| {
+            (1..=63).contains(&label.len())
+                && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        };
+        let labels: Vec<&str> = name.split('.').collect();
+        let has_tld = match labels.as_slice() {
+            [_, .., tld] => !tld.bytes().all(|b| b.is_ascii_digit()),
+            _ => false,
+        };
+        let valid = name.len() <= 253 && labels.iter().all(|l| label(l)) && (has_tld || !self.require_tld);
+        (!valid).then_some("Invalid Hostname")
+    }
+}
+
+struct FilePath {
+    style: PathStyle,
+    must_be_absolute: bool,
+    // Lower case, without the dot
+    extensions: Vec<String>,
+    allow_empty: bool,
+}
+
+impl Rule for FilePath {
+    fn name(&self) -> &'static str {
+        "path"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let absolute = match self.style {
+            PathStyle::Unix => unix_path(value),
+            PathStyle::Windows => windows_path(value),
+            PathStyle::Any => unix_path(value).or_else(|| windows_path(value)),
+        };
+        let Some(absolute) = absolute else { return Some("Invalid Path") };
+        if self.must_be_absolute && !absolute {
+            return Some("Path Not Absolute");
+        }
+        if !self.extensions.is_empty() {
+            let file_name = value.rsplit(['/', '\\']).next().unwrap_or(value);
+            // A leading dot starts a hidden file's name, not an extension
+            let extension = file_name.rsplit_once('.').filter(|(stem, _)| !stem.is_empty()).map(|(_, ext)| ext.to_lowercase());
+            if !extension.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return Some("Extension Not Allowed");
+            }
+        }
+        None
+    }
+}
+
+/// Whether a valid Unix path is absolute.
+fn unix_path(value: &str) -> Option<bool> {
+    (!value.is_empty() && !value.contains(['\0', '\\'])).then(|| value.starts_with('/'))
+}
+
+/// Whether a valid Windows path is absolute.
+fn windows_path(value: &str) -> Option<bool> {
+    let (rest, absolute) = if let Some(unc) = value.strip_prefix("\\\\") {
+        // `\\server\share` at least
+        let mut parts = unc.split('\\');
+        let named = |part: Option<&str>| part.is_some_and(|part| !part.is_empty());
+        if !(named(parts.next()) && named(parts.next())) {
+            return None;
+        }
+        (unc, true)
+    } else if value.len() >= 2 && value.as_bytes()[0].is_ascii_alphabetic() && value.as_bytes()[1] == b':' {
+        // `C:foo` is relative to the drive's current directory
+        (&value[2..], value[2..].starts_with('\\'))
+    } else {
+        (value, false)
+    };
+    let valid = !value.is_empty() && !rest.chars().any(|c| c.is_control() || matches!(c, '/' | '<' | '>' | ':' | '"' | '|' | '?' | '*'));
+    valid.then_some(absolute)
+}
+
+#[derive(Clone)]
+struct PostalCode {
+    // The format of `country`, if set
+    fixed: Option<&'static Regex>,
+    country_column: Option<String>,
+    // Its position, once bound
+    country_col: Option<usize>,
+    allow_empty: bool,
+}
+
+impl Rule for PostalCode {
+    fn name(&self) -> &'static str {
+        "postalcode"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        self.check_record(value, &Vec::<&str>::new())
+    }
+
+    fn check_record(&self, value: &str, record: &dyn Record) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        let country = self.country_col.map_or("", |col| record.cell(col).trim());
+        let regex = if country.is_empty() { self.fixed } else { postal_regex(country) };
+        // Countries without a known format aren't checked
+        (!regex?.is_match(value)).then_some("Invalid Postal Code")
+    }
+
+    fn bind(&self, _column: usize, find: &dyn Fn(&str) -> Option<usize>) -> Result<Option<CompiledRule>, String> {
+        let Some(name) = &self.country_column else { return Ok(None) };
+        let country_col = find(name).ok_or_else(|| format!("Unknown Column: {}", name))?;
+        Ok(Some(Arc::new(PostalCode { country_col: Some(country_col), ..self.clone() })))
+    }
+}
+
+struct NationalId {
+    check: fn(&str) -> bool,
+    allow_empty: bool,
+}
+
+impl Rule for NationalId {
+    fn name(&self) -> &'static str {
+        "nationalid"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        if self.allow_empty && is_blank(value) {
+            return None;
+        }
+        (!(self.check)(value)).then_some("Invalid National ID")
+    }
+}
+
+/// Numbers outside bounds computed from the column's other numbers, as
+/// `detect_outliers` reports them. Blank and non-numeric cells pass.
+#[derive(Clone, Copy)]
+struct Anomaly {
+    method: OutlierMethod,
+    threshold: f64,
+    // None until fitted, or when the column has no numbers
+    bounds: Option<(f64, f64)>,
+}
+
+impl Rule for Anomaly {
+    fn name(&self) -> &'static str {
+        "anomaly"
+    }
+
+    fn check(&self, value: &str) -> Option<&'static str> {
+        let (lower, upper) = self.bounds?;
+        let number = parse_number(value.trim()).ok().filter(|n| n.is_finite())?;
+        (number < lower || number > upper).then_some("Statistical Outlier")
+    }
+
+    fn fit<'a>(&self, column: usize, rows: usize, cell: &dyn Fn(usize, usize) -> &'a str) -> Option<CompiledRule> {
+        let numbers: Vec<f64> = (0..rows)
+            .filter_map(|row| parse_number(cell(row, column).trim()).ok())
+            .filter(|n| n.is_finite())
+            .collect();
+        Some(Arc::new(Anomaly { bounds: self.method.bounds(&numbers, self.threshold), ..*self }))
+    }
+}
+
+/// The cell must agree with every other row that has the same values in
+/// `key_columns`, as a product's name with the other rows of its
+/// `product_id`. Fitted to the loaded rows; rows with a blank key cell and
+/// blank cells aren't compared.
+#[derive(Clone)]
+struct DependsOn {
+    key_columns: Vec<String>,
+    // Their positions, once bound
+    keys: Vec<usize>,
+    // Key -> the distinct values it had, once fitted
+    seen: Option<Arc<HashMap<String, Vec<String>>>>,
+}
+
+/// `dependson` for the key columns at `keys`, as `find_dependency_conflicts`
+/// checks it.
+pub(crate) fn depends_on(keys: Vec<usize>) -> CompiledRule {
+    Arc::new(DependsOn { key_columns: Vec::new(), keys, seen: None })
+}
+
+impl DependsOn {
+    /// The key of a record with cells `cell`, or `None` if a key cell is blank.
+    fn key<'a>(&self, cell: impl Fn(usize) -> &'a str) -> Option<String> {
+        let mut key = String::new();
+        for &col in &self.keys {
+            let value = cell(col);
+            if is_blank(value) {
+                return None;
+            }
+            // Length-prefixed, so different cells never make the same key
+            let _ = write!(key, "{}:{}", value.len(), value);
+        }
+        Some(key)
+    }
+}
+
+impl Rule for DependsOn {
+    fn name(&self) -> &'static str {
+        "dependson"
+    }
+
+    fn check(&self, _value: &str) -> Option<&'static str> {
+        // Needs the record
+        None
+    }
+
+    fn check_record(&self, value: &str, record: &dyn Record) -> Option<&'static str> {
+        let seen = self.seen.as_ref()?;
+        if is_blank(value) {
+            return None;
+        }
+        let values = seen.get(&self.key(|col| record.cell(col))?)?;
+        (values.len() > 1 || values[0] != value).then_some("Dependency Conflict")
+    }
+
+    fn bind(&self, _column: usize, find: &dyn Fn(&str) -> Option<usize>) -> Result<Option<CompiledRule>, String> {
+        let keys = self.key_columns.iter()
+            .map(|name| find(name).ok_or_else(|| format!("Unknown Column: {}", name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(Arc::new(DependsOn { keys, ..self.clone() })))
+    }
+
+    fn fit<'a>(&self, column: usize, rows: usize, cell: &dyn Fn(usize, usize) -> &'a str) -> Option<CompiledRule> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let mut seen: HashMap<String, Vec<String>> = HashMap::new();
+        for row in 0..rows {
+            let value = cell(row, column);
+            if is_blank(value) {
+                continue;
+            }
+            let Some(key) = self.key(|col| cell(row, col)) else { continue };
+            let values = seen.entry(key).or_default();
+            if !values.iter().any(|v| v == value) {
+                values.push(value.to_string());
+            }
+        }
+        Some(Arc::new(DependsOn { seen: Some(Arc::new(seen)), ..self.clone() }))
+    }
+
+    fn follows_edits(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::RuleSet;
+    use crate::store::RecordStore;
+    use crate::{ColumnRule, HeaderMatching, RuleType};
+
+    const NOW: Date = (2024, 1, 31);
+
+    fn naive((year, month, day): Date) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(year as i32, month, day).unwrap()
+    }
+
+    #[test]
+    fn age_counts_whole_years() {
+        assert_eq!(age_on((2000, 6, 15), (2024, 6, 14)), 23);
+        assert_eq!(age_on((2000, 6, 15), (2024, 6, 15)), 24);
+        // A leap-day birthday comes round on 1 March in other years
+        assert_eq!(age_on((2004, 2, 29), (2023, 2, 28)), 18);
+        assert_eq!(age_on((2004, 2, 29), (2023, 3, 1)), 19);
+        assert_eq!(age_on((2030, 1, 1), (2024, 6, 15)), -6);
+
+        let rule = RuleType::Age { min: Some(18), max: Some(65), reference_date: Some("2024-06-15".to_string()), allow_empty: false };
+        let rule = RuleRegistry::new().compile(&rule).unwrap();
+        assert_eq!(rule.check("2006-06-15"), None);
+        assert_eq!(rule.check("2006-06-16"), Some("Min Value"));
+        assert_eq!(rule.check("1958-06-16"), None);
+        assert_eq!(rule.check("1958-06-15"), Some("Max Value"));
+        assert_eq!(rule.check("2006-02-30"), Some("Invalid Date"));
+    }
+
+    #[test]
+    fn age_matches_chrono() {
+        for born in (day_number((1996, 1, 1))..day_number((2005, 1, 1))).step_by(3).map(civil_from_days) {
+            for on in (day_number((2020, 1, 1))..day_number((2025, 1, 1))).step_by(17).map(civil_from_days) {
+                let expected = naive(on).years_since(naive(born)).map(i64::from);
+                assert_eq!(Some(age_on(born, on)), expected, "{:?} on {:?}", born, on);
+            }
+        }
+    }
+
+    #[test]
+    fn dates_must_exist() {
+        assert_eq!(parse_date("2024-02-29"), Some((2024, 2, 29)));
+        assert_eq!(parse_date("2024-02-29T10:00"), Some((2024, 2, 29)));
+        for bad in ["2023-02-29", "2024-13-01", "2024-1-05", "24-01-05", "2024/01/05", "2024-01-05-"] {
+            assert_eq!(parse_date(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn timestamps_carry_the_time_of_day() {
+        assert_eq!(parse_timestamp("2024-01-05"), Some(((2024, 1, 5), 0)));
+        assert_eq!(parse_timestamp("2024/01/05 10:30"), Some(((2024, 1, 5), 37_800_000)));
+        assert_eq!(parse_timestamp("2024-01-05T10:30:15.5Z"), Some(((2024, 1, 5), 37_815_500)));
+        for bad in ["2024/02/30", "2024-01-05T24:00", "2024-01-05 10", "2024-01-05T10:30:00.x"] {
+            assert_eq!(parse_timestamp(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn calendar_matches_chrono() {
+        let epoch = naive((1970, 1, 1));
+        for year in 1600..=2400 {
+            for month in 1..=13 {
+                for day in 0..=32 {
+                    let text = format!("{:04}-{:02}-{:02}", year, month, day);
+                    let expected = chrono::NaiveDate::from_ymd_opt(year as i32, month, day);
+                    assert_eq!(parse_date(&text).is_some(), expected.is_some(), "{}", text);
+                    if let Some(expected) = expected {
+                        let days = (expected - epoch).num_days();
+                        assert_eq!(day_number((year, month, day)), days, "{}", text);
+                        assert_eq!(civil_from_days(days), (year, month, day), "{}", text);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn date_must_be_relative_to_now() {
+        let rule = |must_be| {
+            let rule = RuleType::Date { must_be: Some(must_be), min: None, max: None, now: Some("2024-01-31".to_string()), allow_empty: false };
+            RuleRegistry::new().compile(&rule).unwrap()
+        };
+        let checks = |must_be| ["2024-01-30", "2024-01-31", "2024-02-01"].map(|date| rule(must_be).check(date));
+        assert_eq!(checks(DateConstraint::Past), [None, Some("Not in the Past"), Some("Not in the Past")]);
+        assert_eq!(checks(DateConstraint::TodayOrPast), [None, None, Some("Not in the Past")]);
+        assert_eq!(checks(DateConstraint::Future), [Some("Not in the Future"), Some("Not in the Future"), None]);
+        assert_eq!(rule(DateConstraint::Past).check("2023-02-29"), Some("Invalid Date"));
+    }
+
+    #[test]
+    fn date_bound_offsets() {
+        assert_eq!(date_bound("-90d", NOW), Some((2023, 11, 2)));
+        assert_eq!(date_bound("+2W", NOW), Some((2024, 2, 14)));
+        assert_eq!(date_bound("+1m", NOW), Some((2024, 2, 29)));
+        assert_eq!(date_bound("-1y", NOW), Some((2023, 1, 31)));
+        assert_eq!(date_bound("today", NOW), Some(NOW));
+    }
+
+    #[test]
+    fn date_bound_matches_chrono() {
+        use chrono::{Days, Months};
+        for now in (day_number((1999, 12, 1))..day_number((2001, 3, 31))).map(civil_from_days) {
+            let start = naive(now);
+            for count in -30i64..=30 {
+                let months = Months::new(count.unsigned_abs() as u32);
+                let expected = if count < 0 { start.checked_sub_months(months) } else { start.checked_add_months(months) };
+                assert_eq!(date_bound(&format!("{:+}m", count), now).map(naive), expected, "{:?} {:+}m", now, count);
+
+                let days = Days::new(count.unsigned_abs() * 37);
+                let expected = if count < 0 { start.checked_sub_days(days) } else { start.checked_add_days(days) };
+                assert_eq!(date_bound(&format!("{:+}d", count * 37), now).map(naive), expected, "{:?} {:+}d", now, count * 37);
+            }
+            let years = Months::new(12 * 4);
+            assert_eq!(date_bound("-4y", now).map(naive), start.checked_sub_months(years));
+        }
+    }
+
+    #[test]
+    fn date_bound_rejects_multibyte_unit() {
+        assert_eq!(date_bound("+5é", NOW), None);
+        assert_eq!(date_bound("-é", NOW), None);
+    }
+
+    #[test]
+    fn date_bound_rejects_overflowing_offsets() {
+        assert_eq!(date_bound("+9223372036854775807d", NOW), None);
+        assert_eq!(date_bound("-9223372036854775808d", NOW), None);
+        assert_eq!(date_bound("+9223372036854775807w", NOW), None);
+        assert_eq!(date_bound("+1317624576693539401w", NOW), None);
+        assert_eq!(date_bound("+768614336404564650y", NOW), None);
+        assert_eq!(date_bound("+9223372036854775807m", NOW), None);
+    }
+
+    #[test]
+    fn bad_date_bounds_fail_to_compile() {
+        let registry = RuleRegistry::new();
+        for bound in ["+5é", "+9223372036854775807d"] {
+            let rule = RuleType::Date { must_be: None, min: Some(bound.to_string()), max: None, now: None, allow_empty: false };
+            let error = registry.compile(&rule).err();
+            assert_eq!(error, Some(format!("Invalid min '{}': expected YYYY-MM-DD or an offset like -90d", bound)));
+        }
+    }
+
+    fn semver(min: Option<&str>, max: Option<&str>) -> CompiledRule {
+        let rule = RuleType::Semver { min: min.map(str::to_string), max: max.map(str::to_string), allow_empty: false };
+        RuleRegistry::new().compile(&rule).unwrap()
+    }
+
+    #[test]
+    fn semver_follows_the_spec_grammar() {
+        let rule = semver(None, None);
+        for valid in ["0.0.0", "1.2.3", "1.0.0-alpha", "1.0.0-0.3.7", "1.0.0-x.7.z.92", "1.0.0-x-y-z.--", "1.0.0+20130313144700", "1.0.0-beta+exp.sha.5114f85"] {
+            assert_eq!(rule.check(valid), None, "{}", valid);
+        }
+        for invalid in ["1.2", "1.2.3.4", "01.2.3", "1.02.3", "v1.2.3", "1.2.3-", "1.2.3-01", "1.2.3-a..b", "1.2.3+", "1.2.3+a_b", " 1.2.3", "18446744073709551616.0.0"] {
+            assert_eq!(rule.check(invalid), Some("Invalid Version"), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn semver_bounds_use_precedence() {
+        // The precedence chain from SemVer 2.0.0, section 11
+        let chain = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0"];
+        for pair in chain.windows(2) {
+            let (lower, higher) = (pair[0], pair[1]);
+            assert_eq!(semver(Some(higher), None).check(lower), Some("Min Value"), "{} < {}", lower, higher);
+            assert_eq!(semver(None, Some(lower)).check(higher), Some("Max Value"), "{} > {}", higher, lower);
+            assert_eq!(semver(Some(lower), Some(higher)).check(higher), None);
+        }
+        // Build metadata doesn't count
+        assert_eq!(semver(None, Some("1.0.0")).check("1.0.0+build.5"), None);
+        assert_eq!(semver(Some("1.0.0+zzz"), None).check("1.0.0+aaa"), None);
+    }
+
+    #[test]
+    fn base64_test_vectors() {
+        // RFC 4648, section 10, padded and not
+        for (encoded, decoded) in [("Zg==", 1), ("Zm8=", 2), ("Zm9v", 3), ("Zm9vYg==", 4), ("Zm9vYmE=", 5), ("Zm9vYmFy", 6), ("Zg", 1), ("Zm9vYmE", 5), ("+/+/", 3)] {
+            assert_eq!(base64_decoded_len(encoded), Some(decoded), "{}", encoded);
+        }
+        // Partial padding, leftover bits set, stray characters, the URL-safe alphabet
+        for invalid in ["", "Z", "Zg=", "Zg===", "Zh==", "Zm9=", "Zm=9", "Zm9v YmFy", "-_-_"] {
+            assert_eq!(base64_decoded_len(invalid), None, "{}", invalid);
+        }
+        let rule = RuleRegistry::new().compile(&RuleType::Base64 { max_decoded_bytes: Some(5), allow_empty: false }).unwrap();
+        assert_eq!(rule.check("Zm9vYmE="), None);
+        assert_eq!(rule.check("Zm9vYmFy"), Some("Max Value"));
+    }
+
+    fn rule(json: &str) -> CompiledRule {
+        RuleRegistry::new().compile(&serde_json::from_str(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn durations_in_each_format() {
+        for (value, seconds) in [("1:30", 5_400.0), ("12:05:09", 43_509.0), ("100:00", 360_000.0), ("1d 2h", 93_600.0), ("1.5h", 5_400.0), ("90M", 5_400.0), ("2h30m15s", 9_015.0)] {
+            assert_eq!(parse_duration(value, &[]), Some(seconds), "{}", value);
+        }
+        for (value, seconds) in [("PT1H30M", 5_400.0), ("P1DT12H", 129_600.0), ("P2W", 1_209_600.0), ("PT0.5S", 0.5), ("p1d", 86_400.0)] {
+            assert_eq!(parse_duration(value, &[DurationFormat::Iso8601]), Some(seconds), "{}", value);
+        }
+        // Minutes or seconds past 59, units out of order or repeated, a bare `P` or `T`
+        for invalid in ["1:60", "1:5", "1:30:7", ":30", "30m 1h", "1h 2h", "1h x", "5", "P", "PT", "P1H", "P1W2D"] {
+            assert_eq!(parse_duration(invalid, &[]), None, "{}", invalid);
+        }
+        assert_eq!(parse_duration("1:30", &[DurationFormat::Iso8601, DurationFormat::Units]), None);
+
+        let rule = rule(r#"{"type": "duration", "min": "PT1M", "max": "1h"}"#);
+        assert_eq!(rule.check("0:00:59"), Some("Min Value"));
+        assert_eq!(rule.check("1h"), None);
+        assert_eq!(rule.check("61m"), Some("Max Value"));
+        assert_eq!(rule.check("soon"), Some("Invalid Duration"));
+    }
+
+    #[test]
+    fn hostnames_follow_rfc_1123() {
+        let any = rule(r#"{"type": "hostname"}"#);
+        let qualified = rule(r#"{"type": "hostname", "require_tld": true}"#);
+        let long_label = "a".repeat(63);
+        for valid in ["example.com", "example.com.", "xn--bcher-kva.example", "a-b.c1", &long_label] {
+            assert_eq!(any.check(valid), None, "{}", valid);
+        }
+        for invalid in ["", "-a.com", "a-.com", "a..com", "a_b.com", "ex ample.com", "bücher.de", &format!("{}a", long_label), &format!("{}a", "a.".repeat(127))] {
+            assert_eq!(any.check(invalid), Some("Invalid Hostname"), "{}", invalid);
+        }
+        assert_eq!(any.check("localhost"), None);
+        assert_eq!(qualified.check("localhost"), Some("Invalid Hostname"));
+        assert_eq!(qualified.check("10.0.0.1"), Some("Invalid Hostname"));
+        assert_eq!(qualified.check("mail.example.co.uk"), None);
+    }
+
+    #[test]
+    fn paths_by_style() {
+        for (value, absolute) in [("/usr/bin", true), ("assets/logo.png", false), ("a b/ünï", false)] {
+            assert_eq!(unix_path(value), Some(absolute), "{}", value);
+        }
+        for invalid in ["", "a\\b", "a\0b"] {
+            assert_eq!(unix_path(invalid), None, "{:?}", invalid);
+        }
+        for (value, absolute) in [("C:\\Users\\a.txt", true), ("C:foo", false), ("docs\\a.txt", false), ("\\\\server\\share\\x", true)] {
+            assert_eq!(windows_path(value), Some(absolute), "{}", value);
+        }
+        for invalid in ["", "a/b", "C:\\a:b", "what?.txt", "\\\\server", "\\\\\\share", "a\tb"] {
+            assert_eq!(windows_path(invalid), None, "{:?}", invalid);
+        }
+
+        let rule = rule(r#"{"type": "path", "must_be_absolute": true, "allowed_extensions": [".PNG", "jpg"]}"#);
+        assert_eq!(rule.check("/img/logo.png"), None);
+        assert_eq!(rule.check("C:\\img\\logo.JPG"), None);
+        assert_eq!(rule.check("img/logo.png"), Some("Path Not Absolute"));
+        assert_eq!(rule.check("/img/logo.gif"), Some("Extension Not Allowed"));
+        assert_eq!(rule.check("/img/.png"), Some("Extension Not Allowed"));
+    }
+
+    fn product_rules(headers: &[String]) -> Result<RuleSet, String> {
+        let rule = ColumnRule {
+            column: "name".to_string(),
+            rules: vec![RuleType::DependsOn { key_columns: vec!["id".to_string()] }],
+            disabled: Vec::new(),
+        };
+        RuleSet::new(headers, &[rule], &HeaderMatching::default())
+    }
+
+    fn products(rows: &[[&str; 2]]) -> RecordStore {
+        let mut records = RecordStore::new(2);
+        for row in rows {
+            records.push_row(row.iter().copied()).unwrap();
+        }
+        records
+    }
+
+    fn failing_rows(rule_set: &RuleSet, records: &RecordStore) -> Vec<usize> {
+        (0..records.len())
+            .filter(|&row| rule_set.validate_cell(1, records.get(row, 1), &records.row(row)).next().is_some())
+            .collect()
+    }
+
+    #[test]
+    fn depends_on_fails_every_row_of_a_conflicting_key() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let mut rule_set = product_rules(&headers).unwrap();
+        let records = products(&[["1", "Desk"], ["2", "Lamp"], ["1", "Desk "], ["2", "Lamp"], ["", "Chair"], ["1", ""]]);
+        rule_set.fit(&records);
+        assert_eq!(failing_rows(&rule_set, &records), vec![0, 2]);
+
+        let record = vec!["2", "Lamp shade"];
+        let violation = rule_set.validate_cell(1, "Lamp shade", &record).next().unwrap();
+        assert_eq!(violation.error_type, "Dependency Conflict");
+    }
+
+    #[test]
+    fn depends_on_follows_edits() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let mut rule_set = product_rules(&headers).unwrap();
+        let mut records = products(&[["1", "Desk"], ["1", "Desk "]]);
+        rule_set.fit(&records);
+        assert_eq!(failing_rows(&rule_set, &records), vec![0, 1]);
+
+        records.replace_all(1, "Desk ", "Desk").unwrap();
+        assert!(rule_set.refit_edited(1, &records));
+        assert!(failing_rows(&rule_set, &records).is_empty());
+    }
+
+    #[test]
+    fn depends_on_needs_known_key_columns() {
+        let headers = vec!["sku".to_string(), "name".to_string()];
+        assert_eq!(product_rules(&headers).err(), Some("Invalid Rule for column 'name': Unknown Column: id".to_string()));
+    }
+
+    fn postal_rules(country: Option<&str>, country_column: &str) -> Result<RuleSet, String> {
+        let headers = vec!["country".to_string(), "zip".to_string()];
+        let rule = ColumnRule {
+            column: "zip".to_string(),
+            rules: vec![RuleType::PostalCode { country: country.map(str::to_string), country_column: Some(country_column.to_string()), allow_empty: false }],
+            disabled: Vec::new(),
+        };
+        RuleSet::new(&headers, &[rule], &HeaderMatching::default())
+    }
+
+    #[test]
+    fn postal_code_follows_the_country_column() {
+        let rule_set = postal_rules(Some("US"), "country").unwrap();
+        let passes = |country: &str, zip: &str| rule_set.validate_cell(1, zip, &vec![country, zip]).next().is_none();
+        assert!(passes("GB", "SW1A 1AA"));
+        assert!(passes("gbr", "sw1a 1aa"));
+        assert!(!passes("GB", "90210"));
+        assert!(passes("DE", "10115"));
+        assert!(!passes("DE", "SW1A 1AA"));
+        // Blank falls back to `country`; no known format isn't checked
+        assert!(passes("", "90210-1234"));
+        assert!(!passes(" ", "SW1A 1AA"));
+        assert!(passes("XX", "anything"));
+    }
+
+    #[test]
+    fn postal_code_needs_a_country() {
+        let registry = RuleRegistry::new();
+        let rule = RuleType::PostalCode { country: None, country_column: None, allow_empty: false };
+        assert_eq!(registry.compile(&rule).err(), Some("Set country or country_column".to_string()));
+        assert_eq!(postal_rules(None, "nation").err(), Some("Invalid Rule for column 'zip': Unknown Column: nation".to_string()));
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::{Cancelled, Progress};
+use crate::Dataset;
+
+// --- Random Samples ---
+//
+// A few rows picked at random for someone to eyeball before a full load.
+// The same seed over the same rows always picks the same sample, so a
+// sample can be regenerated when an analyst asks about it later.
+
+/// Which rows `export_sample` draws from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SampleRows {
+    Valid,
+    Invalid,
+    /// Every row, valid or not.
+    Mixed,
+}
+
+impl SampleRows {
+    pub fn parse(rows: &str) -> Result<SampleRows, ValidatorError> {
+        match rows {
+            "valid" => Ok(SampleRows::Valid),
+            "invalid" => Ok(SampleRows::Invalid),
+            "mixed" => Ok(SampleRows::Mixed),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Sample Rows: {}", other))),
+        }
+    }
+}
+
+/// SplitMix64; small, fast and identical on every platform, which is all a
+/// reproducible sample needs.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`; the modulo bias is negligible for row counts.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Uniform in `lo..=hi`, for any span up to the whole of `u64`.
+    pub(crate) fn between(&mut self, lo: u64, hi: u64) -> u64 {
+        match (hi - lo).checked_add(1) {
+            Some(span) => lo + self.next() % span,
+            None => self.next(),
+        }
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub(crate) fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+/// Keeps a uniform sample of up to `n` of the rows offered to it, in one pass.
+struct Reservoir {
+    n: usize,
+    seen: usize,
+    rows: Vec<usize>,
+}
+
+impl Reservoir {
+    fn new(n: usize) -> Reservoir {
+        Reservoir { n, seen: 0, rows: Vec::new() }
+    }
+
+    fn offer(&mut self, idx: usize, rng: &mut SplitMix64) {
+        self.seen += 1;
+        if self.rows.len() < self.n {
+            self.rows.push(idx);
+        } else {
+            let slot = rng.below(self.seen);
+            if slot < self.n {
+                self.rows[slot] = idx;
+            }
+        }
+    }
+}
+
+impl Dataset {
+    fn sample_matches(&self, idx: usize, rows: SampleRows) -> bool {
+        match rows {
+            SampleRows::Mixed => true,
+            SampleRows::Valid => self.violations(idx).is_empty(),
+            SampleRows::Invalid => !self.violations(idx).is_empty(),
+        }
+    }
+
+    /// Up to `n` row indices drawn uniformly from the matching rows, in file order.
+    pub(crate) fn sample_rows(&self, n: usize, seed: u32, rows: SampleRows, progress: &Progress) -> Result<Vec<usize>, Cancelled> {
+        let mut rng = SplitMix64(seed as u64);
+        let mut reservoir = Reservoir::new(n);
+        let total = self.records.len();
+        for idx in 0..total {
+            progress.row(idx, total)?;
+            if self.sample_matches(idx, rows) {
+                reservoir.offer(idx, &mut rng);
+            }
+        }
+        let mut sample = reservoir.rows;
+        sample.sort_unstable();
+        Ok(sample)
+    }
+
+    /// Up to `per_value` matching row indices for each distinct value of
+    /// column `col` (the empty value included), drawn uniformly within each
+    /// value, in file order.
+    pub(crate) fn stratified_sample_rows(&self, col: usize, per_value: usize, seed: u32, rows: SampleRows, progress: &Progress) -> Result<Vec<usize>, Cancelled> {
+        let mut rng = SplitMix64(seed as u64);
+        let mut strata: HashMap<&str, Reservoir> = HashMap::new();
+        let total = self.records.len();
+        for (idx, value) in self.records.column(col).enumerate() {
+            progress.row(idx, total)?;
+            if self.sample_matches(idx, rows) {
+                strata.entry(value).or_insert_with(|| Reservoir::new(per_value)).offer(idx, &mut rng);
+            }
+        }
+        let mut sample: Vec<usize> = strata.into_values().flat_map(|reservoir| reservoir.rows).collect();
+        sample.sort_unstable();
+        Ok(sample)
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::ValidatorError;
+use crate::report::SessionReport;
+use crate::Validator;
+
+// --- Validation Sessions ---
+
+/// Several named datasets, each with its own rules, validated together for a
+/// single verdict (e.g. the related files of one monthly import).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ValidationSession {
+    // In the order they were added
+    datasets: Vec<(String, Validator)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ValidationSession {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> ValidationSession {
+        ValidationSession { datasets: Vec::new() }
+    }
+
+    /// Parses `csv_data` as dataset `name`, validated against `rules_json`.
+    /// `options_json` is an optional `ProcessorOptions` object. Replaces any
+    /// dataset already under that name.
+    pub fn add_dataset(&mut self, name: &str, csv_data: &str, rules_json: &str, options_json: Option<String>) -> Result<(), ValidatorError> {
+        let validator = Validator::from_json(csv_data, rules_json, options_json.as_deref())?;
+        self.insert(name, validator);
+        Ok(())
+    }
+
+    /// Returns whether a dataset called `name` was removed.
+    pub fn remove_dataset(&mut self, name: &str) -> bool {
+        let before = self.datasets.len();
+        self.datasets.retain(|(n, _)| n != name);
+        self.datasets.len() != before
+    }
+
+    pub fn get_dataset_names(&self) -> Vec<String> {
+        self.datasets.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// A versioned JSON report with an overall `passed` verdict, totals over
+    /// every dataset, and each dataset's `generate_report` contents under
+    /// `datasets`.
+    pub fn generate_report(&self) -> Result<String, ValidatorError> {
+        let mut audits = Vec::with_capacity(self.datasets.len());
+        for (name, validator) in &self.datasets {
+            audits.push((name.as_str(), validator.audit()?));
+        }
+        SessionReport::new(audits).to_json()
+    }
+}
+
+impl ValidationSession {
+    /// Adds a loaded `validator` as dataset `name`, like `add_dataset`.
+    pub fn insert(&mut self, name: &str, validator: Validator) {
+        match self.datasets.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = validator,
+            None => self.datasets.push((name.to_string(), validator)),
+        }
+    }
+}
+
+impl Default for ValidationSession {
+    fn default() -> ValidationSession {
+        ValidationSession::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvProcessorBuilder;
+
+    #[test]
+    fn report_covers_every_dataset_by_name() {
+        let mut session = ValidationSession::new();
+        session.add_dataset("orders", "id\n1\n\n", r#"[{"column": "id", "rules": [{"type": "notempty"}]}]"#, None).unwrap();
+        let builder = CsvProcessorBuilder::new().rules(r#"[{"column": "sku", "rules": [{"type": "notempty"}]}]"#).unwrap();
+        session.insert("items", builder.build_validator("sku,qty\nA1,1\n,2\n").unwrap());
+        session.insert("orders", Validator::from_json("id\n1\n", "[]", None).unwrap());
+
+        assert_eq!(session.get_dataset_names(), ["orders", "items"]);
+        let report: serde_json::Value = serde_json::from_str(&session.generate_report().unwrap()).unwrap();
+        assert_eq!(report["passed"], false);
+        assert_eq!(report["summary"]["failed_datasets"], serde_json::json!(["items"]));
+        assert_eq!(report["datasets"][0]["summary"]["total_errors"], 0);
+        assert_eq!(report["datasets"][1]["summary"]["total_errors"], 1);
+        assert!(session.add_dataset("bad", "a\n", "nope", None).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use std::cmp::Ordering;
+
+use crate::engine::parse_number;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::{Cancelled, Progress};
+use crate::rules::parse_timestamp;
+use crate::Dataset;
+
+// --- Sorting ---
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub fn parse(direction: &str) -> Result<SortDirection, ValidatorError> {
+        match direction {
+            "asc" => Ok(SortDirection::Asc),
+            "desc" => Ok(SortDirection::Desc),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Sort Direction: {}", other))),
+        }
+    }
+}
+
+/// How cells are compared by `sort_by`.
+#[derive(Clone, Copy)]
+pub enum SortType {
+    /// Byte-wise string order.
+    Text,
+    Number,
+    /// A date the `date` rule accepts (or the same with `/` separators),
+    /// optionally followed by a `HH:MM[:SS[.fff]]` time after a `T` or space.
+    Date,
+}
+
+impl SortType {
+    pub fn parse(sort_type: &str) -> Result<SortType, ValidatorError> {
+        match sort_type {
+            "text" => Ok(SortType::Text),
+            "number" => Ok(SortType::Number),
+            "date" => Ok(SortType::Date),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Sort Type: {}", other))),
+        }
+    }
+}
+
+/// Row order for `keys`; rows without a key (empty, or not of the sort
+/// type) go last in either direction. Ties keep their current order.
+fn order_by<K>(keys: &[Option<K>], direction: SortDirection, cmp: impl Fn(&K, &K) -> Ordering) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by(|&a, &b| match (&keys[a], &keys[b]) {
+        (Some(x), Some(y)) if direction == SortDirection::Desc => cmp(x, y).reverse(),
+        (Some(x), Some(y)) => cmp(x, y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+    order
+}
+
+impl Dataset {
+    /// Sorts the rows by column `col` (stable), returning the applied order:
+    /// row `i` is now the old row `order[i]`. `origins` is updated so each
+    /// row keeps its original row number.
+    pub(crate) fn sort(&mut self, col: usize, direction: SortDirection, sort_type: SortType, progress: &Progress) -> Result<Vec<usize>, Cancelled> {
+        progress.check()?;
+        let order = match sort_type {
+            SortType::Text => {
+                let keys: Vec<Option<&str>> = self.records.column(col).map(|v| Some(v).filter(|v| !v.is_empty())).collect();
+                order_by(&keys, direction, |a, b| a.cmp(b))
+            }
+            SortType::Number => {
+                let keys: Vec<Option<f64>> = self.records.column(col).map(|v| parse_number(v.trim()).ok()).collect();
+                order_by(&keys, direction, |a, b| a.total_cmp(b))
+            }
+            SortType::Date => {
+                let keys: Vec<_> = self.records.column(col).map(|v| parse_timestamp(v.trim())).collect();
+                order_by(&keys, direction, |a, b| a.cmp(b))
+            }
+        };
+        progress.check()?;
+
+        self.records.permute(&order);
+        self.origins = if self.origins.is_empty() {
+            order.iter().map(|&row| row as u32).collect()
+        } else {
+            order.iter().map(|&row| self.origins[row]).collect()
+        };
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_options, parse_rules};
+
+    #[test]
+    fn dates_sort_by_day_then_time_with_the_rest_last() {
+        let csv = "when\n2024/01/05 10:30\n2024-02-30\n2024-01-05T09:00\n2023-12-31\n";
+        let rules = parse_rules("[]").unwrap();
+        let mut data = Dataset::load(csv, &rules, &parse_options("{}").unwrap(), &Progress::new(None, "")).unwrap();
+        let order = data.sort(0, SortDirection::Asc, SortType::Date, &Progress::new(None, "")).unwrap();
+        assert_eq!(order, [3, 2, 0, 1]);
+        let order = data.sort(0, SortDirection::Desc, SortType::Date, &Progress::new(None, "")).unwrap();
+        assert_eq!(data.records.get(0, 0), "2024/01/05 10:30");
+        assert_eq!(order, [2, 1, 0, 3]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt::Write as _;
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::Dataset;
+
+// --- SQL Output ---
+
+// Rows per multi-row INSERT; SQLite before 3.8.8 rejects more than 500
+const INSERT_BATCH_ROWS: usize = 500;
+
+/// Target database for `to_sql`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SqlDialect {
+    Postgres,
+    MySql,
+    Sqlite,
+    /// A Postgres `COPY ... FROM STDIN` payload for `psql`.
+    PostgresCopy,
+}
+
+impl SqlDialect {
+    pub fn parse(dialect: &str) -> Result<SqlDialect, ValidatorError> {
+        match dialect {
+            "postgres" => Ok(SqlDialect::Postgres),
+            "mysql" => Ok(SqlDialect::MySql),
+            "sqlite" => Ok(SqlDialect::Sqlite),
+            "copy" => Ok(SqlDialect::PostgresCopy),
+            other => Err(ValidatorError::new(ErrorKind::Options, format!("Unknown SQL Dialect: {}", other))),
+        }
+    }
+
+    /// Quotes a possibly schema-qualified name, e.g. `sales.orders`.
+    fn table(self, name: &str) -> String {
+        name.split('.').map(|part| self.identifier(part)).collect::<Vec<_>>().join(".")
+    }
+
+    fn identifier(self, name: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("`{}`", name.replace('`', "``")),
+            _ => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    /// A string literal, or `NULL` for a missing value.
+    fn literal(self, value: Option<&str>) -> String {
+        let Some(value) = value else {
+            return "NULL".to_string();
+        };
+        let escaped = value.replace('\'', "''");
+        match self {
+            // MySQL treats backslashes in literals as escapes by default
+            SqlDialect::MySql => format!("'{}'", escaped.replace('\\', "\\\\")),
+            _ => format!("'{}'", escaped),
+        }
+    }
+}
+
+/// A cell in `COPY` text format, where `\N` is NULL.
+fn copy_value(value: Option<&str>, out: &mut String) {
+    let Some(value) = value else {
+        out.push_str("\\N");
+        return;
+    };
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+}
+
+impl Dataset {
+    /// The valid rows as statements loading them into `table`. Empty cells
+    /// and `null_values` tokens become NULL; every other value is a string
+    /// literal and left to the column type to convert.
+    pub(crate) fn write_sql(&self, table: &str, dialect: SqlDialect, progress: &Progress) -> Result<String, ValidatorError> {
+        if table.is_empty() {
+            return Err(ValidatorError::new(ErrorKind::Export, "Invalid Table: a table name is required"));
+        }
+        let columns: Vec<String> = self.headers.iter().map(|h| dialect.identifier(h)).collect();
+        let prefix = format!("INSERT INTO {} ({}) VALUES", dialect.table(table), columns.join(", "));
+
+        let mut sql = String::new();
+        if dialect == SqlDialect::PostgresCopy {
+            let _ = writeln!(sql, "COPY {} ({}) FROM STDIN;", dialect.table(table), columns.join(", "));
+        }
+
+        let total = self.records.len();
+        let mut batch = 0;
+        for (idx, record) in self.records.rows().enumerate() {
+            progress.row(idx, total)?;
+            if !self.violations(idx).is_empty() {
+                continue;
+            }
+            if dialect == SqlDialect::PostgresCopy {
+                for (col, value) in record.iter().enumerate() {
+                    if col > 0 {
+                        sql.push('\t');
+                    }
+                    copy_value(self.sql_value(value), &mut sql);
+                }
+                sql.push('\n');
+                continue;
+            }
+
+            sql.push_str(if batch == 0 { &prefix } else { "," });
+            let values: Vec<String> = record.iter().map(|value| dialect.literal(self.sql_value(value))).collect();
+            let _ = write!(sql, "\n({})", values.join(", "));
+            batch += 1;
+            if batch == INSERT_BATCH_ROWS {
+                sql.push_str(";\n");
+                batch = 0;
+            }
+        }
+
+        match dialect {
+            SqlDialect::PostgresCopy => sql.push_str("\\.\n"),
+            _ if batch > 0 => sql.push_str(";\n"),
+            _ => {}
+        }
+        Ok(sql)
+    }
+
+    /// `value`, or `None` when it stands for a missing value.
+    fn sql_value<'a>(&self, value: &'a str) -> Option<&'a str> {
+        (!value.is_empty() && !self.rule_set.is_null(value)).then_some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_options, parse_rules};
+
+    fn sql(csv: &str, dialect: SqlDialect, options: &str) -> String {
+        let rules = parse_rules(r#"[{"column": "n", "rules": [{"type": "number", "allow_empty": true}]}]"#).unwrap();
+        let data = Dataset::load(csv, &rules, &parse_options(options).unwrap(), &Progress::new(None, "")).unwrap();
+        data.write_sql("s.t", dialect, &Progress::new(None, "")).unwrap()
+    }
+
+    #[test]
+    fn literals_and_identifiers_are_escaped() {
+        let csv = "n,it's \"x\"\n1,O'Brien \\ co\n";
+        assert_eq!(sql(csv, SqlDialect::Postgres, "{}"), "INSERT INTO \"s\".\"t\" (\"n\", \"it's \"\"x\"\"\") VALUES\n('1', 'O''Brien \\ co');\n");
+        assert_eq!(sql(csv, SqlDialect::MySql, "{}"), "INSERT INTO `s`.`t` (`n`, `it's \"x\"`) VALUES\n('1', 'O''Brien \\\\ co');\n");
+    }
+
+    #[test]
+    fn copy_escapes_tabs_newlines_and_backslashes() {
+        let csv = "n,note\n1,\"a\tb\nc\\d\"\n";
+        assert_eq!(sql(csv, SqlDialect::PostgresCopy, "{}"), "COPY \"s\".\"t\" (\"n\", \"note\") FROM STDIN;\n1\ta\\tb\\nc\\\\d\n\\.\n");
+    }
+
+    #[test]
+    fn empty_cells_and_null_tokens_are_null() {
+        let csv = "n,note\nNA,\n2,NA\n";
+        let options = r#"{"validation": {"null_values": ["NA"]}}"#;
+        assert_eq!(sql(csv, SqlDialect::Sqlite, options), "INSERT INTO \"s\".\"t\" (\"n\", \"note\") VALUES\n(NULL, NULL),\n('2', NULL);\n");
+        assert_eq!(sql(csv, SqlDialect::PostgresCopy, options), "COPY \"s\".\"t\" (\"n\", \"note\") FROM STDIN;\n\\N\t\\N\n2\t\\N\n\\.\n");
+    }
+}
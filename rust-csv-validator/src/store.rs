@@ -0,0 +1,326 @@
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+
+use crate::engine::Record;
+use crate::StorageLayout;
+
+// --- Record Storage ---
+//
+// Owning every cell as its own `String` costs a heap allocation plus 24 bytes
+// of header per cell. Instead all cell text lives in one arena and each cell
+// is an 8-byte offset/length span into it. Short values are interned, so a
+// categorical column repeating "active" a million times stores it once.
+
+// Only values up to this length are looked up in the interner
+const INTERN_MAX_LEN: usize = 32;
+// Cap on distinct interned values so high-cardinality columns can't grow the table forever
+const INTERN_MAX_ENTRIES: usize = 1 << 16;
+
+#[derive(Clone, Copy, Default)]
+struct Span {
+    start: u32,
+    len: u32,
+}
+
+/// Table of cells backed by a single text arena, laid out by row or by column.
+#[derive(Clone, Default)]
+pub struct RecordStore {
+    buf: String,
+    cells: Cells,
+    width: usize,
+    rows: usize,
+    interner: Interner,
+}
+
+#[derive(Clone)]
+enum Cells {
+    // `width` spans per row, one row after another
+    Rows(Vec<Span>),
+    // One contiguous span list per column
+    Columns(Vec<Vec<Span>>),
+}
+
+impl Default for Cells {
+    fn default() -> Cells {
+        Cells::Rows(Vec::new())
+    }
+}
+
+/// Maps the hash of a short value to the span already holding it. Hash
+/// collisions are resolved by comparing text; the loser is simply not shared.
+#[derive(Clone, Default)]
+struct Interner {
+    spans: HashMap<u64, Span>,
+    hasher: RandomState,
+}
+
+impl RecordStore {
+    pub fn new(width: usize) -> RecordStore {
+        RecordStore { width, ..RecordStore::default() }
+    }
+
+    pub fn with_layout(width: usize, layout: StorageLayout) -> RecordStore {
+        let mut store = RecordStore::new(width);
+        if layout == StorageLayout::Columns {
+            store.cells = Cells::Columns(vec![Vec::new(); width]);
+        }
+        store
+    }
+
+    pub fn layout(&self) -> StorageLayout {
+        match self.cells {
+            Cells::Rows(_) => StorageLayout::Rows,
+            Cells::Columns(_) => StorageLayout::Columns,
+        }
+    }
+
+    /// Copies the cells into the given layout (a no-op if already there).
+    pub fn into_layout(self, layout: StorageLayout) -> RecordStore {
+        if self.layout() == layout {
+            return self;
+        }
+        let cells = match &self.cells {
+            Cells::Rows(spans) => Cells::Columns(
+                (0..self.width).map(|col| spans.iter().skip(col).step_by(self.width).copied().collect()).collect(),
+            ),
+            Cells::Columns(columns) => Cells::Rows(
+                (0..self.rows).flat_map(|row| columns.iter().map(move |c| c[row])).collect(),
+            ),
+        };
+        RecordStore { cells, ..self }
+    }
+
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Approximate heap bytes held by the arena, span tables and interner.
+    pub fn byte_size(&self) -> usize {
+        let span_slots = match &self.cells {
+            Cells::Rows(spans) => spans.capacity(),
+            Cells::Columns(columns) => columns.iter().map(|c| c.capacity()).sum(),
+        };
+        self.buf.capacity()
+            + span_slots * std::mem::size_of::<Span>()
+            + self.interner.spans.capacity() * (std::mem::size_of::<u64>() + std::mem::size_of::<Span>())
+    }
+
+    /// Appends a row. Missing trailing cells are stored as empty strings and
+    /// extra cells are dropped so every row has exactly `width` cells.
+    pub fn push_row<'a>(&mut self, cells: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+        let mut cells = cells.into_iter();
+        for col in 0..self.width {
+            let span = match cells.next() {
+                Some(cell) => self.store_text(cell)?,
+                None => Span::default(),
+            };
+            match &mut self.cells {
+                Cells::Rows(spans) => spans.push(span),
+                Cells::Columns(columns) => columns[col].push(span),
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &str {
+        self.text(self.span(row, col))
+    }
+
+    /// All values of one column, top to bottom.
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &str> + '_ {
+        let spans: Box<dyn Iterator<Item = &Span>> = match &self.cells {
+            Cells::Rows(spans) => Box::new(spans.iter().skip(col).step_by(self.width.max(1))),
+            Cells::Columns(columns) => Box::new(columns[col].iter()),
+        };
+        spans.map(move |&span| self.text(span))
+    }
+
+    pub fn row(&self, index: usize) -> Row<'_> {
+        Row { store: self, index }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = Row<'_>> + '_ {
+        (0..self.len()).map(move |index| self.row(index))
+    }
+
+    /// Replaces every `target` cell in `col` with `replacement`, returning the
+    /// indices of the rows that changed. The replacement text is stored once
+    /// and shared by all touched cells.
+    pub fn replace_all(&mut self, col: usize, target: &str, replacement: &str) -> Result<Vec<usize>, String> {
+        let changed: Vec<usize> = self.column(col)
+            .enumerate()
+            .filter(|(_, value)| *value == target)
+            .map(|(row, _)| row)
+            .collect();
+        if changed.is_empty() {
+            return Ok(changed);
+        }
+        let span = self.store_text(replacement)?;
+        for &row in &changed {
+            *self.span_mut(row, col) = span;
+        }
+        Ok(changed)
+    }
+
+    /// Sets the cell at `row`, `col` to `text`.
+    pub fn set(&mut self, row: usize, col: usize, text: &str) -> Result<(), String> {
+        let span = self.store_text(text)?;
+        *self.span_mut(row, col) = span;
+        Ok(())
+    }
+
+    /// A copy holding only `columns`, in that order.
+    pub fn select_columns(&self, columns: &[usize]) -> Result<RecordStore, String> {
+        let mut store = RecordStore::with_layout(columns.len(), self.layout());
+        for row in self.rows() {
+            store.push_row(columns.iter().map(|&col| row.get(col)))?;
+        }
+        Ok(store)
+    }
+
+    /// Drops the rows from `rows` on. Their text stays in the arena.
+    pub fn truncate(&mut self, rows: usize) {
+        if rows >= self.rows {
+            return;
+        }
+        match &mut self.cells {
+            Cells::Rows(spans) => spans.truncate(rows * self.width),
+            Cells::Columns(columns) => columns.iter_mut().for_each(|c| c.truncate(rows)),
+        }
+        self.rows = rows;
+    }
+
+    /// Reorders the rows so row `i` becomes the old row `order[i]`. Only the
+    /// spans move; the arena is left as it is.
+    pub fn permute(&mut self, order: &[usize]) {
+        debug_assert_eq!(order.len(), self.rows);
+        match &mut self.cells {
+            Cells::Rows(spans) => {
+                let mut sorted = Vec::with_capacity(spans.len());
+                for &row in order {
+                    sorted.extend_from_slice(&spans[row * self.width..(row + 1) * self.width]);
+                }
+                *spans = sorted;
+            }
+            Cells::Columns(columns) => {
+                for column in columns.iter_mut() {
+                    *column = order.iter().map(|&row| column[row]).collect();
+                }
+            }
+        }
+    }
+
+    /// Returns a span holding `text`, reusing an interned copy when possible.
+    fn store_text(&mut self, text: &str) -> Result<Span, String> {
+        if text.is_empty() {
+            return Ok(Span::default());
+        }
+        if text.len() > INTERN_MAX_LEN {
+            return self.push_text(text);
+        }
+
+        let key = self.interner.hasher.hash_one(text);
+        if let Some(&span) = self.interner.spans.get(&key) {
+            if self.text(span) == text {
+                return Ok(span);
+            }
+            return self.push_text(text);
+        }
+        let span = self.push_text(text)?;
+        if self.interner.spans.len() < INTERN_MAX_ENTRIES {
+            self.interner.spans.insert(key, span);
+        }
+        Ok(span)
+    }
+
+    fn span(&self, row: usize, col: usize) -> Span {
+        match &self.cells {
+            Cells::Rows(spans) => spans[row * self.width + col],
+            Cells::Columns(columns) => columns[col][row],
+        }
+    }
+
+    fn span_mut(&mut self, row: usize, col: usize) -> &mut Span {
+        match &mut self.cells {
+            Cells::Rows(spans) => &mut spans[row * self.width + col],
+            Cells::Columns(columns) => &mut columns[col][row],
+        }
+    }
+
+    fn text(&self, span: Span) -> &str {
+        &self.buf[span.start as usize..(span.start + span.len) as usize]
+    }
+
+    fn push_text(&mut self, text: &str) -> Result<Span, String> {
+        let start = u32::try_from(self.buf.len()).ok();
+        let len = u32::try_from(text.len()).ok();
+        match (start, len) {
+            (Some(start), Some(len)) if start.checked_add(len).is_some() => {
+                self.buf.push_str(text);
+                Ok(Span { start, len })
+            }
+            _ => Err("Dataset exceeds the 4 GiB record storage limit".to_string()),
+        }
+    }
+}
+
+/// Borrowed view of one row in a `RecordStore`.
+#[derive(Clone, Copy)]
+pub struct Row<'a> {
+    store: &'a RecordStore,
+    index: usize,
+}
+
+impl<'a> Row<'a> {
+    pub fn get(&self, col: usize) -> &'a str {
+        self.store.get(self.index, col)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a str> + 'a {
+        let (store, index) = (self.store, self.index);
+        (0..store.width).map(move |col| store.get(index, col))
+    }
+}
+
+impl Record for Row<'_> {
+    fn cell(&self, column: usize) -> &str {
+        if column < self.store.width { self.get(column) } else { "" }
+    }
+}
+
+// Persisted as a plain array of string arrays so saved sessions stay readable
+impl Serialize for RecordStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for row in self.rows() {
+            seq.serialize_element(&row.iter().collect::<Vec<_>>())?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RecordStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RecordStore, D::Error> {
+        let rows: Vec<Vec<String>> = Vec::deserialize(deserializer)?;
+        let mut store = RecordStore::new(rows.first().map_or(0, |r| r.len()));
+        for row in &rows {
+            if row.len() != store.width {
+                return Err(serde::de::Error::custom("records have inconsistent lengths"));
+            }
+            store.push_row(row.iter().map(|s| s.as_str())).map_err(serde::de::Error::custom)?;
+        }
+        Ok(store)
+    }
+}
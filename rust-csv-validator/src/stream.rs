@@ -0,0 +1,529 @@
+use csv_core::ReadRecordResult;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::engine::error_code;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::mapping::{combine_header_rows, map_headers};
+use crate::store::RecordStore;
+use crate::validator::RowError;
+use crate::{is_known, ColumnRule, Dataset, ErrorSummary, ProcessorOptions, RuleType, UnknownColumns};
+#[cfg(feature = "wasm")]
+use {
+    crate::{parse_options, parse_rules},
+    wasm_bindgen::prelude::*,
+    wasm_bindgen_futures::JsFuture,
+};
+
+// --- Streaming Validation ---
+//
+// For server-side ingestion: bytes are pushed in chunks as they arrive (e.g.
+// from a Node `Readable`) and each chunk's invalid rows are handed back at
+// once, so the file is never held in memory. Options that need the whole
+// file (footer detection, dropping empty columns) are refused.
+
+// UTF-8 byte order mark, skipped at the start of the input
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Validates a CSV file pushed chunk by chunk. Records may span chunks.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct StreamValidator {
+    reader: csv_core::Reader,
+    rules: Vec<ColumnRule>,
+    options: ProcessorOptions,
+    // Header lines read so far, until all `header_rows` are in
+    header_lines: Vec<Vec<String>>,
+    // Headers and compiled rules; the record store stays empty
+    data: Option<Dataset>,
+    // Incoming columns to validate, in header order (all but dropped ones)
+    columns: Vec<usize>,
+    // The record being parsed: field bytes and the end offset of each field
+    output: Vec<u8>,
+    output_len: usize,
+    ends: Vec<usize>,
+    ends_len: usize,
+    summary: ErrorSummary,
+    row_count: usize,
+    // Non-empty cells seen per validated column, for the summary's rates
+    non_empty_cells: Vec<usize>,
+    // Counts for the rows completed by the current push, then by each earlier one
+    chunk: ChunkStats,
+    chunks: Vec<ChunkStats>,
+    pushes: usize,
+    // Bytes pushed so far, for `limits.max_bytes`
+    bytes_read: usize,
+    // The first bytes pushed, held back while they could still be the start
+    // of a byte order mark; `None` once the input is past it
+    head: Option<Vec<u8>>,
+    finished: bool,
+}
+
+/// An invalid row as handed out by `push`.
+#[derive(Serialize)]
+pub struct StreamRow {
+    /// 1-based record number, not counting the header.
+    pub row_number: usize,
+    /// The row's cells, in header order.
+    pub values: Vec<String>,
+    pub errors: Vec<RowError>,
+}
+
+/// Errors in the rows completed by one push, so a file that goes wrong part
+/// way (e.g. columns shifting at row 80,000) stands out. Counts include
+/// errors past `max_errors`.
+#[derive(Serialize, Default)]
+pub struct ChunkStats {
+    /// 0-based index of the push, the final (empty) one included.
+    pub chunk: usize,
+    /// 1-based record numbers of the first and last rows completed.
+    pub first_row: usize,
+    pub last_row: usize,
+    pub invalid_rows: usize,
+    pub errors: usize,
+    /// column -> errors; columns without errors are left out
+    pub columns: BTreeMap<String, usize>,
+}
+
+/// The totals returned by `finish`.
+#[derive(Serialize)]
+pub struct StreamSummary {
+    /// One entry per push that completed at least one row.
+    pub chunks: Vec<ChunkStats>,
+    #[serde(flatten)]
+    pub summary: ErrorSummary,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl StreamValidator {
+    /// `options_json` is an optional `ProcessorOptions` object.
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(rules_json: &str, options_json: Option<String>) -> Result<StreamValidator, JsValue> {
+        let options = options_json.as_deref().map_or_else(|| Ok(ProcessorOptions::default()), parse_options)?;
+        Ok(StreamValidator::new(parse_rules(rules_json)?, options)?)
+    }
+
+    /// Parses a chunk (a `Uint8Array` or Node `Buffer`) and returns the
+    /// invalid rows it completed, as an array of `StreamRow`s. An empty
+    /// chunk ends the input, as `finish` does.
+    #[wasm_bindgen(unchecked_return_type = "StreamRow[]")]
+    pub fn push(&mut self, chunk: &[u8]) -> Result<JsValue, JsValue> {
+        let rows = self.push_bytes(chunk)?;
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        rows.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Ends the input. Returns `{rows, invalid_rows}`, the last invalid rows
+    /// (from a final line without a newline), and the summary totals.
+    #[wasm_bindgen(unchecked_return_type = "StreamFinish")]
+    pub fn finish(&mut self) -> Result<JsValue, JsValue> {
+        let rows = self.push_bytes(&[])?;
+        let result = FinishResult { rows, summary: self.finish_summary()? };
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        result.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[derive(Serialize)]
+struct FinishResult {
+    rows: Vec<StreamRow>,
+    summary: StreamSummary,
+}
+
+impl StreamValidator {
+    pub fn new(rules: Vec<ColumnRule>, options: ProcessorOptions) -> Result<StreamValidator, ValidatorError> {
+        if options.skip_footer_rows > 0 || options.footer_pattern.is_some() || options.drop_empty_columns {
+            return Err(ValidatorError::new(
+                ErrorKind::Options,
+                "Invalid Options: skip_footer_rows, footer_pattern and drop_empty_columns need the whole file and can't be used when streaming",
+            ));
+        }
+        for rule in &rules {
+            let name = match rule.rules.iter().find(|r| matches!(r, RuleType::Anomaly { .. } | RuleType::DependsOn { .. })) {
+                Some(RuleType::Anomaly { .. }) => "anomaly",
+                Some(_) => "dependson",
+                None => continue,
+            };
+            return Err(ValidatorError::new(
+                ErrorKind::Rules,
+                format!("Invalid Rules: the {} rule of column '{}' needs the whole column and can't be used when streaming", name, rule.column),
+            ));
+        }
+        Ok(StreamValidator {
+            reader: csv_core::Reader::new(),
+            rules,
+            options,
+            header_lines: Vec::new(),
+            data: None,
+            columns: Vec::new(),
+            output: vec![0; 1024],
+            output_len: 0,
+            ends: vec![0; 16],
+            ends_len: 0,
+            summary: ErrorSummary::default(),
+            row_count: 0,
+            non_empty_cells: Vec::new(),
+            chunk: ChunkStats::default(),
+            chunks: Vec::new(),
+            pushes: 0,
+            bytes_read: 0,
+            head: Some(Vec::new()),
+            finished: false,
+        })
+    }
+
+    /// Parses `chunk`, returning the invalid rows it completed. An empty
+    /// chunk marks the end of the input.
+    pub fn push_bytes(&mut self, chunk: &[u8]) -> Result<Vec<StreamRow>, ValidatorError> {
+        let mut invalid = Vec::new();
+        if self.finished {
+            return Ok(invalid);
+        }
+        let at_end = chunk.is_empty();
+        self.bytes_read += chunk.len();
+        self.options.limits.check_bytes(self.bytes_read)?;
+        self.chunk = ChunkStats { chunk: self.pushes, first_row: self.row_count + 1, ..ChunkStats::default() };
+        self.pushes += 1;
+        match self.head.take() {
+            Some(mut head) => {
+                head.extend_from_slice(chunk);
+                if !at_end && head.len() < BOM.len() && BOM.starts_with(&head) {
+                    self.head = Some(head);
+                } else {
+                    self.read(head.strip_prefix(BOM).unwrap_or(&head), false, &mut invalid)?;
+                }
+            }
+            None => self.read(chunk, false, &mut invalid)?,
+        }
+        if at_end {
+            self.read(&[], true, &mut invalid)?;
+            self.finished = true;
+        }
+        if self.row_count >= self.chunk.first_row {
+            let mut chunk = std::mem::take(&mut self.chunk);
+            chunk.last_row = self.row_count;
+            self.chunks.push(chunk);
+        }
+        Ok(invalid)
+    }
+
+    /// Feeds `input` to the reader, collecting the invalid rows it completes.
+    /// Empty input ends the file when `at_end` and is skipped otherwise, as
+    /// the reader would take it for the end.
+    fn read(&mut self, mut input: &[u8], at_end: bool, invalid: &mut Vec<StreamRow>) -> Result<(), ValidatorError> {
+        if input.is_empty() && !at_end {
+            return Ok(());
+        }
+        loop {
+            let (result, read, written, ended) = self.reader.read_record(
+                input,
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            input = &input[read..];
+            self.output_len += written;
+            self.ends_len += ended;
+            match result {
+                ReadRecordResult::InputEmpty => break,
+                ReadRecordResult::OutputFull => self.output.resize(self.output.len() * 2, 0),
+                ReadRecordResult::OutputEndsFull => self.ends.resize(self.ends.len() * 2, 0),
+                ReadRecordResult::Record => {
+                    let fields = self.take_record()?;
+                    if let Some(row) = self.accept(fields)? {
+                        invalid.push(row);
+                    }
+                    // An empty input would tell the reader the file ended
+                    if input.is_empty() && !at_end {
+                        break;
+                    }
+                }
+                ReadRecordResult::End => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The totals so far; after `push_bytes(&[])` they cover the whole file.
+    pub fn finish_summary(&mut self) -> Result<StreamSummary, ValidatorError> {
+        if self.data.is_none() && !self.header_lines.is_empty() {
+            self.start()?;
+        }
+        let mut summary = std::mem::take(&mut self.summary);
+        match &self.data {
+            None if !self.options.allow_empty => return Err(ValidatorError::new(ErrorKind::Empty, "Empty File: no header")),
+            None => summary.warnings.push("The file has no header".to_string()),
+            Some(_) if self.row_count == 0 && !self.options.allow_empty => {
+                return Err(ValidatorError::new(ErrorKind::Empty, "Empty File: no records after the header"));
+            }
+            Some(data) => {
+                if self.row_count == 0 {
+                    summary.warnings.push("The file has no records".to_string());
+                }
+                data.report_unvalidated(&mut summary, &self.options.validation);
+                data.report_unknown(&mut summary, self.options.unknown_columns);
+                let non_empty_cells = &self.non_empty_cells;
+                summary.set_rates(self.row_count, |header| {
+                    data.headers.iter().position(|h| h == header)
+                        .and_then(|col| non_empty_cells.get(col).copied())
+                        .unwrap_or(0)
+                });
+            }
+        }
+        Ok(StreamSummary { chunks: std::mem::take(&mut self.chunks), summary })
+    }
+
+    /// The fields of the record just completed, decoded as UTF-8.
+    fn take_record(&mut self) -> Result<Vec<String>, ValidatorError> {
+        let mut fields = Vec::with_capacity(self.ends_len);
+        let mut start = 0;
+        for &end in &self.ends[..self.ends_len] {
+            let bytes = &self.output[start..end];
+            let field = match std::str::from_utf8(bytes) {
+                Ok(text) => text.to_string(),
+                Err(_) if self.options.lossy_utf8 => String::from_utf8_lossy(bytes).into_owned(),
+                Err(e) => {
+                    let message = format!("Encoding Error: invalid UTF-8 in record {} (byte {} of field {})", self.record_index(), e.valid_up_to(), fields.len() + 1);
+                    return Err(self.parse_error(message));
+                }
+            };
+            fields.push(field);
+            start = end;
+        }
+        self.output_len = 0;
+        self.ends_len = 0;
+        Ok(fields)
+    }
+
+    /// Takes one complete record: a header line until the header is in,
+    /// then a data row, returned when invalid.
+    fn accept(&mut self, fields: Vec<String>) -> Result<Option<StreamRow>, ValidatorError> {
+        if self.data.is_none() {
+            self.header_lines.push(fields);
+            if self.header_lines.len() >= self.options.header_rows.max(1) {
+                self.start()?;
+            }
+            return Ok(None);
+        }
+        let Some(data) = &self.data else { return Ok(None) };
+
+        self.options.limits.check_cells(fields.iter().map(|f| f.as_str())).map_err(|e| self.with_record(e))?;
+        let width = self.header_lines[0].len();
+        if fields.len() != width {
+            let message = format!("CSV Parse Error: record {} has {} fields, but the header has {}", self.row_count + 1, fields.len(), width);
+            return Err(self.parse_error(message));
+        }
+        self.row_count += 1;
+        self.options.limits.check_rows(self.row_count)?;
+        let values: Vec<String> = self.columns.iter().map(|&col| fields[col].clone()).collect();
+        self.non_empty_cells.resize(values.len(), 0);
+        for (count, value) in self.non_empty_cells.iter_mut().zip(&values) {
+            *count += usize::from(!value.is_empty() && !data.rule_set.is_null(value));
+        }
+        let mut violations: Vec<_> = data.rule_set.validate_record(&values).collect();
+        if let Some(max) = self.options.validation.max_errors_per_row {
+            if violations.len() > max {
+                violations.truncate(max);
+                self.summary.malformed_rows.push(self.row_count);
+            }
+        }
+        let encoding: Vec<usize> = if self.options.lossy_utf8 {
+            (0..values.len()).filter(|&col| values[col].contains(char::REPLACEMENT_CHARACTER)).collect()
+        } else {
+            Vec::new()
+        };
+        if violations.is_empty() && encoding.is_empty() {
+            return Ok(None);
+        }
+        self.chunk.invalid_rows += 1;
+        self.chunk.errors += violations.len() + encoding.len();
+        for col in violations.iter().map(|v| v.column).chain(encoding.iter().copied()) {
+            *self.chunk.columns.entry(data.headers[col].clone()).or_insert(0) += 1;
+        }
+
+        let max_errors = self.options.validation.max_errors;
+        let mut errors = Vec::with_capacity(violations.len());
+        // Columns with an error in the summary
+        let mut recorded = Vec::new();
+        for v in &violations {
+            if max_errors.is_some_and(|max| self.summary.total_errors >= max) {
+                self.summary.truncated = true;
+            } else {
+                self.summary.record(&data.headers[v.column], v.error_type, &values[v.column]);
+                recorded.push(v.column);
+            }
+            errors.push(RowError {
+                column: data.headers[v.column].clone(),
+                rule: data.rule_set.rules_for(v.column)[v.rule].name(),
+                code: error_code(v.error_type),
+                error: v.error_type,
+            });
+        }
+        for &col in &encoding {
+            if max_errors.is_some_and(|max| self.summary.total_errors >= max) {
+                self.summary.truncated = true;
+            } else {
+                self.summary.record(&data.headers[col], "Encoding", &values[col]);
+                recorded.push(col);
+            }
+            errors.push(RowError { column: data.headers[col].clone(), rule: "encoding", code: error_code("Encoding"), error: "Encoding" });
+        }
+        recorded.sort_unstable();
+        recorded.dedup();
+        self.summary.invalid_cells += recorded.len();
+        self.summary.invalid_rows += usize::from(!recorded.is_empty());
+        Ok(Some(StreamRow { row_number: self.row_count, values, errors }))
+    }
+
+    /// Builds the headers from the header lines and compiles the rules,
+    /// applying `unknown_columns`.
+    fn start(&mut self) -> Result<(), ValidatorError> {
+        let combined = combine_header_rows(&self.header_lines, &self.options.header_join);
+        let headers = map_headers(&combined, &self.options.column_mapping).map_err(|e| ValidatorError::new(ErrorKind::Header, e))?;
+        self.options.limits.check_columns(headers.len())?;
+        let unknown: Vec<String> = match self.options.unknown_columns {
+            UnknownColumns::Ignore => Vec::new(),
+            _ => headers.iter().filter(|h| !is_known(h, &self.rules, &self.options)).cloned().collect(),
+        };
+        if self.options.unknown_columns == UnknownColumns::Reject && !unknown.is_empty() {
+            return Err(ValidatorError::new(ErrorKind::Header, format!("Unknown Columns: {}", unknown.join(", "))));
+        }
+        self.columns = (0..headers.len())
+            .filter(|&col| self.options.unknown_columns != UnknownColumns::Drop || !unknown.contains(&headers[col]))
+            .collect();
+        let kept: Vec<String> = self.columns.iter().map(|&col| headers[col].clone()).collect();
+        let mut data = Dataset::new(kept, RecordStore::new(self.columns.len()), &self.rules, &self.options)?;
+        data.unknown_columns = unknown;
+        self.data = Some(data);
+        Ok(())
+    }
+
+    /// 0-based index of the record being read, header lines included
+    /// (valid until a data row is counted).
+    fn record_index(&self) -> usize {
+        self.header_lines.len() + self.row_count
+    }
+
+    fn parse_error(&self, message: String) -> ValidatorError {
+        self.with_record(ValidatorError::new(ErrorKind::Parse, message))
+    }
+
+    fn with_record(&self, mut error: ValidatorError) -> ValidatorError {
+        error.record = Some(self.record_index() as u64);
+        error
+    }
+}
+
+/// Validates an async iterable of chunks (a Node `Readable`, a web
+/// `ReadableStream` or any async generator of `Uint8Array`s or strings),
+/// calling `on_rows(rows)` with each batch of invalid rows as it is found.
+/// Resolves to the `finish` totals.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(unchecked_return_type = "StreamSummary")]
+pub async fn validate_stream(
+    #[wasm_bindgen(unchecked_param_type = "AsyncIterable<Uint8Array | string>")] source: JsValue,
+    rules_json: String,
+    options_json: Option<String>,
+    on_rows: Option<js_sys::Function>,
+) -> Result<JsValue, JsValue> {
+    let mut validator = StreamValidator::js_new(&rules_json, options_json)?;
+    let iterator_fn = js_sys::Reflect::get(&source, &js_sys::Symbol::async_iterator())?;
+    let iterator_fn: js_sys::Function = iterator_fn
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("Invalid Stream: expected an async iterable"))?;
+    let iterator = iterator_fn.call0(&source)?;
+    let next: js_sys::Function = js_sys::Reflect::get(&iterator, &JsValue::from_str("next"))?.dyn_into()?;
+
+    let emit = |rows: JsValue| -> Result<(), JsValue> {
+        if let Some(callback) = &on_rows {
+            if js_sys::Array::from(&rows).length() > 0 {
+                callback.call1(&JsValue::NULL, &rows)?;
+            }
+        }
+        Ok(())
+    };
+
+    loop {
+        let step = JsFuture::from(js_sys::Promise::resolve(&next.call0(&iterator)?)).await?;
+        if js_sys::Reflect::get(&step, &JsValue::from_str("done"))?.is_truthy() {
+            break;
+        }
+        let chunk = js_sys::Reflect::get(&step, &JsValue::from_str("value"))?;
+        let rows = match chunk.as_string() {
+            Some(text) if !text.is_empty() => validator.push(text.as_bytes())?,
+            Some(_) => continue,
+            None if js_sys::Uint8Array::new(&chunk).length() == 0 => continue,
+            None => validator.push(&js_sys::Uint8Array::new(&chunk).to_vec())?,
+        };
+        emit(rows)?;
+    }
+
+    let rows = validator.push_bytes(&[])?;
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    emit(rows.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))?)?;
+    let summary = validator.finish_summary()?;
+    summary.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_options, parse_rules};
+
+    const RULES: &str = r#"[{"column": "a", "rules": [{"type": "number"}]}]"#;
+
+    fn stream(chunks: &[&[u8]], options: &str) -> (Vec<usize>, StreamSummary) {
+        let mut validator = StreamValidator::new(parse_rules(RULES).unwrap(), parse_options(options).unwrap()).unwrap();
+        let mut rows = Vec::new();
+        // An empty push ends the input, so only the last one is
+        for chunk in chunks.iter().copied().filter(|c| !c.is_empty()).chain([&[][..]]) {
+            rows.extend(validator.push_bytes(chunk).unwrap().into_iter().map(|row| row.row_number));
+        }
+        (rows, validator.finish_summary().unwrap())
+    }
+
+    #[test]
+    fn rows_are_the_same_wherever_the_chunks_split() {
+        let csv = b"\xEF\xBB\xBFa,b\n1,\"x\ny\"\nbad,2\r\n3,\"q\"\"\"\nnope,4";
+        let (whole, summary) = stream(&[csv], "{}");
+        assert_eq!(whole, vec![2, 4]);
+        assert_eq!(summary.summary.row_count, 4);
+        for split in 0..=csv.len() {
+            let (rows, summary) = stream(&[&csv[..split], &csv[split..]], "{}");
+            assert_eq!(rows, whole, "split at {}", split);
+            assert_eq!(summary.summary.total_errors, 2, "split at {}", split);
+        }
+        let bytes: Vec<&[u8]> = csv.chunks(1).collect();
+        assert_eq!(stream(&bytes, "{}").0, whole);
+    }
+
+    #[test]
+    fn a_chunk_holding_only_the_bom_does_not_end_the_input() {
+        let (rows, summary) = stream(&[b"\xEF\xBB\xBF", b"a,b\nx,1\n"], "{}");
+        assert_eq!(rows, vec![1]);
+        assert_eq!(summary.summary.stats["a"]["Not a Number"], 1);
+    }
+
+    #[test]
+    fn bytes_held_back_for_a_bom_are_kept_when_they_are_not_one() {
+        // U+FF21, whose UTF-8 starts with the BOM's first byte
+        let mut validator = StreamValidator::new(Vec::new(), ProcessorOptions::default()).unwrap();
+        validator.push_bytes(b"\xEF").unwrap();
+        validator.push_bytes(b"\xBC\xA1,b\n1,2\n").unwrap();
+        validator.push_bytes(&[]).unwrap();
+        validator.finish_summary().unwrap();
+        assert_eq!(validator.data.unwrap().headers, vec!["\u{FF21}", "b"]);
+    }
+
+    #[test]
+    fn encoding_errors_respect_max_errors() {
+        let options = r#"{"lossy_utf8": true, "validation": {"max_errors": 1}}"#;
+        let (rows, summary) = stream(&[b"a,b\n1,\xFF\n2,\xFE\n"], options);
+        assert_eq!(rows, vec![1, 2]);
+        assert_eq!(summary.summary.total_errors, 1);
+        assert!(summary.summary.truncated);
+    }
+}
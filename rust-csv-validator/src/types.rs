@@ -0,0 +1,359 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// --- TypeScript Types ---
+//
+// Results cross the boundary as plain objects (`JsValue`) and options as JSON
+// strings, so wasm-bindgen can't describe them. These interfaces mirror the
+// serde shapes and are added to the generated `.d.ts`; the methods returning
+// them name them with `unchecked_return_type`. Keep them in step with the
+// structs they describe.
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &str = r#"
+/** One validation rule; `rules_json` is a `ColumnRule[]`. */
+export type RuleType =
+    | { type: "notempty" }
+    | { type: "number"; min?: number | null; max?: number | null; allow_empty?: boolean }
+    | { type: "email"; allow_empty?: boolean }
+    | { type: "regex"; pattern: string; allow_empty?: boolean }
+    | { type: "oneof"; options: string[] }
+    | { type: "percent"; min?: number | null; max?: number | null; allow_symbol?: boolean; fraction?: boolean; allow_empty?: boolean }
+    | { type: "coordinate"; kind: "lat" | "lng" | "pair"; allow_empty?: boolean }
+    | { type: "countrycode"; alpha: 2 | 3; allow_empty?: boolean }
+    | { type: "languagecode"; allow_empty?: boolean }
+    | { type: "timezone"; allow_empty?: boolean }
+    | {
+        type: "date";
+        must_be?: "past" | "future" | "today_or_past" | null;
+        /** `YYYY-MM-DD`, or an offset from `now` such as `-90d`, `+2w`, `-6m` or `+1y`. */
+        min?: string | null;
+        max?: string | null;
+        now?: string | null;
+        allow_empty?: boolean;
+    }
+    | { type: "age"; min?: number | null; max?: number | null; reference_date?: string | null; allow_empty?: boolean }
+    | { type: "duration"; min?: string | null; max?: string | null; formats?: ("clock" | "units" | "iso8601")[]; allow_empty?: boolean }
+    | { type: "semver"; min?: string | null; max?: string | null; allow_empty?: boolean }
+    | { type: "hexcolor"; allow_alpha?: boolean; allow_shorthand?: boolean; allow_empty?: boolean }
+    | { type: "macaddress"; formats?: ("colon" | "dash" | "bare")[]; allow_empty?: boolean }
+    | { type: "base64"; max_decoded_bytes?: number | null; allow_empty?: boolean }
+    | { type: "hostname"; require_tld?: boolean; allow_empty?: boolean }
+    | { type: "path"; style?: "unix" | "windows" | "any"; must_be_absolute?: boolean; allowed_extensions?: string[]; allow_empty?: boolean }
+    | { type: "postalcode"; country?: string | null; country_column?: string | null; allow_empty?: boolean }
+    | { type: "nationalid"; country: string; allow_empty?: boolean }
+    /** Bounds come from the loaded column; `StreamValidator` rejects it. */
+    | { type: "anomaly"; method: "iqr" | "zscore"; threshold?: number | null }
+    | { type: "dependson"; key_columns: string[] }
+    /** A type registered from Rust with `register_rule`. */
+    | { type: string; [param: string]: unknown };
+
+export interface ColumnRule {
+    column: string;
+    rules: RuleType[];
+    /** Indices into `rules` that are switched off. */
+    disabled?: number[];
+}
+
+export type HeaderJoin = { strategy: "join"; separator: string } | { strategy: "last" };
+
+export interface HeaderMatching {
+    ignore_case?: boolean;
+    trim?: boolean;
+    ignore_punctuation?: boolean;
+}
+
+export interface ValidationOptions {
+    max_errors?: number | null;
+    max_errors_per_row?: number | null;
+    instrument?: boolean;
+    unvalidated_columns?: "ignore" | "warn" | "error";
+    short_circuit?: boolean;
+    null_values?: string[];
+}
+
+/** `options_json` of `with_options`, `from_bytes` and `StreamValidator`. */
+export interface ProcessorOptions {
+    layout?: "rows" | "columns";
+    validation?: ValidationOptions;
+    header_rows?: number;
+    header_join?: HeaderJoin;
+    skip_footer_rows?: number;
+    footer_pattern?: string | null;
+    drop_empty_columns?: boolean;
+    /** Source header -> canonical name. */
+    column_mapping?: Record<string, string>;
+    header_matching?: HeaderMatching;
+    unknown_columns?: "ignore" | "warn" | "drop" | "reject";
+    lossy_utf8?: boolean;
+    allow_empty?: boolean;
+    limits?: Limits;
+}
+
+/** Caps on an input; see `CsvProcessorBuilder.limits`. */
+export interface Limits {
+    max_bytes?: number | null;
+    max_rows?: number | null;
+    max_columns?: number | null;
+    max_cell_bytes?: number | null;
+}
+
+/** The optional `options_json` of the export methods. */
+export interface ExportOptions {
+    format?: "csv" | "jsonl";
+    delimiter?: string;
+    quoting?: "minimal" | "always";
+    line_ending?: "lf" | "crlf";
+    escape_formulas?: boolean;
+    columns?: string[] | null;
+    invalid_columns?: string[] | null;
+    error_format?: "reason" | "columns" | "detail";
+    include_review?: boolean;
+    null_value?: string | null;
+}
+
+/** `find_rows` query. */
+export type RowQuery = {
+    column?: string | null;
+    ignore_case?: boolean;
+} & (
+    | { op: "equals" | "not_equals" | "contains"; value: string }
+    | { op: "regex"; pattern: string }
+    | { op: "empty" | "not_empty" }
+    | { op: "gt" | "gte" | "lt" | "lte"; value: number }
+);
+
+export type ErrorCode =
+    | "REQUIRED" | "MIN_VALUE" | "MAX_VALUE" | "NOT_A_NUMBER" | "NOT_A_PERCENTAGE" | "INVALID_EMAIL"
+    | "PATTERN_MISMATCH" | "INVALID_OPTION" | "INVALID_COORDINATE" | "COORDINATE_OUT_OF_RANGE"
+    | "INVALID_COUNTRY_CODE" | "INVALID_LANGUAGE_TAG" | "UNKNOWN_TIME_ZONE" | "INVALID_DATE"
+    | "DATE_NOT_PAST" | "DATE_NOT_FUTURE" | "DATE_TOO_EARLY" | "DATE_TOO_LATE" | "INVALID_DURATION"
+    | "INVALID_VERSION" | "INVALID_HEX_COLOR" | "INVALID_MAC_ADDRESS" | "INVALID_BASE64"
+    | "INVALID_HOSTNAME" | "INVALID_PATH" | "PATH_NOT_ABSOLUTE" | "EXTENSION_NOT_ALLOWED"
+    | "INVALID_POSTAL_CODE" | "INVALID_NATIONAL_ID" | "STATISTICAL_OUTLIER" | "DEPENDENCY_CONFLICT"
+    | "UNVALIDATED_COLUMN" | "ENCODING" | "TYPE_MISMATCH" | "INVALID";
+
+export interface RuleTiming {
+    column: string;
+    rule_index: number;
+    rule: string;
+    millis: number;
+    errors: number;
+}
+
+export interface EncodingIssue {
+    row: number;
+    row_number: number;
+    column: string;
+}
+
+export interface ErrorSummary {
+    /** column -> error type -> count */
+    stats: Record<string, Record<string, number>>;
+    /** column -> error type -> first failing value */
+    examples: Record<string, Record<string, string>>;
+    /** Violations: one per failed rule per cell. */
+    total_errors: number;
+    /** Cells and rows with at least one error. */
+    invalid_cells: number;
+    invalid_rows: number;
+    truncated: boolean;
+    warnings?: string[];
+    encoding_issues?: EncodingIssue[];
+    /** Row numbers over `max_errors_per_row`. */
+    malformed_rows?: number[];
+    timings?: RuleTiming[];
+    row_count: number;
+    /** column -> `stats` as rates, for columns with errors */
+    rates?: Record<string, ErrorRates>;
+}
+
+/** A column's errors over its non-empty cells and over all rows. */
+export interface ErrorRates {
+    non_empty_cells: number;
+    of_non_empty: number;
+    of_rows: number;
+    /** error type -> rates of that error alone */
+    by_type: Record<string, { of_non_empty: number; of_rows: number }>;
+}
+
+/** `thresholds_json` of `evaluate`; omitted limits aren't checked. */
+export interface Thresholds {
+    max_invalid_rows?: number | null;
+    max_invalid_fraction?: number | null;
+    max_errors?: number | null;
+    columns?: Record<string, number>;
+    no_structural_errors?: boolean;
+}
+
+export interface ThresholdCheck {
+    check: "max_invalid_rows" | "max_invalid_fraction" | "max_errors" | "columns" | "no_structural_errors";
+    column?: string;
+    limit: number;
+    actual: number;
+    passed: boolean;
+}
+
+export interface Verdict {
+    accepted: boolean;
+    row_count: number;
+    invalid_rows: number;
+    total_errors: number;
+    checks: ThresholdCheck[];
+    structural_errors?: string[];
+}
+
+export interface Metrics {
+    row_count: number;
+    column_count: number;
+    rule_count: number;
+    record_bytes: number;
+    cache_bytes: number;
+}
+
+export interface SplitExport {
+    valid: string;
+    invalid: string;
+    /** Only with `error_format: "detail"`. */
+    errors?: string;
+}
+
+export interface SplitExportBytes {
+    valid: Uint8Array;
+    invalid: Uint8Array;
+    errors?: Uint8Array;
+}
+
+export interface FixResult {
+    column: string;
+    rows_changed: number;
+    total_errors: number;
+}
+
+export interface RowError {
+    column: string;
+    rule: string;
+    code: ErrorCode;
+    error: string;
+}
+
+export type ReviewStatus = "reviewed" | "accepted" | "follow_up";
+
+export interface Annotation {
+    column?: string;
+    status: ReviewStatus;
+    note?: string;
+}
+
+export interface AnnotationEntry extends Annotation {
+    row: number;
+    row_number: number;
+}
+
+/** A record keyed by header, as `to_json` and the page methods return it. */
+export interface JsonRecord {
+    [column: string]: string | RowError[] | Annotation[] | undefined;
+    _errors?: RowError[];
+    _review?: Annotation[];
+}
+
+export interface ValueCount {
+    value: string;
+    count: number;
+}
+
+export interface DuplicateGroup {
+    /** The key columns' values. */
+    key: string[];
+    count: number;
+    rows: number[];
+    row_numbers: number[];
+}
+
+export interface DependencyConflict {
+    /** The dependent column the rows disagree on. */
+    column: string;
+    key: string[];
+    values: { value: string; rows: number[]; row_numbers: number[] }[];
+}
+
+export interface OutlierReport {
+    column: string;
+    method: "iqr" | "zscore";
+    threshold: number;
+    numeric_cells: number;
+    /** Missing when the column has no numbers. */
+    lower?: number | null;
+    upper?: number | null;
+    outliers: { row: number; row_number: number; value: string }[];
+}
+
+export interface BenchmarkResult {
+    rows: number;
+    bytes: number;
+    parse_ms: number;
+    validate_ms: number;
+    rows_per_second: number;
+    mb_per_second: number;
+}
+
+export interface PiiFinding {
+    column: string;
+    kind: "credit_card" | "national_id" | "email" | "phone";
+    matches: number;
+    cells: number;
+    fraction: number;
+}
+
+export interface RowRef {
+    key: string;
+    row: number;
+    row_number: number;
+}
+
+export interface CompareReport {
+    key_column: string;
+    added: RowRef[];
+    removed: { key: string; row_number: number; values: Record<string, string> }[];
+    changed: (RowRef & { cells: { column: string; previous: string; current: string }[] })[];
+    unchanged: number;
+    columns_added: string[];
+    columns_removed: string[];
+    duplicate_keys: string[];
+}
+
+export interface StreamRow {
+    row_number: number;
+    values: string[];
+    errors: RowError[];
+}
+
+/** Errors in the rows completed by one `push`. */
+export interface ChunkStats {
+    chunk: number;
+    first_row: number;
+    last_row: number;
+    invalid_rows: number;
+    errors: number;
+    columns: Record<string, number>;
+}
+
+export interface StreamSummary extends ErrorSummary {
+    chunks: ChunkStats[];
+}
+
+export interface StreamFinish {
+    rows: StreamRow[];
+    summary: StreamSummary;
+}
+
+/** What load and export methods throw: an `Error` with these fields set. */
+export interface ValidatorError extends Error {
+    kind: "rules" | "options" | "header" | "parse" | "empty" | "data" | "export" | "cancelled";
+    byte_offset?: number;
+    line?: number;
+    record?: number;
+    snippet?: string;
+}
+"#;
@@ -0,0 +1,586 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::compare::CompareReport;
+use crate::encoding::decode;
+use crate::engine::error_code;
+use crate::error::{ErrorKind, ValidatorError};
+use crate::export::{ExportOptions, SplitExport, SplitOutput};
+use crate::mask::MaskStrategy;
+use crate::merge::{merge, MergeMode};
+use crate::outliers::{OutlierMethod, OutlierReport};
+use crate::pii::PiiFinding;
+use crate::progress::{CancellationToken, Progress, ProgressFn};
+use crate::query::{DependencyConflict, DuplicateGroup, RowQuery, ValueCount};
+use crate::report::DatasetAudit;
+use crate::review::{AnnotationEntry, ReviewStatus};
+use crate::sample::SampleRows;
+use crate::sort::{SortDirection, SortType};
+use crate::sql::SqlDialect;
+use crate::verdict::{Thresholds, Verdict};
+use crate::{parse_options, parse_rules, ColumnRule, Dataset, ErrorSummary, FixResult, Metrics, ProcessorOptions};
+
+// --- Native API ---
+//
+// The engine without JS types, for Rust callers such as a server or the CLI.
+// Loading, validation, reports, edits and exports share their code with
+// `CsvProcessor`; results are plain serde structs and failures are
+// `ValidatorError`s.
+
+/// A loaded file and its compiled rules.
+pub struct Validator {
+    data: Dataset,
+    rules: Vec<ColumnRule>,
+    options: ProcessorOptions,
+    on_progress: Option<Box<ProgressFn>>,
+    cancel_token: CancellationToken,
+}
+
+/// One failed rule of a row, as `Validator::row_errors` lists it.
+#[derive(Serialize, Clone, Debug)]
+pub struct RowError {
+    pub column: String,
+    /// The rule's `type` tag, e.g. "email".
+    pub rule: &'static str,
+    /// Stable code, e.g. "INVALID_EMAIL".
+    pub code: &'static str,
+    pub error: &'static str,
+}
+
+/// The valid rows as the caller's own type, from
+/// `Validator::deserialize_valid_rows`.
+pub struct TypedRows<T> {
+    pub rows: Vec<T>,
+    /// 1-based row number of each of `rows`, as loaded.
+    pub row_numbers: Vec<usize>,
+    /// `Validator::summary`, plus a "Type Mismatch" error for every cell
+    /// that passed the rules but not deserialization.
+    pub summary: ErrorSummary,
+}
+
+impl Validator {
+    pub fn new(csv_data: &str, rules: &[ColumnRule], options: ProcessorOptions) -> Result<Validator, ValidatorError> {
+        let data = Dataset::load(csv_data, rules, &options, &Progress::new(None, "parse"))?;
+        Ok(Validator::from_dataset(data, rules.to_vec(), options))
+    }
+
+    /// Like `new`, with the rules and options as the JSON `CsvProcessor`
+    /// takes (no options meaning the defaults).
+    pub fn from_json(csv_data: &str, rules_json: &str, options_json: Option<&str>) -> Result<Validator, ValidatorError> {
+        let options = options_json.map_or_else(|| Ok(ProcessorOptions::default()), parse_options)?;
+        Validator::new(csv_data, &parse_rules(rules_json)?, options)
+    }
+
+    /// Like `new`, for raw file bytes. They must be UTF-8 unless
+    /// `options.lossy_utf8` is set.
+    pub fn from_bytes(bytes: &[u8], rules: &[ColumnRule], options: ProcessorOptions) -> Result<Validator, ValidatorError> {
+        options.limits.check_bytes(bytes.len())?;
+        let (csv_data, replaced) = decode(bytes, options.lossy_utf8)
+            .map_err(|e| e.with_snippet(&String::from_utf8_lossy(bytes)))?;
+        let mut validator = Validator::new(&csv_data, rules, options)?;
+        validator.data.lossy_decoded = replaced;
+        Ok(validator)
+    }
+
+    fn from_dataset(data: Dataset, rules: Vec<ColumnRule>, options: ProcessorOptions) -> Validator {
+        Validator { data, rules, options, on_progress: None, cancel_token: CancellationToken::new() }
+    }
+
+    /// Sets (or clears) the `on_progress(percent, phase)` callback of later
+    /// long operations. It is called every few thousand rows and once at the
+    /// end of each phase (e.g. "validate", "export").
+    pub fn set_progress_callback(&mut self, on_progress: Option<Box<ProgressFn>>) {
+        self.on_progress = on_progress;
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.data.headers
+    }
+
+    pub fn rules(&self) -> &[ColumnRule] {
+        &self.rules
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.data.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.records.is_empty()
+    }
+
+    /// Row `row`'s cells with their current values; `None` when out of range.
+    pub fn row(&self, row: usize) -> Option<Vec<&str>> {
+        (row < self.data.records.len()).then(|| self.data.records.row(row).iter().collect())
+    }
+
+    /// Footer lines set aside at load (see `ProcessorOptions::skip_footer_rows`).
+    pub fn footer_rows(&self) -> &[Vec<String>] {
+        &self.data.footer
+    }
+
+    /// Shape and memory footprint, as `get_metrics` reports them. There is no
+    /// revalidation cache, so `cache_bytes` is 0.
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            row_count: self.data.records.len(),
+            column_count: self.data.headers.len(),
+            rule_count: self.rules.iter().map(|r| r.rules.len()).sum(),
+            record_bytes: self.data.records.byte_size(),
+            cache_bytes: 0,
+        }
+    }
+
+    /// Stops a running `summary`, report or export from another thread.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel_token.clone()
+    }
+
+    /// Error counts per column and error type, as `get_error_summary`
+    /// returns them.
+    pub fn summary(&self) -> Result<ErrorSummary, ValidatorError> {
+        self.run("validate", |data, progress| Ok(data.error_summary(&self.options, progress, &self.cancel_token)?))
+    }
+
+    /// Like `summary` for the rules of `columns` alone, as
+    /// `get_error_summary_for` returns it.
+    pub fn summary_for(&self, columns: &[&str]) -> Result<ErrorSummary, ValidatorError> {
+        let cols = self.columns(columns)?;
+        self.run("validate", |data, progress| Ok(data.summarize_columns(&cols, &self.options.validation, progress)?))
+    }
+
+    /// Accepts or rejects the file against `thresholds`, as `evaluate` does.
+    pub fn evaluate(&self, thresholds: &Thresholds) -> Result<Verdict, ValidatorError> {
+        self.run("validate", |data, progress| data.evaluate(thresholds, &self.options, progress))
+    }
+
+    /// Every failed rule of row `row`; empty when it is valid or out of range.
+    pub fn row_errors(&self, row: usize) -> Vec<RowError> {
+        if row >= self.data.records.len() {
+            return Vec::new();
+        }
+        self.data.violations(row).into_iter()
+            .map(|v| RowError {
+                column: self.data.headers[v.column].clone(),
+                rule: self.data.rule_set.rules_for(v.column)[v.rule].name(),
+                code: error_code(v.error_type),
+                error: v.error_type,
+            })
+            .collect()
+    }
+
+    /// Number of rows failing at least one rule.
+    pub fn invalid_row_count(&self) -> usize {
+        (0..self.data.records.len()).filter(|&idx| !self.data.violations(idx).is_empty()).count()
+    }
+
+    /// Every row that passes the rules, deserialized into `T` by header name
+    /// as the `csv` crate does it (so `Option` fields take empty cells as
+    /// `None`). Rows where a field fails to deserialize are left out and
+    /// counted in the summary. Fails if `T` needs a column the file lacks.
+    pub fn deserialize_valid_rows<T: DeserializeOwned>(&self) -> Result<TypedRows<T>, ValidatorError> {
+        let mut summary = self.summary()?;
+        let progress = self.progress("deserialize");
+        let headers = csv::StringRecord::from(self.data.headers.clone());
+        let total = self.data.records.len();
+        let mut rows = Vec::new();
+        let mut row_numbers = Vec::new();
+        let mut record = csv::StringRecord::new();
+        for idx in 0..total {
+            progress.row(idx, total)?;
+            let row = self.data.records.row(idx);
+            if self.data.rule_set.validate_record(&row).next().is_some() {
+                continue;
+            }
+            record.clear();
+            row.iter().for_each(|value| record.push_field(value));
+            match record.deserialize::<T>(Some(&headers)) {
+                Ok(value) => {
+                    rows.push(value);
+                    row_numbers.push(self.data.row_number(idx));
+                }
+                Err(e) => match e.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => match err.field() {
+                        Some(col) => {
+                            if self.options.validation.max_errors.is_some_and(|max| summary.total_errors >= max) {
+                                summary.truncated = true;
+                                continue;
+                            }
+                            let col = col as usize;
+                            summary.record(&self.data.headers[col], "Type Mismatch", row.get(col));
+                            // The row passed the rules, so only an "Encoding" error
+                            // can have counted the cell or the row already
+                            let encoding = |value: &str| self.data.lossy_decoded && value.contains(char::REPLACEMENT_CHARACTER);
+                            summary.invalid_cells += usize::from(!encoding(row.get(col)));
+                            summary.invalid_rows += usize::from(!row.iter().any(encoding));
+                        }
+                        None => return Err(ValidatorError::new(ErrorKind::Data, format!("Deserialize Error: {}", err))),
+                    },
+                    _ => return Err(ValidatorError::new(ErrorKind::Data, format!("Deserialize Error: {}", e))),
+                },
+            }
+        }
+        summary.set_rates(total, |header| self.data.non_empty_cells(header));
+        progress.finish();
+        Ok(TypedRows { rows, row_numbers, summary })
+    }
+
+    /// A standalone HTML page of the results, as `generate_html_report`
+    /// writes it.
+    pub fn html_report(&self) -> Result<String, ValidatorError> {
+        self.run("validate", |data, progress| Ok(data.html_report(progress)?))
+    }
+
+    /// The versioned JSON audit report `generate_report` writes.
+    pub fn audit_report(&self) -> Result<String, ValidatorError> {
+        self.run("validate", |data, progress| data.audit_report(&self.rules, progress))
+    }
+
+    /// The per-dataset part of the audit report, for `ValidationSession`.
+    pub(crate) fn audit(&self) -> Result<DatasetAudit<'_>, ValidatorError> {
+        self.run("validate", |data, progress| data.audit(&self.rules, progress))
+    }
+
+    /// Compares the rows with an earlier version of the file, matching rows
+    /// by `key_column`, as `compare_with` does.
+    pub fn compare_with<'a>(&'a self, previous_csv: &str, key_column: &'a str) -> Result<CompareReport<'a>, ValidatorError> {
+        self.run("compare", |data, progress| data.compare(previous_csv, key_column, &self.options, progress))
+    }
+
+    /// Indices of the rows matching `query`, in order.
+    pub fn find_rows(&self, query: &RowQuery) -> Result<Vec<u32>, ValidatorError> {
+        self.run("search", |data, progress| data.find_rows(query, progress))
+    }
+
+    /// Groups of rows with the same values in `key_columns` (the whole row
+    /// when empty), as `find_duplicates` returns them.
+    pub fn find_duplicates(&self, key_columns: &[&str]) -> Result<Vec<DuplicateGroup<'_>>, ValidatorError> {
+        let cols = self.columns(key_columns)?;
+        self.run("search", |data, progress| data.find_duplicates(&cols, progress))
+    }
+
+    /// Keys whose rows disagree on one of `dependent_columns`, as
+    /// `find_dependency_conflicts` returns them.
+    pub fn find_dependency_conflicts(&self, key_columns: &[&str], dependent_columns: &[&str]) -> Result<Vec<DependencyConflict<'_>>, ValidatorError> {
+        let (key_cols, dependent_cols) = (self.columns(key_columns)?, self.columns(dependent_columns)?);
+        if key_cols.is_empty() {
+            return Err(ValidatorError::new(ErrorKind::Options, "Invalid Key: at least one key column is needed"));
+        }
+        self.run("search", |data, progress| data.find_dependency_conflicts(&key_cols, &dependent_cols, progress))
+    }
+
+    /// The `n` most common values of `column`, most frequent first.
+    pub fn top_values(&self, column: &str, n: usize) -> Result<Vec<ValueCount<'_>>, ValidatorError> {
+        let col = self.data.column_index(column)?;
+        self.run("profile", |data, progress| data.top_values(col, n, progress))
+    }
+
+    /// Numeric values of `column` far from the rest, as `detect_outliers`
+    /// finds them; `threshold` defaults to the method's.
+    pub fn detect_outliers(&self, column: &str, method: OutlierMethod, threshold: Option<f64>) -> Result<OutlierReport<'_>, ValidatorError> {
+        let col = self.data.column_index(column)?;
+        self.run("profile", |data, progress| data.outliers(col, method, threshold, progress))
+    }
+
+    /// Columns whose cells look like personal data, as `detect_pii` reports them.
+    pub fn detect_pii(&self) -> Result<Vec<PiiFinding<'_>>, ValidatorError> {
+        self.run("scan", |data, progress| Ok(data.detect_pii(progress)?))
+    }
+
+    /// The versioned JSON privacy report `generate_privacy_report` writes.
+    pub fn privacy_report(&self) -> Result<String, ValidatorError> {
+        self.run("scan", |data, progress| data.privacy_report(progress))
+    }
+
+    /// Sorts the records by `column`, as `sort_by` does. Rows keep their
+    /// original row numbers.
+    pub fn sort_by(&mut self, column: &str, direction: SortDirection, sort_type: SortType) -> Result<(), ValidatorError> {
+        let col = self.data.column_index(column)?;
+        self.cancel_token.reset();
+        let progress = Progress::with_fn(self.on_progress.as_deref(), "sort").cancellable(&self.cancel_token);
+        self.data.sort(col, direction, sort_type, &progress)?;
+        progress.finish();
+        Ok(())
+    }
+
+    /// Masks the non-empty cells of `column` in place, as `mask_column` and
+    /// `hash_column` do. Returns the number of cells changed.
+    pub fn mask_column(&mut self, column: &str, strategy: &MaskStrategy) -> Result<usize, ValidatorError> {
+        let col = self.data.column_index(column)?;
+        self.cancel_token.reset();
+        let progress = Progress::with_fn(self.on_progress.as_deref(), "mask").cancellable(&self.cancel_token);
+        let changed = self.data.mask_column(col, strategy, &progress)?;
+        progress.finish();
+        if !changed.is_empty() {
+            self.data.refit_edited(col);
+        }
+        Ok(changed.len())
+    }
+
+    /// Replaces every `target` cell of `column` with `replacement`, as
+    /// `apply_bulk_fix_detailed` does.
+    pub fn apply_bulk_fix(&mut self, column: &str, target: &str, replacement: &str) -> Result<FixResult, ValidatorError> {
+        let mut rows_changed = 0;
+        if let Some(col) = self.data.headers.iter().position(|h| h == column) {
+            let changed = self.data.records.replace_all(col, target, replacement)
+                .map_err(|e| ValidatorError::new(ErrorKind::Data, e))?;
+            if !changed.is_empty() {
+                self.data.refit_edited(col);
+            }
+            rows_changed = changed.len();
+        }
+        let total_errors = (0..self.data.records.len())
+            .map(|idx| self.data.rule_set.validate_record(&self.data.records.row(idx)).count())
+            .sum();
+        Ok(FixResult { column: column.to_string(), rows_changed, total_errors })
+    }
+
+    /// The original (load order) index of each record in its current position.
+    pub fn original_indices(&self) -> Vec<u32> {
+        if self.data.origins.is_empty() {
+            (0..self.data.records.len() as u32).collect()
+        } else {
+            self.data.origins.clone()
+        }
+    }
+
+    /// Marks row `row`, or its cell in `column`, with a review status and an
+    /// optional note, as `annotate_row` and `annotate_cell` do.
+    pub fn annotate(&mut self, row: usize, column: Option<&str>, status: ReviewStatus, note: Option<String>) -> Result<(), ValidatorError> {
+        self.data.annotate(row, column.map(str::to_string), status, note)
+    }
+
+    /// Removes the annotation of row `row`, or of its cell in `column`.
+    /// Returns whether there was one.
+    pub fn clear_annotation(&mut self, row: usize, column: Option<&str>) -> Result<bool, ValidatorError> {
+        self.data.clear_annotation(row, column)
+    }
+
+    /// Every annotation in row order, as `get_annotations` lists them.
+    pub fn annotations(&self) -> Vec<AnnotationEntry<'_>> {
+        self.data.annotation_entries()
+    }
+
+    /// This validator's rows followed by `other`'s, as `CsvProcessor::merge`
+    /// combines them, with this validator's options.
+    pub fn merge(&self, other: &Validator, mode: MergeMode) -> Result<Validator, ValidatorError> {
+        let (data, rules) = merge((&self.data, &self.rules), (&other.data, &other.rules), &self.options, mode)?;
+        Ok(Validator::from_dataset(data, rules, self.options.clone()))
+    }
+
+    /// Every row, in the format `options` describes.
+    pub fn export_all(&self, options: &ExportOptions) -> Result<Vec<u8>, ValidatorError> {
+        self.run("export", |data, progress| data.write_all(options, progress))
+    }
+
+    /// The valid rows, the invalid rows with their errors, and with
+    /// `ErrorFormat::Detail` the error lines, as `generate_split_export`
+    /// writes them.
+    pub fn export_split(&self, options: &ExportOptions) -> Result<SplitOutput, ValidatorError> {
+        self.run("export", |data, progress| {
+            let mut export = SplitExport::new(data, options)?;
+            data.write_split(&mut export, 0..data.records.len(), progress)?;
+            export.into_output()
+        })
+    }
+
+    /// A reproducible random sample of up to `n` records, as `export_sample`
+    /// writes it.
+    pub fn export_sample(&self, n: usize, seed: u32, rows: SampleRows, options: &ExportOptions) -> Result<Vec<u8>, ValidatorError> {
+        let sample = self.run("sample", |data, progress| Ok(data.sample_rows(n, seed, rows, progress)?))?;
+        self.run("export", |data, progress| data.write_rows(options, sample.into_iter(), progress))
+    }
+
+    /// Up to `per_value` records for every distinct value of `column`, as
+    /// `export_stratified_sample` writes them.
+    pub fn export_stratified_sample(&self, column: &str, per_value: usize, seed: u32, rows: SampleRows, options: &ExportOptions) -> Result<Vec<u8>, ValidatorError> {
+        let col = self.data.column_index(column)?;
+        let sample = self.run("sample", |data, progress| Ok(data.stratified_sample_rows(col, per_value, seed, rows, progress)?))?;
+        self.run("export", |data, progress| data.write_rows(options, sample.into_iter(), progress))
+    }
+
+    /// Every record as an `.xlsx` workbook, as `to_xlsx` writes it.
+    pub fn export_xlsx(&self, options: &ExportOptions) -> Result<Vec<u8>, ValidatorError> {
+        self.run("export", |data, progress| data.write_xlsx(options, progress))
+    }
+
+    /// The valid rows as SQL loading them into `table`, as `to_sql` writes it.
+    pub fn export_sql(&self, table: &str, dialect: SqlDialect) -> Result<String, ValidatorError> {
+        self.run("export", |data, progress| data.write_sql(table, dialect, progress))
+    }
+
+    /// The valid rows as an Arrow IPC stream, as `to_arrow` writes it.
+    pub fn export_arrow(&self) -> Result<Vec<u8>, ValidatorError> {
+        self.run("export", |data, progress| data.write_arrow(progress))
+    }
+
+    /// The valid rows as a Parquet file, as `to_parquet` writes it.
+    pub fn export_parquet(&self) -> Result<Vec<u8>, ValidatorError> {
+        self.run("export", |data, progress| data.write_parquet(progress))
+    }
+
+    /// Every record as a JSON array of objects keyed by header, as `to_json`
+    /// returns it.
+    pub fn export_json(&self, with_errors: bool) -> Result<Vec<u8>, ValidatorError> {
+        let page = self.data.json_page(0..self.data.records.len(), None, with_errors)?;
+        serde_json::to_vec(&page).map_err(|e| ValidatorError::new(ErrorKind::Export, e.to_string()))
+    }
+
+    /// One line per violation, as `generate_error_detail` writes them.
+    pub fn export_errors(&self, options: &ExportOptions) -> Result<Vec<u8>, ValidatorError> {
+        self.run("export", |data, progress| data.write_error_details(options, progress))
+    }
+
+    /// Positions of the headers `columns`.
+    fn columns(&self, columns: &[&str]) -> Result<Vec<usize>, ValidatorError> {
+        columns.iter().map(|column| self.data.column_index(column)).collect()
+    }
+
+    /// Runs `operation` as phase `phase`, reporting its end.
+    fn run<'a, T>(&'a self, phase: &'static str, operation: impl FnOnce(&'a Dataset, &Progress) -> Result<T, ValidatorError>) -> Result<T, ValidatorError> {
+        let progress = self.progress(phase);
+        let result = operation(&self.data, &progress)?;
+        progress.finish();
+        Ok(result)
+    }
+
+    /// Progress for a new operation; clears any cancel left over from the last one.
+    fn progress(&self, phase: &'static str) -> Progress<'_> {
+        self.cancel_token.reset();
+        Progress::with_fn(self.on_progress.as_deref(), phase).cancellable(&self.cancel_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Item {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn type_mismatches_count_as_invalid_cells_and_rows() {
+        let csv = "id,name\none,a\ntwo,b\n3,c\n";
+        let rules = r#"[{"column": "id", "rules": [{"type": "notempty"}]}, {"column": "name", "rules": [{"type": "notempty"}]}]"#;
+        let validator = Validator::from_json(csv, rules, None).unwrap();
+        let typed = validator.deserialize_valid_rows::<Item>().unwrap();
+
+        assert_eq!(typed.row_numbers, vec![3]);
+        let summary = typed.summary;
+        assert_eq!(summary.stats["id"]["Type Mismatch"], 2);
+        assert_eq!(summary.total_errors, 2);
+        assert_eq!(summary.invalid_cells, 2);
+        assert_eq!(summary.invalid_rows, 2);
+        let rates = &summary.rates["id"];
+        assert_eq!(rates.non_empty_cells, 3);
+        assert_eq!(rates.by_type["Type Mismatch"].of_rows, 2.0 / 3.0);
+    }
+
+    #[test]
+    fn type_mismatches_respect_max_errors() {
+        let csv = "id,name\none,a\ntwo,b\nthree,c\n";
+        let rules = r#"[{"column": "id", "rules": [{"type": "notempty"}]}]"#;
+        let validator = Validator::from_json(csv, rules, Some(r#"{"validation": {"max_errors": 1}}"#)).unwrap();
+        let summary = validator.deserialize_valid_rows::<Item>().unwrap().summary;
+
+        assert_eq!(summary.total_errors, 1);
+        assert_eq!(summary.invalid_rows, 1);
+        assert!(summary.truncated);
+    }
+
+    #[test]
+    fn export_arrow_writes_the_valid_rows_typed() {
+        let csv = "amount,when,note\n1.5,2024-02-03,kept\nx,2024-02-04,dropped\n,2024-02-05,\n";
+        let rules = r#"[{"column": "amount", "rules": [{"type": "number", "allow_empty": true}]}, {"column": "when", "rules": [{"type": "date"}]}]"#;
+        let bytes = Validator::from_json(csv, rules, None).unwrap().export_arrow().unwrap();
+
+        let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+        assert_eq!(bytes[..4], u32::MAX.to_le_bytes());
+        assert_eq!(bytes[bytes.len() - 8..], [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+        assert!(contains(&1.5f64.to_le_bytes()));
+        // 2024-02-03 and 2024-02-05 as days since the epoch
+        assert!(contains(&[19756i32.to_le_bytes(), 19758i32.to_le_bytes()].concat()));
+        assert!(contains(b"kept"));
+        assert!(!contains(b"dropped"));
+    }
+
+    #[test]
+    fn progress_reaches_the_callback_and_cancel_stops_the_next_phase() {
+        use std::sync::{Arc, Mutex};
+
+        let mut validator = Validator::from_json("a\n1\n", "[]", None).unwrap();
+        let phases = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&phases);
+        let token = validator.cancellation_token();
+        validator.set_progress_callback(Some(Box::new(move |percent, phase| {
+            seen.lock().unwrap().push((percent, phase.to_string()));
+            token.cancel();
+        })));
+
+        validator.summary().unwrap();
+        assert_eq!(*phases.lock().unwrap(), vec![(100.0, "validate".to_string())]);
+        // Each operation clears the last one's cancel
+        validator.summary().unwrap();
+    }
+
+    #[test]
+    fn edits_revalidate_and_keep_row_numbers() {
+        let csv = "id,email\n3,c@x.com\n1,bad\n2,bad\n";
+        let rules = r#"[{"column": "email", "rules": [{"type": "email"}]}]"#;
+        let mut validator = Validator::from_json(csv, rules, None).unwrap();
+
+        validator.sort_by("id", SortDirection::Asc, SortType::Number).unwrap();
+        assert_eq!(validator.row(0), Some(vec!["1", "bad"]));
+        assert_eq!(validator.original_indices(), vec![1, 2, 0]);
+
+        let fix = validator.apply_bulk_fix("email", "bad", "ok@x.com").unwrap();
+        assert_eq!((fix.rows_changed, fix.total_errors), (2, 0));
+        assert_eq!(validator.apply_bulk_fix("nope", "a", "b").unwrap().rows_changed, 0);
+
+        assert_eq!(validator.mask_column("email", &MaskStrategy::Token("***".to_string())).unwrap(), 3);
+        assert_eq!(validator.summary().unwrap().stats["email"]["Invalid Email"], 3);
+        assert_eq!(validator.sort_by("nope", SortDirection::Asc, SortType::Text).unwrap_err().kind, ErrorKind::Options);
+    }
+
+    #[test]
+    fn merge_combines_rows_and_rules() {
+        let left = Validator::from_json("id,name\n1,a\n", r#"[{"column": "name", "rules": [{"type": "notempty"}]}]"#, None).unwrap();
+        let right = Validator::from_json("name,id\n,2\n", "[]", None).unwrap();
+
+        let merged = left.merge(&right, MergeMode::Strict).unwrap();
+        assert_eq!(merged.row(1), Some(vec!["2", ""]));
+        assert_eq!(merged.summary().unwrap().total_errors, 1);
+
+        let other = Validator::from_json("id,extra\n3,x\n", "[]", None).unwrap();
+        assert_eq!(left.merge(&other, MergeMode::Strict).err().map(|e| e.kind), Some(ErrorKind::Header));
+        assert_eq!(left.merge(&other, MergeMode::Union).unwrap().headers(), ["id", "name", "extra"]);
+    }
+
+    #[test]
+    fn exports_cover_sql_xlsx_json_and_samples() {
+        let csv = "id,name\n1,O'Neil\n2,\n3,c\n";
+        let rules = r#"[{"column": "name", "rules": [{"type": "notempty"}]}]"#;
+        let validator = Validator::from_json(csv, rules, None).unwrap();
+
+        let sql = validator.export_sql("people", SqlDialect::Postgres).unwrap();
+        assert!(sql.contains("'O''Neil'"));
+        assert!(!sql.contains("'2'"));
+        assert_eq!(validator.export_sql("", SqlDialect::Postgres).unwrap_err().kind, ErrorKind::Export);
+
+        assert_eq!(validator.export_xlsx(&ExportOptions::default()).unwrap()[..2], *b"PK");
+
+        let json: serde_json::Value = serde_json::from_slice(&validator.export_json(false).unwrap()).unwrap();
+        assert_eq!(json[0]["name"], "O'Neil");
+
+        let options = ExportOptions::default();
+        let sample = validator.export_sample(5, 7, SampleRows::Invalid, &options).unwrap();
+        assert_eq!(String::from_utf8(sample).unwrap(), "id,name\n2,\n");
+        let strata = validator.export_stratified_sample("id", 1, 7, SampleRows::Valid, &options).unwrap();
+        assert_eq!(String::from_utf8(strata).unwrap().lines().count(), 3);
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::error::{ErrorKind, ValidatorError};
+use crate::progress::Progress;
+use crate::{Dataset, ErrorSummary, ProcessorOptions};
+
+// --- Verdict ---
+//
+// One accept/reject decision from the validation results, so every consumer
+// applies the same limits instead of re-deriving them from the summary.
+
+/// The `thresholds_json` of `evaluate`. Limits left out aren't checked, so
+/// `{}` accepts anything.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)] // A misspelt limit must not pass silently
+pub struct Thresholds {
+    pub max_invalid_rows: Option<usize>,
+    /// Share of rows, e.g. `0.005` for 0.5%.
+    pub max_invalid_fraction: Option<f64>,
+    pub max_errors: Option<usize>,
+    /// Column -> most errors allowed in it, e.g. `{"id": 0}`.
+    pub columns: BTreeMap<String, usize>,
+    /// Reject files with problems beyond single cells: no header or
+    /// records, columns flagged by the `unvalidated_columns` or
+    /// `unknown_columns` policies, or cells that held invalid UTF-8.
+    pub no_structural_errors: bool,
+}
+
+impl Thresholds {
+    pub fn from_json(thresholds_json: &str) -> Result<Thresholds, ValidatorError> {
+        let thresholds: Thresholds = serde_json::from_str(thresholds_json)
+            .map_err(|e| ValidatorError::new(ErrorKind::Options, format!("Invalid Thresholds JSON: {}", e)))?;
+        if thresholds.max_invalid_fraction.is_some_and(|f| !(0.0..=1.0).contains(&f)) {
+            return Err(ValidatorError::new(ErrorKind::Options, "Invalid Thresholds: max_invalid_fraction must be between 0 and 1"));
+        }
+        Ok(thresholds)
+    }
+
+    /// Checks a finished summary of the file with these `headers`, one
+    /// holding the file-level `warnings` and `encoding_issues` as
+    /// `Validator::summary` returns it. A summary cut short by `max_errors`
+    /// only counts the errors it got to.
+    pub fn verdict(&self, summary: &ErrorSummary, headers: &[String]) -> Result<Verdict, ValidatorError> {
+        for column in self.columns.keys() {
+            if !headers.contains(column) {
+                return Err(ValidatorError::new(ErrorKind::Options, format!("Unknown Column: {}", column)));
+            }
+        }
+        let row_count = summary.row_count;
+        let mut structural_errors = summary.warnings.clone();
+        structural_errors.extend(summary.encoding_issues.iter()
+            .map(|issue| format!("Invalid UTF-8 in row {}, column '{}'", issue.row_number, issue.column)));
+
+        let mut checks = Vec::new();
+        if let Some(max) = self.max_invalid_rows {
+            checks.push(ThresholdCheck::new("max_invalid_rows", max as f64, summary.invalid_rows as f64));
+        }
+        if let Some(max) = self.max_invalid_fraction {
+            let fraction = if row_count == 0 { 0.0 } else { summary.invalid_rows as f64 / row_count as f64 };
+            checks.push(ThresholdCheck::new("max_invalid_fraction", max, fraction));
+        }
+        if let Some(max) = self.max_errors {
+            checks.push(ThresholdCheck::new("max_errors", max as f64, summary.total_errors as f64));
+        }
+        for (column, &max) in &self.columns {
+            let errors: usize = summary.stats.get(column).map_or(0, |counts| counts.values().sum());
+            checks.push(ThresholdCheck { column: Some(column.clone()), ..ThresholdCheck::new("columns", max as f64, errors as f64) });
+        }
+        if self.no_structural_errors {
+            checks.push(ThresholdCheck::new("no_structural_errors", 0.0, structural_errors.len() as f64));
+        }
+
+        Ok(Verdict {
+            accepted: checks.iter().all(|check| check.passed),
+            row_count,
+            invalid_rows: summary.invalid_rows,
+            total_errors: summary.total_errors,
+            checks,
+            structural_errors,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Verdict {
+    pub accepted: bool,
+    pub row_count: usize,
+    pub invalid_rows: usize,
+    pub total_errors: usize,
+    /// Every configured limit, failed or not.
+    pub checks: Vec<ThresholdCheck>,
+    /// The structural problems found, whether or not they were checked.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub structural_errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ThresholdCheck {
+    /// The `Thresholds` field, e.g. `max_invalid_fraction`.
+    pub check: &'static str,
+    /// Set for `columns` checks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    pub limit: f64,
+    pub actual: f64,
+    pub passed: bool,
+}
+
+impl ThresholdCheck {
+    fn new(check: &'static str, limit: f64, actual: f64) -> ThresholdCheck {
+        ThresholdCheck { check, column: None, limit, actual, passed: actual <= limit }
+    }
+}
+
+impl Dataset {
+    pub(crate) fn evaluate(&self, thresholds: &Thresholds, options: &ProcessorOptions, progress: &Progress) -> Result<Verdict, ValidatorError> {
+        let mut summary = self.report_data(progress)?.summary;
+        self.report_file(&mut summary, options);
+        thresholds.verdict(&summary, &self.headers)
+    }
+}
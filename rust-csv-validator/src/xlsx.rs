@@ -0,0 +1,366 @@
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+// --- XLSX Output ---
+//
+// Just enough SpreadsheetML for a single-sheet review workbook: inline
+// strings, a bold header, a highlight fill and cell comments. Everything is
+// written as text so values come back exactly as they were in the CSV.
+
+/// Excel's hard row limit per sheet.
+pub const MAX_ROWS: usize = 1_048_576;
+
+/// Cell formats defined in `styles.xml`, by `cellXfs` index.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CellStyle {
+    Normal = 0,
+    Header = 1,
+    Highlight = 2,
+}
+
+/// One worksheet, built row by row and packaged by `finish`.
+pub struct SheetWriter {
+    rows: String,
+    // (zero-based column, one-based row, text)
+    comments: Vec<(usize, usize, String)>,
+    next_row: usize,
+}
+
+impl SheetWriter {
+    pub fn new() -> SheetWriter {
+        SheetWriter { rows: String::new(), comments: Vec::new(), next_row: 1 }
+    }
+
+    /// Appends a row of `(text, style, comment)` cells.
+    pub fn write_row<'a>(&mut self, cells: impl IntoIterator<Item = (&'a str, CellStyle, Option<String>)>) -> Result<(), String> {
+        if self.next_row > MAX_ROWS {
+            return Err(format!("XLSX sheets are limited to {} rows", MAX_ROWS));
+        }
+        let row = self.next_row;
+        self.next_row += 1;
+
+        let _ = write!(self.rows, "<row r=\"{}\">", row);
+        for (col, (text, style, comment)) in cells.into_iter().enumerate() {
+            let cell_ref = format!("{}{}", column_name(col), row);
+            let style_attr = if style == CellStyle::Normal { String::new() } else { format!(" s=\"{}\"", style as u8) };
+            if text.is_empty() {
+                // Still written when styled so an empty invalid cell is highlighted
+                if style != CellStyle::Normal || comment.is_some() {
+                    let _ = write!(self.rows, "<c r=\"{}\"{}/>", cell_ref, style_attr);
+                }
+            } else {
+                let space = if text.trim() != text { " xml:space=\"preserve\"" } else { "" };
+                let _ = write!(self.rows, "<c r=\"{}\"{} t=\"inlineStr\"><is><t{}>{}</t></is></c>", cell_ref, style_attr, space, escape_xml(text));
+            }
+            if let Some(comment) = comment {
+                self.comments.push((col, row, comment));
+            }
+        }
+        self.rows.push_str("</row>");
+        Ok(())
+    }
+
+    /// Packages the sheet into a complete `.xlsx` file.
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        let has_comments = !self.comments.is_empty();
+        let mut zip = ZipWriter::default();
+
+        zip.add("[Content_Types].xml", &content_types(has_comments))?;
+        zip.add("_rels/.rels", ROOT_RELS)?;
+        zip.add("xl/workbook.xml", WORKBOOK)?;
+        zip.add("xl/_rels/workbook.xml.rels", WORKBOOK_RELS)?;
+        zip.add("xl/styles.xml", STYLES)?;
+
+        let legacy_drawing = if has_comments { "<legacyDrawing r:id=\"rId2\"/>" } else { "" };
+        let sheet = format!(
+            "{}<worksheet xmlns=\"{}\" xmlns:r=\"{}\"><sheetViews><sheetView workbookViewId=\"0\"><pane ySplit=\"1\" topLeftCell=\"A2\" activePane=\"bottomLeft\" state=\"frozen\"/></sheetView></sheetViews><sheetData>{}</sheetData>{}</worksheet>",
+            XML_DECL, MAIN_NS, REL_NS, self.rows, legacy_drawing,
+        );
+        zip.add("xl/worksheets/sheet1.xml", &sheet)?;
+
+        if has_comments {
+            zip.add("xl/worksheets/_rels/sheet1.xml.rels", SHEET_RELS)?;
+            zip.add("xl/comments1.xml", &comments_xml(&self.comments))?;
+            zip.add("xl/drawings/vmlDrawing1.vml", &vml_drawing(&self.comments))?;
+        }
+        zip.finish()
+    }
+}
+
+/// Zero-based column index to its letters, e.g. 0 -> "A", 27 -> "AB".
+fn column_name(mut col: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (col % 26) as u8);
+        if col < 26 {
+            break;
+        }
+        col = col / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+/// Escapes markup. Control characters XML 1.0 can't carry are written as
+/// `_xHHHH_`, as Excel does, and an underscore that would read as the start
+/// of such an escape is itself escaped as `_x005F_`.
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(ch),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "_x{:04X}_", c as u32);
+            }
+            '_' if is_escape(&text[idx..]) => out.push_str("_x005F_"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Whether `text` starts with an `_xHHHH_` escape.
+fn is_escape(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 7 && bytes.starts_with(b"_x") && bytes[2..6].iter().all(u8::is_ascii_hexdigit) && bytes[6] == b'_'
+}
+
+fn content_types(has_comments: bool) -> String {
+    let mut xml = format!(
+        "{}<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+         <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+         <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+         <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+         <Override PartName=\"/xl/worksheets/sheet1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>\
+         <Override PartName=\"/xl/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml\"/>",
+        XML_DECL,
+    );
+    if has_comments {
+        xml.push_str(
+            "<Default Extension=\"vml\" ContentType=\"application/vnd.openxmlformats-officedocument.vmlDrawing\"/>\
+             <Override PartName=\"/xl/comments1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.comments+xml\"/>",
+        );
+    }
+    xml.push_str("</Types>");
+    xml
+}
+
+fn comments_xml(comments: &[(usize, usize, String)]) -> String {
+    let mut xml = format!("{}<comments xmlns=\"{}\"><authors><author>CSV Validator</author></authors><commentList>", XML_DECL, MAIN_NS);
+    for (col, row, text) in comments {
+        let _ = write!(xml, "<comment ref=\"{}{}\" authorId=\"0\"><text><t xml:space=\"preserve\">{}</t></text></comment>", column_name(*col), row, escape_xml(text));
+    }
+    xml.push_str("</commentList></comments>");
+    xml
+}
+
+/// The legacy VML shapes Excel needs to actually display the comments.
+fn vml_drawing(comments: &[(usize, usize, String)]) -> String {
+    // Each idmap block covers 1024 shape ids
+    let blocks: Vec<String> = (1..=comments.len() / 1024 + 1).map(|b| b.to_string()).collect();
+    let mut xml = format!(
+        "<xml xmlns:v=\"urn:schemas-microsoft-com:vml\" xmlns:o=\"urn:schemas-microsoft-com:office:office\" xmlns:x=\"urn:schemas-microsoft-com:office:excel\">\
+         <o:shapelayout v:ext=\"edit\"><o:idmap v:ext=\"edit\" data=\"{}\"/></o:shapelayout>\
+         <v:shapetype id=\"_x0000_t202\" coordsize=\"21600,21600\" o:spt=\"202\" path=\"m,l,21600r21600,l21600,xe\">\
+         <v:stroke joinstyle=\"miter\"/><v:path gradientshapeok=\"t\" o:connecttype=\"rect\"/></v:shapetype>",
+        blocks.join(","),
+    );
+    for (idx, &(col, row, _)) in comments.iter().enumerate() {
+        // VML anchors count rows from zero
+        let row = row - 1;
+        let _ = write!(
+            xml,
+            "<v:shape id=\"_x0000_s{}\" type=\"#_x0000_t202\" style=\"position:absolute;width:108pt;height:59.25pt;z-index:{};visibility:hidden\" fillcolor=\"#ffffe1\" o:insetmode=\"auto\">\
+             <v:fill color2=\"#ffffe1\"/><v:shadow on=\"t\" color=\"black\" obscured=\"t\"/><v:path o:connecttype=\"none\"/>\
+             <v:textbox style=\"mso-direction-alt:auto\"><div style=\"text-align:left\"></div></v:textbox>\
+             <x:ClientData ObjectType=\"Note\"><x:MoveWithCells/><x:SizeWithCells/>\
+             <x:Anchor>{}, 15, {}, 10, {}, 15, {}, 4</x:Anchor><x:AutoFill>False</x:AutoFill>\
+             <x:Row>{}</x:Row><x:Column>{}</x:Column></x:ClientData></v:shape>",
+            1025 + idx, idx + 1, col + 1, row, col + 3, row + 4, row, col,
+        );
+    }
+    xml.push_str("</xml>");
+    xml
+}
+
+const XML_DECL: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+const MAIN_NS: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+const REL_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+const ROOT_RELS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+<Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+</Relationships>";
+
+const WORKBOOK: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+<sheets><sheet name=\"Data\" sheetId=\"1\" r:id=\"rId1\"/></sheets></workbook>";
+
+const WORKBOOK_RELS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+<Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet1.xml\"/>\
+<Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" Target=\"styles.xml\"/>\
+</Relationships>";
+
+const SHEET_RELS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+<Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments\" Target=\"../comments1.xml\"/>\
+<Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/vmlDrawing\" Target=\"../drawings/vmlDrawing1.vml\"/>\
+</Relationships>";
+
+// cellXfs order must match `CellStyle`
+const STYLES: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<styleSheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+<fonts count=\"2\"><font><sz val=\"11\"/><name val=\"Calibri\"/></font><font><b/><sz val=\"11\"/><name val=\"Calibri\"/></font></fonts>\
+<fills count=\"3\"><fill><patternFill patternType=\"none\"/></fill><fill><patternFill patternType=\"gray125\"/></fill>\
+<fill><patternFill patternType=\"solid\"><fgColor rgb=\"FFFFC7CE\"/><bgColor indexed=\"64\"/></patternFill></fill></fills>\
+<borders count=\"1\"><border><left/><right/><top/><bottom/><diagonal/></border></borders>\
+<cellStyleXfs count=\"1\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\"/></cellStyleXfs>\
+<cellXfs count=\"3\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\"/>\
+<xf numFmtId=\"0\" fontId=\"1\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyFont=\"1\"/>\
+<xf numFmtId=\"0\" fontId=\"0\" fillId=\"2\" borderId=\"0\" xfId=\"0\" applyFill=\"1\"/></cellXfs>\
+<cellStyles count=\"1\"><cellStyle name=\"Normal\" xfId=\"0\" builtinId=\"0\"/></cellStyles>\
+</styleSheet>";
+
+// --- Zip container ---
+
+/// Minimal deflate-only zip writer (no zip64, so each part stays under 4 GiB).
+#[derive(Default)]
+struct ZipWriter {
+    out: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+// 1980-01-01 00:00 in DOS format
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, content: &str) -> Result<(), String> {
+        let mut crc = Crc::new();
+        crc.update(content.as_bytes());
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes()).map_err(|e| e.to_string())?;
+        let compressed = encoder.finish().map_err(|e| e.to_string())?;
+
+        let too_large = || "XLSX part exceeds the 4 GiB zip limit".to_string();
+        let offset = u32::try_from(self.out.len()).map_err(|_| too_large())?;
+        let size = u32::try_from(content.len()).map_err(|_| too_large())?;
+        let compressed_size = u32::try_from(compressed.len()).map_err(|_| too_large())?;
+
+        // Fields shared by the local and central headers, from "version needed" on
+        let mut common = Vec::with_capacity(26);
+        for field in [20u16, 0, 8, 0, DOS_DATE] {
+            common.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc.sum(), compressed_size, size] {
+            common.extend_from_slice(&field.to_le_bytes());
+        }
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        self.out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        self.out.extend_from_slice(&common);
+        self.out.extend_from_slice(name.as_bytes());
+        self.out.extend_from_slice(&compressed);
+
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes());
+        self.central.extend_from_slice(&common);
+        // comment length, disk number, internal and external attributes
+        self.central.extend_from_slice(&[0; 10]);
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, String> {
+        let too_large = || "XLSX file exceeds the 4 GiB zip limit".to_string();
+        let central_offset = u32::try_from(self.out.len()).map_err(|_| too_large())?;
+        let central_size = u32::try_from(self.central.len()).map_err(|_| too_large())?;
+
+        self.out.extend_from_slice(&self.central);
+        self.out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        for field in [0u16, 0, self.entries, self.entries] {
+            self.out.extend_from_slice(&field.to_le_bytes());
+        }
+        self.out.extend_from_slice(&central_size.to_le_bytes());
+        self.out.extend_from_slice(&central_offset.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes());
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::collections::HashMap;
+    use std::io::Read as _;
+
+    /// Reads back every part through the local file headers.
+    fn unzip(mut bytes: &[u8]) -> HashMap<String, String> {
+        let u16_at = |b: &[u8], at: usize| u16::from_le_bytes([b[at], b[at + 1]]) as usize;
+        let u32_at = |b: &[u8], at: usize| u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]]) as usize;
+        let mut parts = HashMap::new();
+        while u32_at(bytes, 0) == 0x0403_4b50 {
+            let (compressed_size, size, name_len) = (u32_at(bytes, 18), u32_at(bytes, 22), u16_at(bytes, 26));
+            let name = std::str::from_utf8(&bytes[30..30 + name_len]).unwrap().to_string();
+            let data = &bytes[30 + name_len..30 + name_len + compressed_size];
+            let mut content = String::new();
+            DeflateDecoder::new(data).read_to_string(&mut content).unwrap();
+            assert_eq!(content.len(), size, "{}", name);
+            let mut crc = Crc::new();
+            crc.update(content.as_bytes());
+            assert_eq!(crc.sum() as usize, u32_at(bytes, 14), "{}", name);
+            parts.insert(name, content);
+            bytes = &bytes[30 + name_len + compressed_size..];
+        }
+        assert_eq!(u32_at(bytes, 0), 0x0201_4b50);
+        parts
+    }
+
+    #[test]
+    fn control_characters_are_escaped_not_dropped() {
+        assert_eq!(escape_xml("a\u{b}b\u{1}c"), "a_x000B_b_x0001_c");
+        assert_eq!(escape_xml("<a & \"b\">\tc\n"), "&lt;a &amp; &quot;b&quot;&gt;\tc\n");
+        assert_eq!(escape_xml("_x000B_ snake_case _x12_"), "_x005F_x000B_ snake_case _x12_");
+    }
+
+    #[test]
+    fn workbook_parts_are_packaged() {
+        let mut sheet = SheetWriter::new();
+        sheet.write_row([("id", CellStyle::Header, None), ("name", CellStyle::Header, None)]).unwrap();
+        sheet.write_row([("1", CellStyle::Normal, None), ("A\u{b}&B", CellStyle::Highlight, Some("bad <name>".to_string()))]).unwrap();
+        let parts = unzip(&sheet.finish().unwrap());
+
+        let content_types = &parts["[Content_Types].xml"];
+        for part in ["/xl/workbook.xml", "/xl/worksheets/sheet1.xml", "/xl/styles.xml", "/xl/comments1.xml"] {
+            assert!(content_types.contains(&format!("PartName=\"{}\"", part)), "{}", part);
+        }
+        assert!(parts["xl/workbook.xml"].contains("<sheet name=\"Data\" sheetId=\"1\" r:id=\"rId1\"/>"));
+        assert!(parts["xl/_rels/workbook.xml.rels"].contains("Target=\"worksheets/sheet1.xml\""));
+
+        let sheet = &parts["xl/worksheets/sheet1.xml"];
+        assert!(sheet.contains("<row r=\"1\"><c r=\"A1\" s=\"1\" t=\"inlineStr\"><is><t>id</t></is></c><c r=\"B1\" s=\"1\" t=\"inlineStr\"><is><t>name</t></is></c></row>"));
+        assert!(sheet.contains("<c r=\"B2\" s=\"2\" t=\"inlineStr\"><is><t>A_x000B_&amp;B</t></is></c>"));
+        assert!(sheet.contains("<legacyDrawing r:id=\"rId2\"/>"));
+        assert!(parts["xl/comments1.xml"].contains("<comment ref=\"B2\" authorId=\"0\"><text><t xml:space=\"preserve\">bad &lt;name&gt;</t></text></comment>"));
+        assert!(parts.contains_key("xl/drawings/vmlDrawing1.vml"));
+    }
+
+    #[test]
+    fn column_names_roll_over_after_z() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(column_name(16_383), "XFD");
+    }
+}